@@ -0,0 +1 @@
+DROP TABLE IF EXISTS planets;
\ No newline at end of file
@@ -0,0 +1,15 @@
+CREATE TABLE planets (
+    id UUID NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE,
+    version INTEGER NOT NULL,
+    solar_system_id UUID NOT NULL REFERENCES solar_systems(id),
+    parent_planet_id UUID REFERENCES planets(id),
+    planet_type_id UUID NOT NULL,
+    name VARCHAR NOT NULL,
+    orbital_resonance REAL,
+    solar_energy_percent SMALLINT NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT positive_version CHECK (version >= 0),
+    UNIQUE (solar_system_id, name)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE planets DROP COLUMN IF EXISTS rotation_direction;
+DROP TYPE IF EXISTS rotation_direction;
\ No newline at end of file
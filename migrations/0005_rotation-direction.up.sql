@@ -0,0 +1,8 @@
+CREATE TYPE rotation_direction AS ENUM (
+    'normal',
+    'reverse',
+    'horizontal'
+);
+
+ALTER TABLE planets ADD COLUMN rotation_direction rotation_direction NOT NULL DEFAULT 'normal';
+ALTER TABLE planets ALTER COLUMN rotation_direction DROP DEFAULT;
@@ -0,0 +1,3 @@
+ALTER TABLE planets DROP CONSTRAINT IF EXISTS planets_planet_type_id_fkey;
+DROP TABLE IF EXISTS planet_types;
+DROP TYPE IF EXISTS ocean_type;
\ No newline at end of file
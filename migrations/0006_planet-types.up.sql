@@ -0,0 +1,22 @@
+CREATE TYPE ocean_type AS ENUM (
+    'water',
+    'lava',
+    'ice',
+    'sulfuric_acid'
+);
+
+CREATE TABLE planet_types (
+    id UUID NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE,
+    version INTEGER NOT NULL,
+    name VARCHAR NOT NULL,
+    ocean_type ocean_type,
+    wind_energy_percent SMALLINT NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT positive_version CHECK (version >= 0),
+    UNIQUE (name)
+);
+
+ALTER TABLE planets
+    ADD CONSTRAINT planets_planet_type_id_fkey FOREIGN KEY (planet_type_id) REFERENCES planet_types(id);
\ No newline at end of file
@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS item_recipe_outputs;
+DROP TABLE IF EXISTS item_recipe_inputs;
+DROP TABLE IF EXISTS item_recipes;
+DROP TABLE IF EXISTS items;
+DROP TYPE IF EXISTS item_sub_type;
+DROP TYPE IF EXISTS item_type;
\ No newline at end of file
@@ -0,0 +1,61 @@
+CREATE TYPE item_type AS ENUM (
+    'component',
+    'building'
+);
+
+CREATE TYPE item_sub_type AS ENUM (
+    'common_resource',
+    'rare_resource',
+    'miner',
+    'assembling_machine',
+    'chemical_plant',
+    'matrix_lab'
+);
+
+CREATE TABLE items (
+    id UUID NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE,
+    version INTEGER NOT NULL,
+    name VARCHAR NOT NULL,
+    item_type item_type NOT NULL,
+    item_sub_type item_sub_type,
+    stack_size SMALLINT NOT NULL,
+    production_multiplier REAL,
+    image_path VARCHAR NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT positive_version CHECK (version >= 0),
+    CONSTRAINT positive_stack_size CHECK (stack_size > 0),
+    UNIQUE (name)
+);
+
+CREATE TABLE item_recipes (
+    id UUID NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE,
+    version INTEGER NOT NULL,
+    name VARCHAR NOT NULL,
+    craft_time_secs REAL NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT positive_version CHECK (version >= 0),
+    CONSTRAINT positive_craft_time_secs CHECK (craft_time_secs > 0.0),
+    UNIQUE (name)
+);
+
+CREATE TABLE item_recipe_inputs (
+    recipe_id UUID NOT NULL REFERENCES item_recipes(id) ON DELETE CASCADE,
+    item_id UUID NOT NULL REFERENCES items(id),
+    amount SMALLINT NOT NULL,
+    extra_products BOOLEAN NOT NULL,
+    production_speedup BOOLEAN NOT NULL,
+    PRIMARY KEY (recipe_id, item_id),
+    CONSTRAINT positive_amount CHECK (amount > 0)
+);
+
+CREATE TABLE item_recipe_outputs (
+    recipe_id UUID NOT NULL REFERENCES item_recipes(id) ON DELETE CASCADE,
+    item_id UUID NOT NULL REFERENCES items(id),
+    amount SMALLINT NOT NULL,
+    PRIMARY KEY (recipe_id, item_id),
+    CONSTRAINT positive_amount CHECK (amount > 0)
+);
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE stars DROP CONSTRAINT positive_radius;
+ALTER TABLE stars ADD CONSTRAINT positive_radius CHECK (version > 0.0);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP INDEX solar_systems_search_idx;
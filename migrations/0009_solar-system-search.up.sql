@@ -0,0 +1,2 @@
+CREATE INDEX solar_systems_search_idx ON solar_systems
+    USING GIN (to_tsvector('simple', name || ' ' || coalesce(notes, '')));
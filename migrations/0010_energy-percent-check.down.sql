@@ -0,0 +1,2 @@
+ALTER TABLE planet_types DROP CONSTRAINT wind_energy_percent_range;
+ALTER TABLE planets DROP CONSTRAINT solar_energy_percent_range;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE planets ADD CONSTRAINT solar_energy_percent_range CHECK (solar_energy_percent BETWEEN 0 AND 100);
+ALTER TABLE planet_types ADD CONSTRAINT wind_energy_percent_range CHECK (wind_energy_percent BETWEEN 0 AND 100);
\ No newline at end of file
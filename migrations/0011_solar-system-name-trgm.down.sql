@@ -0,0 +1 @@
+DROP INDEX solar_systems_name_trgm_idx;
@@ -0,0 +1,2 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+CREATE INDEX solar_systems_name_trgm_idx ON solar_systems USING GIN (name gin_trgm_ops);
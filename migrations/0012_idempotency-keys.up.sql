@@ -0,0 +1,8 @@
+CREATE TABLE idempotency_keys (
+    endpoint VARCHAR NOT NULL,
+    key VARCHAR NOT NULL,
+    resource_id UUID NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY (endpoint, key)
+);
+CREATE INDEX idempotency_keys_created_at_idx ON idempotency_keys (created_at);
@@ -0,0 +1,3 @@
+ALTER TABLE planets DROP CONSTRAINT planets_name_length;
+ALTER TABLE solar_systems DROP CONSTRAINT solar_systems_name_length;
+ALTER TABLE saves DROP CONSTRAINT saves_name_length;
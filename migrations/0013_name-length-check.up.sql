@@ -0,0 +1,3 @@
+ALTER TABLE saves ADD CONSTRAINT saves_name_length CHECK (char_length(name) <= 120);
+ALTER TABLE solar_systems ADD CONSTRAINT solar_systems_name_length CHECK (char_length(name) <= 120);
+ALTER TABLE planets ADD CONSTRAINT planets_name_length CHECK (char_length(name) <= 120);
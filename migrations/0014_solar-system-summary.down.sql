@@ -0,0 +1,4 @@
+ALTER TABLE solar_systems
+    DROP COLUMN counts_refreshed_at,
+    DROP COLUMN cached_has_star,
+    DROP COLUMN cached_planet_count;
@@ -0,0 +1,4 @@
+ALTER TABLE solar_systems
+    ADD COLUMN cached_planet_count INTEGER,
+    ADD COLUMN cached_has_star BOOLEAN,
+    ADD COLUMN counts_refreshed_at TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,3 @@
+ALTER TABLE saves DROP CONSTRAINT saves_name_key;
+ALTER TABLE saves ADD CONSTRAINT saves_name_key UNIQUE (name);
+ALTER TABLE saves DROP COLUMN owner_id;
@@ -0,0 +1,3 @@
+ALTER TABLE saves ADD COLUMN owner_id UUID;
+ALTER TABLE saves DROP CONSTRAINT saves_name_key;
+ALTER TABLE saves ADD CONSTRAINT saves_name_key UNIQUE NULLS NOT DISTINCT (owner_id, name);
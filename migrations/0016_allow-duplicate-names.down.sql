@@ -0,0 +1,6 @@
+-- Fails if any save already holds duplicate solar system names; rename or
+-- delete them before rolling back.
+DROP INDEX solar_systems_save_id_name_key;
+ALTER TABLE solar_systems ADD CONSTRAINT solar_systems_save_id_name_key UNIQUE (save_id, name);
+ALTER TABLE solar_systems DROP COLUMN allow_duplicate_names;
+ALTER TABLE saves DROP COLUMN allow_duplicate_names;
@@ -0,0 +1,15 @@
+-- Saves can opt out of unique solar system names, e.g. for imported star catalogs
+-- that reuse designations.
+ALTER TABLE saves ADD COLUMN allow_duplicate_names BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- A partial unique index cannot reference another table, so each solar system
+-- carries a copy of its save's flag. The application sets it on insert and on
+-- moving a solar system between saves, and rewrites it whenever the save's flag
+-- changes.
+ALTER TABLE solar_systems ADD COLUMN allow_duplicate_names BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- The index keeps the old constraint name so existing error mapping still applies.
+-- Inserts relying on ON CONFLICT (save_id, name) must repeat the index predicate.
+ALTER TABLE solar_systems DROP CONSTRAINT solar_systems_save_id_name_key;
+CREATE UNIQUE INDEX solar_systems_save_id_name_key ON solar_systems (save_id, name)
+    WHERE NOT allow_duplicate_names;
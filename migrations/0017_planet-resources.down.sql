@@ -0,0 +1 @@
+DROP TABLE IF EXISTS planet_available_resources;
\ No newline at end of file
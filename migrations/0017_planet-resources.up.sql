@@ -0,0 +1,5 @@
+CREATE TABLE planet_available_resources (
+    planet_id UUID NOT NULL REFERENCES planets(id) ON DELETE CASCADE,
+    item_id UUID NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+    PRIMARY KEY (planet_id, item_id)
+);
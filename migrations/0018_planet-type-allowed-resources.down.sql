@@ -0,0 +1 @@
+DROP TABLE IF EXISTS planet_type_allowed_resources;
\ No newline at end of file
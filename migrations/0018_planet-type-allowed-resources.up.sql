@@ -0,0 +1,7 @@
+-- A NULL star_spectral_class means the resource can appear around any star.
+CREATE TABLE planet_type_allowed_resources (
+    planet_type_id UUID NOT NULL REFERENCES planet_types(id) ON DELETE CASCADE,
+    item_id UUID NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+    star_spectral_class spectral_class,
+    UNIQUE NULLS NOT DISTINCT (planet_type_id, item_id, star_spectral_class)
+);
@@ -0,0 +1,2 @@
+DROP INDEX item_recipe_outputs_item_id_idx;
+DROP INDEX item_recipe_inputs_item_id_idx;
\ No newline at end of file
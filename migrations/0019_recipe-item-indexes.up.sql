@@ -0,0 +1,3 @@
+-- The primary keys lead with recipe_id, so looking up recipes by item needs its own index.
+CREATE INDEX item_recipe_inputs_item_id_idx ON item_recipe_inputs (item_id);
+CREATE INDEX item_recipe_outputs_item_id_idx ON item_recipe_outputs (item_id);
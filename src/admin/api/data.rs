@@ -0,0 +1,25 @@
+use crate::admin::domain;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecountResponse {
+    pub save_id: Uuid,
+    pub solar_system_count: u64,
+    pub star_count: u64,
+    pub planet_count: u64,
+    pub recounted_at: DateTime<Utc>,
+}
+
+impl From<domain::RecountSummary> for RecountResponse {
+    fn from(value: domain::RecountSummary) -> Self {
+        Self {
+            save_id: value.save_id,
+            solar_system_count: value.solar_system_count,
+            star_count: value.star_count,
+            planet_count: value.planet_count,
+            recounted_at: value.recounted_at,
+        }
+    }
+}
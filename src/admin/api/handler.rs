@@ -0,0 +1,31 @@
+use super::RecountResponse;
+use crate::admin::domain;
+use crate::{
+    auth::AdminAuth,
+    error::{log_entity_error, ObjectKind, Result},
+    AppState,
+};
+use actix_web::{post, web, HttpResponse};
+use uuid::Uuid;
+
+#[post("/saves/{id}/recount")]
+async fn recount_handler(
+    _auth: AdminAuth,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut transaction = data.db.begin().await?;
+    let id = path.into_inner();
+
+    let response = domain::recount(&mut transaction, id)
+        .await
+        .map(RecountResponse::from)
+        .inspect_err(log_entity_error(
+            "Failed to recount save",
+            ObjectKind::Save,
+            id,
+        ))?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
@@ -0,0 +1,11 @@
+mod data;
+mod handler;
+
+use actix_web::web;
+pub use data::*;
+
+pub const ADMIN_PREFIX: &str = "/admin";
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope(ADMIN_PREFIX).service(handler::recount_handler));
+}
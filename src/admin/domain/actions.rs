@@ -0,0 +1,83 @@
+use super::RecountSummary;
+use crate::{
+    data::is_serialization_failure,
+    error::{ObjectKind, Result, TrackerError},
+    field::FieldValue,
+    game_save::{self, GameSaveColumns},
+    planet::PlanetColumns,
+    solar_system::SolarSystemColumns,
+    star::domain::StarColumns,
+};
+use chrono::Utc;
+use sea_query::{Expr, Func, PostgresQueryBuilder, Query, SimpleExpr};
+use sea_query_binder::SqlxBinder;
+use sqlx::{Postgres, Row, Transaction};
+use uuid::Uuid;
+
+/// Recomputes the cached planet count and star presence of every solar system
+/// in the save. Neither `version` nor `updated_at` are touched as the cached
+/// columns are derived data rather than user edits.
+pub async fn recount<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+) -> Result<RecountSummary> {
+    game_save::lookup(tx, save_id).await?;
+
+    let planet_count = Query::select()
+        .expr(Func::count(Expr::col((
+            PlanetColumns::Table,
+            PlanetColumns::Id,
+        ))))
+        .from(PlanetColumns::Table)
+        .and_where(
+            Expr::col((PlanetColumns::Table, PlanetColumns::SolarSystemId))
+                .equals((SolarSystemColumns::Table, SolarSystemColumns::Id)),
+        )
+        .to_owned();
+    let star = Query::select()
+        .expr(Expr::val(1))
+        .from(StarColumns::Table)
+        .and_where(
+            Expr::col((StarColumns::Table, StarColumns::SolarSystemId))
+                .equals((SolarSystemColumns::Table, SolarSystemColumns::Id)),
+        )
+        .to_owned();
+
+    let recounted_at = Utc::now();
+    let (sql, values) = Query::update()
+        .table(SolarSystemColumns::Table)
+        .value(
+            SolarSystemColumns::CachedPlanetCount,
+            SimpleExpr::SubQuery(None, Box::new(planet_count.into_sub_query_statement())),
+        )
+        .value(SolarSystemColumns::CachedHasStar, Expr::exists(star))
+        .value(SolarSystemColumns::CountsRefreshedAt, recounted_at)
+        .and_where(Expr::col(SolarSystemColumns::SaveId).eq(save_id))
+        .returning(Query::returning().columns([
+            SolarSystemColumns::CachedPlanetCount,
+            SolarSystemColumns::CachedHasStar,
+        ]))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows = sqlx::query_with(&sql, values.clone())
+        .fetch_all(&mut **tx)
+        .await
+        .map_err(|err| {
+            if is_serialization_failure(&err) {
+                TrackerError::concurrent_update(
+                    ObjectKind::Save,
+                    FieldValue::new(GameSaveColumns::Id, save_id),
+                )
+            } else {
+                err.into()
+            }
+        })?;
+
+    Ok(RecountSummary {
+        save_id,
+        solar_system_count: rows.len() as u64,
+        star_count: rows.iter().filter(|row| row.get::<bool, _>(1)).count() as u64,
+        planet_count: rows.iter().map(|row| row.get::<i32, _>(0) as u64).sum(),
+        recounted_at,
+    })
+}
@@ -0,0 +1,11 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct RecountSummary {
+    pub save_id: Uuid,
+    pub solar_system_count: u64,
+    pub star_count: u64,
+    pub planet_count: u64,
+    pub recounted_at: DateTime<Utc>,
+}
@@ -0,0 +1,4 @@
+pub mod api;
+mod domain;
+
+pub use api::config;
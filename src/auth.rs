@@ -0,0 +1,151 @@
+use crate::{error::TrackerError, AppState};
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{Payload, Service, ServiceRequest, ServiceResponse},
+    http::header,
+    web, Error, FromRequest, HttpMessage, HttpRequest,
+};
+use std::future::{ready, Future, Ready};
+use uuid::Uuid;
+
+/// A bearer token accepted on `/api/1`. Tokens without an owner are unscoped
+/// and can access every save.
+#[derive(Debug, Clone)]
+pub struct ApiToken {
+    pub token: String,
+    pub owner_id: Option<Uuid>,
+}
+
+/// The owner the request is scoped to, taken from the token it authenticated
+/// with. Saves of other owners are reported as not found.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Owner(Option<Uuid>);
+
+impl Owner {
+    pub fn id(&self) -> Option<Uuid> {
+        self.0
+    }
+}
+
+impl FromRequest for Owner {
+    type Error = TrackerError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Ok(req
+            .extensions()
+            .get::<Owner>()
+            .copied()
+            .unwrap_or_default()))
+    }
+}
+
+/// Extractor guarding the admin endpoints with the static `ADMIN_TOKEN`.
+/// When no token is configured every request is forbidden.
+#[derive(Debug, Copy, Clone)]
+pub struct AdminAuth;
+
+impl FromRequest for AdminAuth {
+    type Error = TrackerError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let expected = req
+            .app_data::<web::Data<AppState>>()
+            .and_then(|data| data.admin_token.as_deref());
+        ready(check_bearer_token(req, expected).map(|_| AdminAuth))
+    }
+}
+
+/// Middleware requiring one of the configured API tokens on every request and
+/// recording its [`Owner`]. Without any configured token all requests are let
+/// through unscoped.
+pub fn require_api_token<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<EitherBody<B>>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let tokens = req
+        .app_data::<web::Data<AppState>>()
+        .map_or(&[][..], |data| data.api_tokens.as_slice());
+    let owner = if tokens.is_empty() {
+        Some(Owner::default())
+    } else {
+        bearer_token(req.request()).and_then(|provided| {
+            tokens
+                .iter()
+                .find(|t| constant_time_eq(t.token.as_bytes(), provided.as_bytes()))
+                .map(|t| Owner(t.owner_id))
+        })
+    };
+
+    let result = match owner {
+        Some(owner) => {
+            req.extensions_mut().insert(owner);
+            Ok(srv.call(req))
+        }
+        None => Err(req.error_response(TrackerError::Unauthorized)),
+    };
+    async move {
+        match result {
+            Ok(fut) => fut.await.map(ServiceResponse::map_into_left_body),
+            Err(res) => Ok(res.map_into_right_body()),
+        }
+    }
+}
+
+pub fn check_bearer_token(req: &HttpRequest, expected: Option<&str>) -> Result<(), TrackerError> {
+    let provided = bearer_token(req).ok_or(TrackerError::Unauthorized)?;
+    match expected {
+        Some(expected) if constant_time_eq(expected.as_bytes(), provided.as_bytes()) => Ok(()),
+        _ => Err(TrackerError::Forbidden),
+    }
+}
+
+fn bearer_token(req: &HttpRequest) -> Option<&str> {
+    let value = req.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApiToken;
+    use crate::test_support::{app_state, db_pool, send, test_app};
+    use actix_web::{http::StatusCode, test};
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn api_requests_require_a_configured_token() {
+        let mut state = app_state(db_pool().await);
+        state.api_tokens = vec![ApiToken {
+            token: "s3cret".to_owned(),
+            owner_id: None,
+        }];
+        let app = test::init_service(test_app(state)).await;
+        let list_saves = |authorization: Option<&str>| {
+            let mut req = test::TestRequest::get().uri("/api/1/saves?size=1");
+            if let Some(authorization) = authorization {
+                req = req.insert_header(("Authorization", authorization.to_owned()));
+            }
+            req.to_request()
+        };
+
+        let (status, body) = send(&app, list_saves(Some("Bearer s3cret"))).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+
+        for authorization in [None, Some("Bearer wrong"), Some("Basic s3cret")] {
+            let (status, body) = send(&app, list_saves(authorization)).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{authorization:?}");
+            assert_eq!(body["error_code"], "Unauthorized", "{authorization:?}");
+        }
+    }
+}
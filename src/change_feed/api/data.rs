@@ -0,0 +1,75 @@
+use crate::{
+    change_feed::{domain, ChangeKind},
+    data::{PageRequest, PageRequestRaw},
+    error::TrackerError,
+    field::Field,
+    field_names,
+    utils::parse_datetime,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeOperation {
+    Created,
+    Updated,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub op: ChangeOperation,
+    pub id: Uuid,
+    pub version: i32,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangesRequestRaw {
+    #[serde(flatten)]
+    pub page_request: PageRequestRaw,
+    pub since: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChangesRequest {
+    pub page_request: PageRequest<ChangeFields>,
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl From<domain::Change> for Change {
+    fn from(value: domain::Change) -> Self {
+        Self {
+            kind: value.kind,
+            op: match value.updated_at {
+                Some(_) => ChangeOperation::Updated,
+                None => ChangeOperation::Created,
+            },
+            id: value.id,
+            version: value.version,
+            changed_at: value.changed_at,
+        }
+    }
+}
+
+impl TryFrom<ChangesRequestRaw> for ChangesRequest {
+    type Error = TrackerError;
+
+    fn try_from(value: ChangesRequestRaw) -> Result<Self, Self::Error> {
+        Ok(Self {
+            page_request: PageRequest::try_from(value.page_request)?,
+            since: parse_datetime("since", value.since)?,
+        })
+    }
+}
+
+field_names!(
+    ChangeFields<domain::ChangeColumns> {
+        Id => { value: "id", kind: Uuid },
+        #[default]
+        ChangedAt => { value: "changed_at", kind: DateTime }
+    }
+);
@@ -0,0 +1,50 @@
+use super::{Change, ChangesRequest, ChangesRequestRaw};
+use crate::change_feed::domain;
+use crate::{
+    auth::Owner,
+    data::{Page, PageRequestRaw},
+    error::{log_entity_error, ObjectKind, Result},
+    game_save, AppState,
+};
+use actix_web::{get, web};
+use uuid::Uuid;
+
+#[utoipa::path(
+    operation_id = "listSaveChanges",
+    tag = "saves",
+    params(
+        ("saveId" = Uuid, Path, description = "The save id"),
+        PageRequestRaw,
+        ("since" = Option<String>, Query, description = "Only include changes made after this ISO-8601 timestamp"),
+    ),
+    responses(
+        (status = 200, description = "A page of solar system and star changes, oldest first", body = ChangePage),
+        (status = 400, description = "The search parameters were invalid", body = ErrorResponse),
+        (status = 404, description = "The save does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/saves/{saveId}/changes")]
+async fn changes_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    query: web::Query<ChangesRequestRaw>,
+    data: web::Data<AppState>,
+) -> Result<Page<Change>> {
+    let mut transaction = data.db.begin().await?;
+    let save_id = path.into_inner();
+    game_save::check_owner(&mut transaction, owner, save_id).await?;
+    let mut search_raw = query.into_inner();
+    search_raw.page_request.paging = data.paging;
+    let search_params = ChangesRequest::try_from(search_raw)?;
+
+    let response = domain::search(&mut transaction, save_id, &search_params)
+        .await
+        .map(Page::convert)
+        .inspect_err(log_entity_error(
+            "Failed to list changes for save",
+            ObjectKind::Save,
+            save_id,
+        ))?;
+    transaction.commit().await?;
+    Ok(response)
+}
@@ -0,0 +1,18 @@
+mod data;
+mod handler;
+
+use crate::change_feed::ChangeKind;
+use actix_web::web;
+pub use data::*;
+use utoipa::OpenApi;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(handler::changes_handler);
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(handler::changes_handler),
+    components(schemas(Change, ChangeKind, ChangeOperation, crate::data::ChangePage))
+)]
+pub struct ApiDoc;
@@ -0,0 +1,143 @@
+use super::{Change, ChangeColumns};
+use crate::{
+    change_feed::{
+        api::{ChangeFields, ChangesRequest},
+        ChangeKind,
+    },
+    data::{add_sorts, paginate, Cursor, Page, Sort},
+    error::Result,
+    game_save,
+    solar_system::SolarSystemColumns,
+    star::domain::StarColumns,
+};
+use sea_query::{Asterisk, Expr, Func, Iden, Query, SelectStatement, SimpleExpr, UnionType};
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+pub async fn search<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+    search_params: &ChangesRequest,
+) -> Result<Page<Change>> {
+    game_save::lookup(tx, save_id).await?;
+
+    let page_req = &search_params.page_request;
+    let mut select_count_stmt = Query::select()
+        .expr(Func::count(Expr::col(Asterisk)))
+        .from_subquery(changes(save_id), ChangeColumns::Table)
+        .to_owned();
+    add_where_clause(&mut select_count_stmt, search_params);
+
+    let mut select_stmt = Query::select()
+        .column(Asterisk)
+        .from_subquery(changes(save_id), ChangeColumns::Table)
+        .to_owned();
+    add_where_clause(&mut select_stmt, search_params);
+    if let Some(cursor_predicate) =
+        page_req.cursor_predicate((ChangeColumns::Table, ChangeColumns::Id))?
+    {
+        select_stmt.and_where(cursor_predicate);
+    }
+    add_sorts(
+        &mut select_stmt,
+        &page_req.sorts,
+        (ChangeColumns::Table, ChangeColumns::Id),
+    );
+
+    let mut page = paginate(tx, select_count_stmt, select_stmt, page_req).await?;
+    let next_cursor = page_req.next_cursor(&page.data, cursor_for);
+    page.metadata = page.metadata.with_next_cursor(next_cursor);
+    Ok(page)
+}
+
+/// Solar systems and stars of the save as a single `UNION ALL`, so both can be
+/// filtered and ordered by their modification time together.
+fn changes(save_id: Uuid) -> SelectStatement {
+    let stars = Query::select()
+        .expr_as(Expr::val(ChangeKind::Star.as_ref()), ChangeColumns::Kind)
+        .expr_as(
+            Expr::col((StarColumns::Table, StarColumns::Id)),
+            ChangeColumns::Id,
+        )
+        .expr_as(
+            Expr::col((StarColumns::Table, StarColumns::Version)),
+            ChangeColumns::Version,
+        )
+        .expr_as(
+            Expr::col((StarColumns::Table, StarColumns::UpdatedAt)),
+            ChangeColumns::UpdatedAt,
+        )
+        .expr_as(
+            last_modified(
+                StarColumns::Table,
+                StarColumns::CreatedAt,
+                StarColumns::UpdatedAt,
+            ),
+            ChangeColumns::ChangedAt,
+        )
+        .from(StarColumns::Table)
+        .inner_join(
+            SolarSystemColumns::Table,
+            Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Id))
+                .equals((StarColumns::Table, StarColumns::SolarSystemId)),
+        )
+        .and_where(Expr::col((SolarSystemColumns::Table, SolarSystemColumns::SaveId)).eq(save_id))
+        .to_owned();
+
+    Query::select()
+        .expr_as(
+            Expr::val(ChangeKind::SolarSystem.as_ref()),
+            ChangeColumns::Kind,
+        )
+        .expr_as(
+            Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Id)),
+            ChangeColumns::Id,
+        )
+        .expr_as(
+            Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Version)),
+            ChangeColumns::Version,
+        )
+        .expr_as(
+            Expr::col((SolarSystemColumns::Table, SolarSystemColumns::UpdatedAt)),
+            ChangeColumns::UpdatedAt,
+        )
+        .expr_as(
+            last_modified(
+                SolarSystemColumns::Table,
+                SolarSystemColumns::CreatedAt,
+                SolarSystemColumns::UpdatedAt,
+            ),
+            ChangeColumns::ChangedAt,
+        )
+        .from(SolarSystemColumns::Table)
+        .and_where(Expr::col((SolarSystemColumns::Table, SolarSystemColumns::SaveId)).eq(save_id))
+        .union(UnionType::All, stars)
+        .to_owned()
+}
+
+fn last_modified<T, C>(table: T, created_at: C, updated_at: C) -> SimpleExpr
+where
+    T: Iden + Copy + 'static,
+    C: Iden + 'static,
+{
+    Func::coalesce([
+        Expr::col((table, updated_at)).into(),
+        Expr::col((table, created_at)).into(),
+    ])
+    .into()
+}
+
+fn add_where_clause(select_stmt: &mut SelectStatement, search_params: &ChangesRequest) {
+    if let Some(since) = search_params.since {
+        select_stmt
+            .and_where(Expr::col((ChangeColumns::Table, ChangeColumns::ChangedAt)).gt(since));
+    }
+}
+
+fn cursor_for(change: &Change, sort: &Sort<ChangeFields>) -> Cursor {
+    let key = match sort.field {
+        ChangeFields::Id => change.id.to_string(),
+        ChangeFields::ChangedAt => change.changed_at.to_rfc3339(),
+    };
+    Cursor::new(sort, Some(key), change.id)
+}
@@ -0,0 +1,31 @@
+use crate::change_feed::ChangeKind;
+use chrono::{DateTime, Utc};
+use sea_query::Iden;
+use uuid::Uuid;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub id: Uuid,
+    pub version: i32,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Copy, Clone, Iden)]
+#[allow(dead_code)]
+pub enum ChangeColumns {
+    #[iden(rename = "changes")]
+    Table,
+    Kind,
+    Id,
+    Version,
+    UpdatedAt,
+    ChangedAt,
+}
+
+impl From<ChangeColumns> for String {
+    fn from(value: ChangeColumns) -> Self {
+        value.to_string()
+    }
+}
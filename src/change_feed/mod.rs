@@ -0,0 +1,17 @@
+pub mod api;
+mod domain;
+
+pub use api::config;
+
+use serde::{Deserialize, Serialize};
+use strum::AsRefStr;
+use utoipa::ToSchema;
+
+#[derive(Debug, Copy, Clone, sqlx::Type, AsRefStr, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    SolarSystem,
+    Star,
+}
@@ -1,39 +1,149 @@
 use crate::{
+    change_feed::api::Change,
     error::TrackerError,
-    field::{AllowedValues, Bound, Field, FieldValue},
+    field::{AllowedValues, Bound, Field, FieldType, FieldValue},
+    game_save::api::GameSave,
+    item::api::Item,
+    item_recipe::api::ItemRecipe,
+    planet::api::Planet,
+    planet_type::api::PlanetType,
+    solar_system::api::{SolarSystem, SolarSystemSearchResult},
+    star::api::Star,
+    utils::{comma_separated, escape_like},
+    AppState,
 };
-use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
-use sea_query::Order;
+use actix_web::{
+    body::BoxBody,
+    error::JsonPayloadError,
+    http::header::{self, Header},
+    web, HttpRequest, HttpResponse, Responder, ResponseError,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use sea_query::{
+    extension::postgres::PgBinOper, Alias, Expr, Iden, IntoColumnRef, NullOrdering, Order,
+    PostgresQueryBuilder, Query, SelectStatement, SimpleExpr,
+};
+use sea_query_binder::SqlxBinder;
 use serde::{Deserialize, Serialize};
+use sqlx::{
+    postgres::{PgPool, PgRow},
+    FromRow, Postgres, Row, Transaction,
+};
 use std::str::FromStr;
 use strum::{AsRefStr, EnumIter, EnumString, IntoEnumIterator};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
 
 pub const FIRST_PAGE: u64 = 1;
 pub const MAX_PAGE_SIZE: u64 = 500;
 pub const DEFAULT_PAGE_SIZE: u64 = 100;
+const SERIALIZATION_FAILURE: &str = "40001";
+const CSV_CONTENT_TYPE: &str = "text/csv";
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[aliases(
+    GameSavePage = Page<GameSave>,
+    SolarSystemPage = Page<SolarSystem>,
+    SolarSystemSearchResultPage = Page<SolarSystemSearchResult>,
+    StarPage = Page<Star>,
+    PlanetPage = Page<Planet>,
+    PlanetTypePage = Page<PlanetType>,
+    ItemPage = Page<Item>,
+    ItemRecipePage = Page<ItemRecipe>,
+    ChangePage = Page<Change>
+)]
 pub struct Page<T> {
     pub data: Vec<T>,
     pub metadata: PageMetadata,
 }
 
-#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 pub struct PageMetadata {
     pub total_results: u64,
-    pub total_pages: u64,
+    /// Left out when `total_results` is estimated, as it would be just as inaccurate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<u64>,
     pub current_page: u64,
     pub next_page: Option<u64>,
     pub prev_page: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// Whether `total_results` is the planner's estimate rather than an exact count
+    #[serde(default)]
+    pub count_estimated: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Page sizes used when a request omits `size` or asks for more than allowed.
+/// Defaults to `DEFAULT_PAGE_SIZE` and `MAX_PAGE_SIZE`, overridable at startup.
+#[derive(Debug, Clone, Copy)]
+pub struct PagingConfig {
+    pub default_size: u64,
+    pub max_size: u64,
+}
+
+impl Default for PagingConfig {
+    fn default() -> Self {
+        PagingConfig {
+            default_size: DEFAULT_PAGE_SIZE,
+            max_size: MAX_PAGE_SIZE,
+        }
+    }
+}
+
+/// Flattened into each endpoint's query struct. `deny_unknown_fields` does not
+/// work together with `#[serde(flatten)]`, so unknown query parameters are
+/// ignored rather than rejected like unknown JSON fields.
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PageRequestRaw {
+    #[param(value_type = Option<u64>, minimum = 1)]
     pub page: Option<String>,
+    #[param(value_type = Option<u64>, minimum = 0, maximum = 500)]
     pub size: Option<String>,
+    /// Comma separated list of `field[:asc|desc[:nulls_first|nulls_last]]`.
+    #[serde(default, deserialize_with = "comma_separated")]
+    #[param(value_type = Option<String>)]
     pub sorts: Vec<String>,
+    #[serde(skip)]
+    pub filters: Vec<String>,
+    /// Set by the handler from `AppState`, like `filters`.
+    #[serde(skip)]
+    pub paging: PagingConfig,
+    /// The `next_cursor` of the previous page, for endpoints that return one.
+    /// It pages by the single sort it was returned for.
+    pub cursor: Option<String>,
+    #[param(value_type = Option<bool>)]
+    pub strict_paging: Option<String>,
+    /// `exact` (default) or `estimate`, which trades accuracy of `total_results` for speed.
+    #[param(value_type = Option<String>)]
+    pub count: Option<String>,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct IncludeRequestRaw {
+    /// Comma separated list of additional fields to include.
+    #[serde(default, deserialize_with = "comma_separated")]
+    #[param(value_type = Option<String>)]
+    pub include: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Includes<T>(Vec<T>);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FieldsRequestRaw {
+    /// Comma separated list of fields to return. The `id` is always returned.
+    #[serde(default, deserialize_with = "comma_separated")]
+    #[param(value_type = Option<String>)]
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SparseFields<T: Field>(Vec<T>);
+
 #[derive(Debug, Copy, Clone, Default, AsRefStr, EnumIter, EnumString)]
 #[strum(ascii_case_insensitive, serialize_all = "lowercase")]
 pub enum SortDirection {
@@ -42,17 +152,115 @@ pub enum SortDirection {
     Desc,
 }
 
+#[derive(Debug, Copy, Clone, AsRefStr, EnumIter, EnumString)]
+#[strum(ascii_case_insensitive, serialize_all = "snake_case")]
+pub enum NullsOrder {
+    NullsFirst,
+    NullsLast,
+}
+
+#[derive(Debug, Copy, Clone, Default, AsRefStr, EnumIter, EnumString)]
+#[strum(ascii_case_insensitive, serialize_all = "lowercase")]
+pub enum MatchMode {
+    Start,
+    #[default]
+    Prefix,
+    Contains,
+    Exact,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, AsRefStr, EnumIter, EnumString)]
+#[strum(ascii_case_insensitive, serialize_all = "lowercase")]
+pub enum CountMode {
+    #[default]
+    Exact,
+    Estimate,
+}
+
+#[derive(Debug, Copy, Clone, AsRefStr, EnumIter, EnumString)]
+#[strum(ascii_case_insensitive, serialize_all = "lowercase")]
+pub enum FilterOperator {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cursor {
+    /// The sort the cursor was returned for, as [`Sort::spec`] writes it
+    pub sort: String,
+    /// The sort key of the last row, `None` when that row's key is null
+    pub key: Option<String>,
+    pub id: Uuid,
+}
+
+#[derive(Debug, Clone)]
+pub struct Filter<T: Field> {
+    pub field: T,
+    pub operator: FilterOperator,
+    pub value: SimpleExpr,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Sort<T: Field> {
     pub field: T,
     pub direction: SortDirection,
+    pub nulls: Option<NullsOrder>,
+}
+
+impl<T: Field> Sort<T> {
+    pub fn asc(field: T) -> Self {
+        Self {
+            field,
+            direction: SortDirection::Asc,
+            nulls: None,
+        }
+    }
+
+    pub fn apply(&self, select_stmt: &mut SelectStatement) {
+        match self.nulls {
+            Some(nulls) => select_stmt.order_by_with_nulls(
+                self.field.column(),
+                self.direction.into(),
+                nulls.into(),
+            ),
+            None => select_stmt.order_by(self.field.column(), self.direction.into()),
+        };
+    }
+
+    /// The sort as written in `sorts`, with the direction spelled out.
+    pub fn spec(&self) -> String {
+        let mut spec = format!("{0}:{1}", self.field.name(), self.direction.as_ref());
+        if let Some(nulls) = self.nulls {
+            spec = format!("{spec}:{0}", nulls.as_ref());
+        }
+        spec
+    }
+
+    /// Whether nulls sort after every value; without an explicit order
+    /// Postgres treats null as larger than any value.
+    pub fn nulls_last(&self) -> bool {
+        match self.nulls {
+            Some(NullsOrder::NullsFirst) => false,
+            Some(NullsOrder::NullsLast) => true,
+            None => matches!(self.direction, SortDirection::Asc),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct PageRequest<T: Field> {
     pub page: u64,
     pub size: u64,
+    /// The configured default size, used to count pages when `size` is 0
+    pub default_size: u64,
     pub sorts: Vec<Sort<T>>,
+    pub filters: Vec<Filter<T>>,
+    pub cursor: Option<Cursor>,
+    pub count: CountMode,
 }
 
 impl From<SortDirection> for Order {
@@ -64,25 +272,247 @@ impl From<SortDirection> for Order {
     }
 }
 
+impl From<NullsOrder> for NullOrdering {
+    fn from(value: NullsOrder) -> Self {
+        match value {
+            NullsOrder::NullsFirst => NullOrdering::First,
+            NullsOrder::NullsLast => NullOrdering::Last,
+        }
+    }
+}
+
 impl From<SortDirection> for String {
     fn from(value: SortDirection) -> Self {
         value.as_ref().to_owned()
     }
 }
 
+impl TryFrom<Option<String>> for MatchMode {
+    type Error = TrackerError;
+
+    fn try_from(value: Option<String>) -> Result<Self, Self::Error> {
+        value
+            .map(|mode| {
+                MatchMode::from_str(&mode).map_err(|_| {
+                    TrackerError::invalid_field(
+                        FieldValue::new("match_mode", mode),
+                        AllowedValues::described_choice(
+                            MatchMode::iter().map(|m| (m, m.description())),
+                        ),
+                    )
+                })
+            })
+            .transpose()
+            .map(Option::unwrap_or_default)
+    }
+}
+
+impl TryFrom<Option<String>> for CountMode {
+    type Error = TrackerError;
+
+    fn try_from(value: Option<String>) -> Result<Self, Self::Error> {
+        value
+            .map(|mode| {
+                CountMode::from_str(&mode).map_err(|_| {
+                    TrackerError::invalid_field(
+                        FieldValue::new("count", mode),
+                        AllowedValues::described_choice(
+                            CountMode::iter().map(|m| (m, m.description())),
+                        ),
+                    )
+                })
+            })
+            .transpose()
+            .map(Option::unwrap_or_default)
+    }
+}
+
+impl CountMode {
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Exact => "count every matching result",
+            Self::Estimate => {
+                "use the query planner's row estimate, which is faster on large saves"
+            }
+        }
+    }
+}
+
+impl MatchMode {
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Start => "case-insensitive match at the start of the value",
+            Self::Prefix => "case-insensitive match at the start of any word in the value",
+            Self::Contains => "case-insensitive match anywhere in the value",
+            Self::Exact => "case-sensitive match of the entire value",
+        }
+    }
+
+    pub fn predicate<C: IntoColumnRef>(self, column: C, value: &str) -> SimpleExpr {
+        match self {
+            Self::Start => Expr::col(column).binary(
+                PgBinOper::RegexCaseInsensitive,
+                format!("^{0}", regex::escape(value)),
+            ),
+            Self::Prefix => Expr::col(column).binary(
+                PgBinOper::RegexCaseInsensitive,
+                format!("(^|\\s+){0}", regex::escape(value)),
+            ),
+            Self::Contains => {
+                Expr::col(column).binary(PgBinOper::ILike, format!("%{0}%", escape_like(value)))
+            }
+            Self::Exact => Expr::col(column).eq(value),
+        }
+    }
+}
+
+impl<T> Default for Includes<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T: Copy + PartialEq> Includes<T> {
+    pub fn contains(&self, include: T) -> bool {
+        self.0.contains(&include)
+    }
+}
+
+impl<T: AsRef<str>> Includes<T> {
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|include| include.as_ref())
+    }
+}
+
+impl<T: Field> Default for SparseFields<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T: Field> SparseFields<T> {
+    fn selectable() -> impl Iterator<Item = T> {
+        T::values().filter(|field| !field.name().contains('.'))
+    }
+
+    pub fn select_columns<C: IntoColumnRef>(&self, select_stmt: &mut SelectStatement, all: C) {
+        if self.0.is_empty() {
+            select_stmt.column(all);
+        } else {
+            select_stmt.columns(self.0.iter().map(|field| field.column()));
+        }
+    }
+
+    pub fn project<V: Serialize>(
+        &self,
+        value: V,
+        includes: impl Iterator<Item = impl AsRef<str>>,
+    ) -> Result<serde_json::Value, TrackerError> {
+        let value = serde_json::to_value(value).map_err(JsonPayloadError::Serialize)?;
+        match value {
+            serde_json::Value::Object(mut map) if !self.0.is_empty() => {
+                let mut names: Vec<String> = self.0.iter().map(|field| field.name()).collect();
+                names.extend(includes.map(|include| include.as_ref().to_owned()));
+                map.retain(|key, _| names.contains(key));
+                Ok(serde_json::Value::Object(map))
+            }
+            value => Ok(value),
+        }
+    }
+}
+
+impl<T: Field> TryFrom<FieldsRequestRaw> for SparseFields<T> {
+    type Error = TrackerError;
+
+    fn try_from(value: FieldsRequestRaw) -> Result<Self, Self::Error> {
+        if value.fields.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let mut fields: Vec<T> = Self::selectable()
+            .filter(|field| field.name() == "id")
+            .collect();
+        for name in value.fields {
+            let field = Self::selectable()
+                .find(|field| field.name() == name)
+                .ok_or_else(|| {
+                    TrackerError::invalid_field(
+                        FieldValue::new("fields", name.as_str()),
+                        AllowedValues::choice(Self::selectable().map(|field| field.name())),
+                    )
+                })?;
+
+            if !fields.iter().any(|f| f.name() == name) {
+                fields.push(field);
+            }
+        }
+        Ok(Self(fields))
+    }
+}
+
+impl<T> TryFrom<IncludeRequestRaw> for Includes<T>
+where
+    T: Copy + PartialEq + FromStr + AsRef<str> + IntoEnumIterator,
+{
+    type Error = TrackerError;
+
+    fn try_from(value: IncludeRequestRaw) -> Result<Self, Self::Error> {
+        let mut includes = Vec::with_capacity(value.include.len());
+        for include in value.include {
+            let include = T::from_str(&include).map_err(|_| {
+                TrackerError::invalid_field(
+                    FieldValue::new("include", include),
+                    AllowedValues::choice(T::iter().map(|v| v.as_ref().to_owned())),
+                )
+            })?;
+
+            if !includes.contains(&include) {
+                includes.push(include);
+            }
+        }
+        Ok(Self(includes))
+    }
+}
+
+pub fn query_values(query_string: &str, key: &str) -> Result<Vec<String>, TrackerError> {
+    Ok(
+        web::Query::<Vec<(String, String)>>::from_query(query_string)?
+            .into_inner()
+            .into_iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v)
+            .collect(),
+    )
+}
+
 impl<T: Field> TryFrom<PageRequestRaw> for PageRequest<T> {
     type Error = TrackerError;
 
     fn try_from(page_request: PageRequestRaw) -> Result<Self, Self::Error> {
+        let paging = page_request.paging;
         let mut sorts: Vec<Sort<T>> = Vec::with_capacity(page_request.sorts.len());
         for sort_raw in page_request.sorts {
-            sorts.push(Sort::try_from(sort_raw)?);
+            let sort = Sort::<T>::try_from(sort_raw)?;
+            if sorts.iter().any(|s| s.field.name() == sort.field.name()) {
+                return Err(TrackerError::invalid_field(
+                    FieldValue::new("sort:field", sort.field.name()),
+                    AllowedValues::choice(
+                        T::values().filter(|f| !sorts.iter().any(|s| s.field.name() == f.name())),
+                    ),
+                ));
+            }
+            sorts.push(sort);
         }
 
         if sorts.is_empty() {
             sorts.push(Sort::<T>::default());
         }
 
+        let mut filters: Vec<Filter<T>> = Vec::with_capacity(page_request.filters.len());
+        for filter_raw in page_request.filters {
+            filters.push(Filter::try_from(filter_raw)?);
+        }
+
         let page = page_request
             .page
             .map(|page| {
@@ -101,16 +531,78 @@ impl<T: Field> TryFrom<PageRequestRaw> for PageRequest<T> {
                 u64::from_str_radix(&size, 10).map_err(|_| {
                     TrackerError::invalid_field(
                         FieldValue::new("size", size),
-                        AllowedValues::integer_min(Bound::inclusive(1)),
+                        AllowedValues::integer_between(
+                            Bound::inclusive(0),
+                            Bound::inclusive(paging.max_size),
+                        ),
                     )
                 })
             })
             .transpose()?;
 
+        let cursor = page_request
+            .cursor
+            .map(|cursor| {
+                let decoded = Cursor::decode(&cursor)?;
+                let invalid_cursor = || {
+                    TrackerError::invalid_field(
+                        FieldValue::new("cursor", cursor.as_str()),
+                        AllowedValues::cursor(),
+                    )
+                };
+                // A cursor is a position in one sort order; paging it by
+                // another sort, or by several, would skip or repeat rows.
+                if sorts.len() > 1 || decoded.sort != sorts[0].spec() {
+                    return Err(invalid_cursor());
+                }
+                if let Some(key) = &decoded.key {
+                    parse_field_value(sorts[0].field, "cursor", key)
+                        .map_err(|_| invalid_cursor())?;
+                }
+                Ok(decoded)
+            })
+            .transpose()?;
+
+        let count = CountMode::try_from(page_request.count)?;
+
+        let strict_paging = match page_request.strict_paging.as_deref() {
+            None | Some("false") => false,
+            Some("true") => true,
+            Some(other) => {
+                return Err(TrackerError::invalid_field(
+                    FieldValue::new("strict_paging", other),
+                    AllowedValues::choice(["true", "false"]),
+                ))
+            }
+        };
+
+        if strict_paging {
+            if let Some(page) = page.filter(|page| *page < FIRST_PAGE) {
+                return Err(TrackerError::invalid_field(
+                    FieldValue::new("page", page),
+                    AllowedValues::integer_min(Bound::inclusive(FIRST_PAGE)),
+                ));
+            }
+
+            if let Some(size) = size.filter(|size| *size > paging.max_size) {
+                return Err(TrackerError::invalid_field(
+                    FieldValue::new("size", size),
+                    AllowedValues::integer_between(
+                        Bound::inclusive(0),
+                        Bound::inclusive(paging.max_size),
+                    ),
+                ));
+            }
+        }
+
         Ok(Self {
             page: page.unwrap_or(FIRST_PAGE).max(FIRST_PAGE),
-            size: size.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE),
+            size: size.unwrap_or(paging.default_size).min(paging.max_size),
+            default_size: paging.default_size,
             sorts,
+            filters,
+            cursor,
+            count,
         })
     }
 }
@@ -119,43 +611,427 @@ impl<T: Field> TryFrom<String> for Sort<T> {
     type Error = TrackerError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        if let Some((field_raw, dir_raw)) = value.split_once(':') {
-            let field = T::from_str(&field_raw).map_err(|_| {
-                TrackerError::invalid_field(
-                    FieldValue::new("sort:field", field_raw),
-                    AllowedValues::choice(T::values()),
-                )
-            })?;
-            let dir = SortDirection::from_str(&dir_raw).map_err(|_| {
-                TrackerError::invalid_field(
-                    FieldValue::new("sort:direction", dir_raw),
-                    AllowedValues::choice(SortDirection::iter()),
-                )
-            })?;
+        let mut parts = value.splitn(3, ':');
+        let field_raw = parts.next().unwrap_or_default();
+        let field = T::from_str(field_raw).map_err(|_| {
+            TrackerError::invalid_field(
+                FieldValue::new("sort:field", field_raw),
+                AllowedValues::choice(T::values()),
+            )
+        })?;
 
-            Ok(Self {
-                field,
-                direction: dir,
+        let direction = parts
+            .next()
+            .map(|dir_raw| {
+                SortDirection::from_str(dir_raw).map_err(|_| {
+                    TrackerError::invalid_field(
+                        FieldValue::new(format!("sort:{0}:direction", field.name()), dir_raw),
+                        AllowedValues::choice(SortDirection::iter()),
+                    )
+                })
             })
-        } else {
-            let field = T::from_str(&value).map_err(|_| {
-                TrackerError::invalid_field(
-                    FieldValue::new("sort:field", value),
-                    AllowedValues::choice(T::values()),
-                )
-            })?;
-            Ok(Self {
-                field,
-                direction: SortDirection::default(),
+            .transpose()?
+            .unwrap_or_default();
+
+        let nulls = parts
+            .next()
+            .map(|nulls_raw| {
+                NullsOrder::from_str(nulls_raw).map_err(|_| {
+                    TrackerError::invalid_field(
+                        FieldValue::new(format!("sort:{0}:nulls", field.name()), nulls_raw),
+                        AllowedValues::choice(NullsOrder::iter()),
+                    )
+                })
             })
+            .transpose()?;
+
+        Ok(Self {
+            field,
+            direction,
+            nulls,
+        })
+    }
+}
+
+impl<T: Field> TryFrom<String> for Filter<T> {
+    type Error = TrackerError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let mut parts = value.splitn(3, ':');
+        let field_raw = parts.next().unwrap_or_default();
+        let field = T::from_str(field_raw).map_err(|_| {
+            TrackerError::invalid_field(
+                FieldValue::new("filter:field", field_raw),
+                AllowedValues::choice(T::values()),
+            )
+        })?;
+
+        let op_raw = parts.next().unwrap_or_default();
+        let operator = FilterOperator::from_str(op_raw).map_err(|_| {
+            TrackerError::invalid_field(
+                FieldValue::new(format!("filter:{0}:operator", field.name()), op_raw),
+                AllowedValues::choice(FilterOperator::iter()),
+            )
+        })?;
+
+        let value_raw = parts.next().unwrap_or_default();
+        let value =
+            parse_field_value(field, &format!("filter:{0}:value", field.name()), value_raw)?;
+
+        Ok(Self {
+            field,
+            operator,
+            value,
+        })
+    }
+}
+
+impl<T: Field> Filter<T> {
+    pub fn predicate(&self) -> SimpleExpr {
+        let column = Expr::col(self.field.column());
+        let value = self.value.clone();
+        match self.operator {
+            FilterOperator::Eq => column.eq(value),
+            FilterOperator::Ne => column.ne(value),
+            FilterOperator::Lt => column.lt(value),
+            FilterOperator::Lte => column.lte(value),
+            FilterOperator::Gt => column.gt(value),
+            FilterOperator::Gte => column.gte(value),
         }
     }
 }
 
 impl<T: Field> PageRequest<T> {
+    pub fn new(page: u64, size: u64, sorts: Vec<Sort<T>>) -> Self {
+        Self {
+            page,
+            size,
+            default_size: DEFAULT_PAGE_SIZE,
+            sorts,
+            filters: Vec::new(),
+            cursor: None,
+            count: CountMode::Exact,
+        }
+    }
+
     pub fn offset(&self) -> u64 {
-        (self.page - 1) * self.size
+        if self.cursor.is_some() {
+            0
+        } else {
+            (self.page - 1) * self.size
+        }
+    }
+
+    pub fn cursor_predicate<C: IntoColumnRef>(
+        &self,
+        id_column: C,
+    ) -> Result<Option<SimpleExpr>, TrackerError> {
+        let Some(cursor) = self.cursor.as_ref() else {
+            return Ok(None);
+        };
+        let sort = &self.sorts[0];
+        let column = Expr::col(sort.field.column());
+        let after_id = Expr::col(id_column).gt(cursor.id);
+
+        // Rows with the same key, null or not, follow in id order, and nulls
+        // come before or after all other keys as the sort places them.
+        let predicate = match &cursor.key {
+            Some(key) => {
+                let key = parse_field_value(sort.field, "cursor", key).map_err(|_| {
+                    TrackerError::invalid_field(
+                        FieldValue::new("cursor", cursor.encode()),
+                        AllowedValues::cursor(),
+                    )
+                })?;
+                let after_key = match sort.direction {
+                    SortDirection::Asc => column.clone().gt(key.clone()),
+                    SortDirection::Desc => column.clone().lt(key.clone()),
+                };
+                let predicate = after_key.or(column.clone().eq(key).and(after_id));
+                if sort.nulls_last() {
+                    predicate.or(column.is_null())
+                } else {
+                    predicate
+                }
+            }
+            None => {
+                let predicate = column.clone().is_null().and(after_id);
+                if sort.nulls_last() {
+                    predicate
+                } else {
+                    predicate.or(column.is_not_null())
+                }
+            }
+        };
+
+        Ok(Some(predicate))
+    }
+
+    pub fn next_cursor<E, F>(&self, results: &[E], to_cursor: F) -> Option<String>
+    where
+        F: Fn(&E, &Sort<T>) -> Cursor,
+    {
+        if self.sorts.len() > 1 || (results.len() as u64) < self.size {
+            return None;
+        }
+
+        results
+            .last()
+            .map(|last| to_cursor(last, &self.sorts[0]).encode())
+    }
+}
+
+impl PageRequestRaw {
+    /// For searches that only page by offset; they would otherwise ignore the
+    /// cursor and return the first page every time.
+    pub fn reject_cursor(&self) -> Result<(), TrackerError> {
+        match &self.cursor {
+            Some(cursor) => Err(TrackerError::invalid_field(
+                FieldValue::new("cursor", cursor.as_str()),
+                AllowedValues::cursor(),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Cursor {
+    pub fn new<T: Field>(sort: &Sort<T>, key: Option<String>, id: Uuid) -> Self {
+        Self {
+            sort: sort.spec(),
+            key,
+            id,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(raw: &str) -> Result<Self, TrackerError> {
+        URL_SAFE_NO_PAD
+            .decode(raw)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| {
+                TrackerError::invalid_field(FieldValue::new("cursor", raw), AllowedValues::cursor())
+            })
+    }
+}
+
+fn parse_field_value<T: Field>(
+    field: T,
+    value_field: &str,
+    value_raw: &str,
+) -> Result<SimpleExpr, TrackerError> {
+    Ok(match field.field_type() {
+        FieldType::Uuid => Expr::val(value_raw.parse::<Uuid>().map_err(|_| {
+            TrackerError::invalid_field(
+                FieldValue::new(value_field, value_raw),
+                AllowedValues::uuid(),
+            )
+        })?)
+        .into(),
+        FieldType::String => Expr::val(value_raw).into(),
+        FieldType::Integer => Expr::val(value_raw.parse::<i64>().map_err(|_| {
+            TrackerError::invalid_field(
+                FieldValue::new(value_field, value_raw),
+                AllowedValues::integer(),
+            )
+        })?)
+        .into(),
+        FieldType::Float => Expr::val(value_raw.parse::<f64>().map_err(|_| {
+            TrackerError::invalid_field(
+                FieldValue::new(value_field, value_raw),
+                AllowedValues::float(),
+            )
+        })?)
+        .into(),
+        FieldType::DateTime => Expr::val(value_raw.parse::<DateTime<Utc>>().map_err(|_| {
+            TrackerError::invalid_field(
+                FieldValue::new(value_field, value_raw),
+                AllowedValues::datetime_iso(),
+            )
+        })?)
+        .into(),
+        FieldType::Enum(type_name, values) => {
+            let allowed = values();
+            if !allowed.iter().any(|v| v == value_raw) {
+                return Err(TrackerError::invalid_field(
+                    FieldValue::new(value_field, value_raw),
+                    AllowedValues::choice(allowed),
+                ));
+            }
+            Expr::val(value_raw).as_enum(Alias::new(type_name))
+        }
+    })
+}
+
+pub async fn begin_repeatable_read(
+    pool: &PgPool,
+) -> Result<Transaction<'static, Postgres>, TrackerError> {
+    let mut transaction = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+        .execute(&mut *transaction)
+        .await?;
+    Ok(transaction)
+}
+
+/// Begins a transaction for a read-modify-write update.
+///
+/// With optimistic locking (the default) the transaction is REPEATABLE READ, so a row changed
+/// by another transaction after this one started fails with `ConcurrentUpdate` and the client
+/// retries. With pessimistic locking it is READ COMMITTED, so `SELECT ... FOR UPDATE` makes
+/// concurrent updates of the same row wait for each other instead, at the cost of holding the
+/// row lock for the rest of the transaction.
+pub async fn begin_update(
+    pool: &PgPool,
+    pessimistic_locking: bool,
+) -> Result<Transaction<'static, Postgres>, TrackerError> {
+    if pessimistic_locking {
+        Ok(pool.begin().await?)
+    } else {
+        begin_repeatable_read(pool).await
+    }
+}
+
+/// Adds the attempted and the stored version to a `ConcurrentUpdate` error so
+/// optimistic-concurrency clients can decide how to merge. The failed
+/// transaction may already be aborted, so the stored version is read on a
+/// separate connection.
+pub async fn with_current_version<T, C>(
+    result: Result<T, TrackerError>,
+    pool: &PgPool,
+    (table, id_column, version_column): (C, C, C),
+    id: Uuid,
+    attempted_version: Option<i32>,
+) -> Result<T, TrackerError>
+where
+    C: Iden + 'static,
+{
+    let err = match result {
+        Err(err @ TrackerError::ConcurrentUpdate(..)) => err,
+        other => return other,
+    };
+
+    let (sql, values) = Query::select()
+        .column(version_column)
+        .from(table)
+        .and_where(Expr::col(id_column).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let current_version: Option<i32> = sqlx::query_with(&sql, values.clone())
+        .fetch_optional(pool)
+        .await?
+        .map(|row| row.get(0));
+
+    Err(err.with_versions(attempted_version, current_version))
+}
+
+pub fn is_serialization_failure(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Database(db_err) => db_err.code().as_deref() == Some(SERIALIZATION_FAILURE),
+        _ => false,
+    }
+}
+
+/// Runs a `SELECT COUNT(*)` statement and returns the count.
+pub async fn count_rows<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    count_stmt: SelectStatement,
+) -> Result<u64, TrackerError> {
+    let (sql, values) = count_stmt.build_sqlx(PostgresQueryBuilder);
+
+    let count: i64 = sqlx::query_with(&sql, values.clone())
+        .fetch_one(&mut **tx)
+        .await?
+        .get(0);
+    Ok(count as u64)
+}
+
+pub async fn paginate<'a, E, T>(
+    tx: &mut Transaction<'a, Postgres>,
+    count_stmt: SelectStatement,
+    mut select_stmt: SelectStatement,
+    page_req: &PageRequest<T>,
+) -> Result<Page<E>, TrackerError>
+where
+    E: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    T: Field,
+{
+    let total_results = match page_req.count {
+        CountMode::Exact => {
+            let (count_sql, count_values) = count_stmt.build_sqlx(PostgresQueryBuilder);
+            let total_results: i64 = sqlx::query_with(&count_sql, count_values)
+                .fetch_one(&mut **tx)
+                .await?
+                .get(0);
+            total_results as u64
+        }
+        CountMode::Estimate => estimate_rows(tx, count_stmt).await?,
+    };
+
+    // An estimated count can't tell whether there is a next page, so one more
+    // row than requested is fetched to find out.
+    let limit = match page_req.count {
+        CountMode::Exact => page_req.size,
+        CountMode::Estimate => page_req.size + 1,
+    };
+    let (sql, values) = select_stmt
+        .limit(limit)
+        .offset(page_req.offset())
+        .build_sqlx(PostgresQueryBuilder);
+
+    let mut data = sqlx::query_as_with::<_, E, _>(&sql, values.clone())
+        .fetch_all(&mut **tx)
+        .await?;
+
+    let metadata = match page_req.count {
+        CountMode::Exact => PageMetadata::new(
+            page_req.page,
+            page_req.size,
+            page_req.default_size,
+            total_results,
+        ),
+        CountMode::Estimate => {
+            let has_next_page = page_req.size > 0 && data.len() as u64 > page_req.size;
+            data.truncate(page_req.size as usize);
+            PageMetadata::estimated(page_req.page, total_results, has_next_page)
+        }
+    };
+    Ok(Page::new(data, metadata))
+}
+
+/// Reads the planner's row estimate for the rows the count statement would
+/// count, which relies on table statistics (`reltuples`) instead of scanning
+/// every matching row.
+async fn estimate_rows(
+    tx: &mut Transaction<'_, Postgres>,
+    mut count_stmt: SelectStatement,
+) -> Result<u64, TrackerError> {
+    let (sql, values) = count_stmt
+        .clear_selects()
+        .expr(Expr::val(1))
+        .build_sqlx(PostgresQueryBuilder);
+    let plan: serde_json::Value = sqlx::query_with(&format!("EXPLAIN (FORMAT JSON) {sql}"), values)
+        .fetch_one(&mut **tx)
+        .await?
+        .get(0);
+
+    Ok(plan[0]["Plan"]["Plan Rows"]
+        .as_f64()
+        .map_or(0, |rows| rows.round() as u64))
+}
+
+pub fn add_sorts<T: Field, C: IntoColumnRef>(
+    select_stmt: &mut SelectStatement,
+    sorts: &[Sort<T>],
+    id_column: C,
+) {
+    for sort in sorts {
+        sort.apply(select_stmt);
     }
+    select_stmt.order_by(id_column, Order::Asc);
 }
 
 impl<T> Page<T> {
@@ -178,6 +1054,21 @@ impl<T> Page<T> {
         }
     }
 
+    pub fn try_map<U, E, F>(self, mut f: F) -> Result<Page<U>, E>
+    where
+        F: FnMut(T) -> Result<U, E>,
+    {
+        let mut new_data: Vec<U> = Vec::with_capacity(self.data.len());
+        for e in self.data {
+            new_data.push(f(e)?);
+        }
+
+        Ok(Page {
+            data: new_data,
+            metadata: self.metadata,
+        })
+    }
+
     pub fn convert<U>(self) -> Page<U>
     where
         T: Into<U>,
@@ -187,26 +1078,289 @@ impl<T> Page<T> {
 }
 
 impl PageMetadata {
-    pub fn new(page: u64, size: u64, total_results: u64) -> PageMetadata {
-        let total_pages = f32::ceil(total_results as f32 / size as f32) as u64;
+    pub fn new(page: u64, size: u64, default_size: u64, total_results: u64) -> PageMetadata {
+        let page_size = if size == 0 { default_size } else { size };
+        let total_pages = total_results.div_ceil(page_size);
         PageMetadata {
             total_results,
-            total_pages,
+            total_pages: Some(total_pages),
             current_page: page,
             next_page: if page < total_pages {
                 Some(page + 1)
             } else {
                 None
             },
-            prev_page: if page > 1 { Some(page - 1) } else { None },
+            prev_page: if total_pages > 0 && page > 1 {
+                Some(page - 1)
+            } else {
+                None
+            },
+            next_cursor: None,
+            count_estimated: false,
+        }
+    }
+
+    /// Metadata for an estimated `total_results`, which leaves the page count
+    /// unknown; whether there is a next page has to be found out separately.
+    pub fn estimated(page: u64, total_results: u64, has_next_page: bool) -> PageMetadata {
+        PageMetadata {
+            total_results,
+            total_pages: None,
+            current_page: page,
+            next_page: has_next_page.then_some(page + 1),
+            prev_page: (page > FIRST_PAGE).then(|| page - 1),
+            next_cursor: None,
+            count_estimated: true,
+        }
+    }
+
+    pub fn with_next_cursor(mut self, next_cursor: Option<String>) -> Self {
+        self.next_cursor = next_cursor;
+        self
+    }
+
+    fn links(&self, req: &HttpRequest) -> Vec<String> {
+        let base_url = req
+            .app_data::<web::Data<AppState>>()
+            .and_then(|state| state.base_url.clone())
+            .unwrap_or_else(|| {
+                let conn_info = req.connection_info();
+                format!("{0}://{1}", conn_info.scheme(), conn_info.host())
+            });
+        let query = web::Query::<Vec<(String, String)>>::from_query(req.query_string())
+            .map(web::Query::into_inner)
+            .unwrap_or_default();
+
+        let page_url = |page: u64| {
+            let mut serializer = form_urlencoded::Serializer::new(String::new());
+            for (key, value) in query.iter().filter(|(key, _)| key != "page") {
+                serializer.append_pair(key, value);
+            }
+            serializer.append_pair("page", &page.to_string());
+            format!("{0}{1}?{2}", base_url, req.path(), serializer.finish())
+        };
+
+        let mut links = Vec::with_capacity(4);
+        if let Some(next_page) = self.next_page {
+            links.push(format!("<{0}>; rel=\"next\"", page_url(next_page)));
+        }
+        if let Some(prev_page) = self.prev_page {
+            links.push(format!("<{0}>; rel=\"prev\"", page_url(prev_page)));
+        }
+        links.push(format!("<{0}>; rel=\"first\"", page_url(FIRST_PAGE)));
+        if let Some(total_pages) = self.total_pages {
+            links.push(format!(
+                "<{0}>; rel=\"last\"",
+                page_url(total_pages.max(FIRST_PAGE))
+            ));
+        }
+        links
+    }
+}
+
+impl<T: Serialize> Page<T> {
+    fn to_csv(&self) -> Result<Vec<u8>, TrackerError> {
+        let mut columns: Vec<String> = Vec::new();
+        let mut rows = Vec::with_capacity(self.data.len());
+        for record in &self.data {
+            let value = serde_json::to_value(record).map_err(JsonPayloadError::Serialize)?;
+            let mut row = serde_json::Map::new();
+            flatten_csv_row(None, value, &mut row);
+            for column in row.keys() {
+                if !columns.contains(column) {
+                    columns.push(column.clone());
+                }
+            }
+            rows.push(row);
+        }
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(&columns)?;
+        for row in &rows {
+            writer.write_record(columns.iter().map(|column| match row.get(column) {
+                None | Some(serde_json::Value::Null) => String::new(),
+                Some(serde_json::Value::String(value)) => value.clone(),
+                Some(value) => value.to_string(),
+            }))?;
+        }
+
+        writer
+            .into_inner()
+            .map_err(|err| csv::Error::from(err.into_error()).into())
+    }
+}
+
+fn flatten_csv_row(
+    prefix: Option<&str>,
+    value: serde_json::Value,
+    row: &mut serde_json::Map<String, serde_json::Value>,
+) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields {
+                let column = match prefix {
+                    Some(prefix) => format!("{0}.{1}", prefix, name),
+                    None => name,
+                };
+                flatten_csv_row(Some(&column), field, row);
+            }
+        }
+        value => {
+            row.insert(prefix.unwrap_or_default().to_owned(), value);
         }
     }
 }
 
+fn prefers_csv(req: &HttpRequest) -> bool {
+    header::Accept::parse(req)
+        .map(|accept| accept.preference().essence_str() == CSV_CONTENT_TYPE)
+        .unwrap_or(false)
+}
+
 impl<T: Serialize> Responder for Page<T> {
     type Body = BoxBody;
 
-    fn respond_to(self, _: &HttpRequest) -> HttpResponse<Self::Body> {
-        HttpResponse::Ok().json(self)
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let mut response = HttpResponse::Ok();
+        response
+            .insert_header(("X-Total-Count", self.metadata.total_results))
+            .insert_header((header::VARY, "Accept"));
+        if let Some(total_pages) = self.metadata.total_pages {
+            response.insert_header(("X-Total-Pages", total_pages));
+        }
+
+        let links = self.metadata.links(req);
+        if !links.is_empty() {
+            response.insert_header((header::LINK, links.join(", ")));
+        }
+
+        if prefers_csv(req) {
+            return match self.to_csv() {
+                Ok(body) => response
+                    .content_type(format!("{0}; charset=utf-8", CSV_CONTENT_TYPE))
+                    .body(body),
+                Err(err) => err.error_response(),
+            };
+        }
+
+        response.json(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_save::api::SaveFields;
+
+    const F32_EXACT_LIMIT: u64 = 1 << 24;
+
+    #[test]
+    fn total_pages_are_exact_at_the_f32_boundary() {
+        let metadata = PageMetadata::new(1, 1, DEFAULT_PAGE_SIZE, F32_EXACT_LIMIT);
+        assert_eq!(metadata.total_pages, Some(16_777_216));
+
+        let metadata = PageMetadata::new(1, 1, DEFAULT_PAGE_SIZE, F32_EXACT_LIMIT + 1);
+        assert_eq!(metadata.total_pages, Some(16_777_217));
+        assert_eq!(metadata.next_page, Some(2));
+
+        let metadata = PageMetadata::new(
+            F32_EXACT_LIMIT + 1,
+            1,
+            DEFAULT_PAGE_SIZE,
+            F32_EXACT_LIMIT + 1,
+        );
+        assert_eq!(metadata.next_page, None);
+        assert_eq!(metadata.prev_page, Some(16_777_216));
+    }
+
+    #[test]
+    fn total_pages_round_up_partial_pages() {
+        let metadata = PageMetadata::new(1, 2, DEFAULT_PAGE_SIZE, F32_EXACT_LIMIT + 1);
+        assert_eq!(metadata.total_pages, Some(8_388_609));
+    }
+
+    #[test]
+    fn empty_results_have_no_adjacent_pages() {
+        let metadata = PageMetadata::new(3, 10, DEFAULT_PAGE_SIZE, 0);
+        assert_eq!(metadata.total_pages, Some(0));
+        assert_eq!(metadata.current_page, 3);
+        assert_eq!(metadata.next_page, None);
+        assert_eq!(metadata.prev_page, None);
+    }
+
+    #[test]
+    fn estimated_counts_leave_out_the_page_count_and_last_link() {
+        let metadata = PageMetadata::estimated(2, 324, true);
+        assert_eq!(metadata.total_pages, None);
+        assert_eq!(metadata.next_page, Some(3));
+        assert_eq!(metadata.prev_page, Some(1));
+        assert!(serde_json::to_value(&metadata)
+            .unwrap()
+            .get("total_pages")
+            .is_none());
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/1/saves?page=2")
+            .to_http_request();
+        let links = metadata.links(&req);
+        assert!(links.iter().any(|link| link.ends_with("rel=\"next\"")));
+        assert!(!links.iter().any(|link| link.ends_with("rel=\"last\"")));
+
+        let metadata = PageMetadata::estimated(1, 324, false);
+        assert_eq!(metadata.next_page, None);
+        assert_eq!(metadata.prev_page, None);
+    }
+
+    #[test]
+    fn invalid_sort_direction_names_the_sort_field() {
+        let err = Sort::<SaveFields>::try_from("name:sideways".to_owned()).unwrap_err();
+        match err {
+            TrackerError::InvalidFieldValue(field, _) => {
+                assert_eq!(field.name, "sort:name:direction");
+                assert!(
+                    matches!(field.value, Some(crate::field::Value::String(ref v)) if v == "sideways")
+                );
+            }
+            other => panic!("expected InvalidFieldValue, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn zero_size_pages_against_the_default_size() {
+        let metadata = PageMetadata::new(1, 0, DEFAULT_PAGE_SIZE, 250);
+        assert_eq!(metadata.total_results, 250);
+        assert_eq!(metadata.total_pages, Some(3));
+        assert_eq!(metadata.next_page, Some(2));
+        assert_eq!(metadata.prev_page, None);
+    }
+
+    fn page_request(query: serde_json::Value) -> Result<PageRequest<SaveFields>, TrackerError> {
+        PageRequest::try_from(serde_json::from_value::<PageRequestRaw>(query).unwrap())
+    }
+
+    fn assert_invalid_cursor(result: Result<PageRequest<SaveFields>, TrackerError>) {
+        match result {
+            Err(TrackerError::InvalidFieldValue(field, _)) => assert_eq!(field.name, "cursor"),
+            other => panic!("expected an invalid cursor, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn cursors_only_page_the_sort_they_were_returned_for() {
+        let sort = Sort::<SaveFields>::try_from("name:desc".to_owned()).unwrap();
+        let cursor = Cursor::new(&sort, Some("Sol".to_owned()), Uuid::new_v4()).encode();
+
+        let page = page_request(serde_json::json!({ "sorts": "name:desc", "cursor": cursor }));
+        assert!(page.unwrap().cursor.is_some());
+
+        assert_invalid_cursor(page_request(
+            serde_json::json!({ "sorts": "name", "cursor": cursor }),
+        ));
+        assert_invalid_cursor(page_request(
+            serde_json::json!({ "sorts": "notes:desc", "cursor": cursor }),
+        ));
+        assert_invalid_cursor(page_request(
+            serde_json::json!({ "sorts": "name:desc,created_at", "cursor": cursor }),
+        ));
     }
 }
@@ -1,10 +1,22 @@
-use crate::field::{format_value, AllowedValues, FieldValue, FieldValues};
-use actix_web::{error::JsonPayloadError, http::StatusCode, HttpResponse, ResponseError};
+use crate::{
+    field::{format_value, AllowedValues, FieldValue, FieldValues},
+    request_id::RequestId,
+};
+use actix_web::{
+    error::JsonPayloadError,
+    http::{header, StatusCode},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use log::{log, Level};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+const RETRY_AFTER_SECS: u32 = 1;
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, ToSchema)]
 pub enum ObjectKind {
     #[serde(rename = "save")]
     Save,
@@ -22,7 +34,28 @@ pub enum ObjectKind {
     ItemRecipe,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DependentCount {
+    pub object: ObjectKind,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct DependentCounts(pub Vec<DependentCount>);
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InvalidField {
+    pub field: FieldValue,
+    pub allowed_values: AllowedValues,
+}
+
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: Vec<(FieldValue, AllowedValues)>,
+    other: Option<TrackerError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub error_code: String,
     pub message: String,
@@ -34,6 +67,14 @@ pub struct ErrorResponse {
     pub field: Option<FieldValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allowed_values: Option<AllowedValues>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependents: Option<Vec<DependentCount>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<InvalidField>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 #[derive(Error, Debug)]
@@ -46,15 +87,35 @@ pub enum TrackerError {
     Duplicate(ObjectKind, FieldValues),
     #[error("The value `{}` for the field {} is invalid. {1}", format_value(&.0.value), .0.name)]
     InvalidFieldValue(FieldValue, AllowedValues),
+    #[error("The request contains invalid values: {}", format_invalid_fields(.0))]
+    Validation(Vec<(FieldValue, AllowedValues)>),
     #[error("Missing required field {0}. {1}")]
     MissingRequiredField(String, AllowedValues),
     #[error("Another transaction has already updated the {0} with {1}. Please try again.")]
     ConcurrentUpdate(ObjectKind, FieldValues),
+    #[error("An If-Match header with the current ETag is required to update a {0}.")]
+    PreconditionRequired(ObjectKind),
+    #[error("The {0} with {1} has been modified since it was fetched. Please fetch it again.")]
+    PreconditionFailed(ObjectKind, FieldValues),
+    #[error("The {0} with {1} cannot be deleted while it is referenced by {2}.")]
+    DeleteRestricted(ObjectKind, FieldValues, DependentCounts),
+    #[error("The {0} with {1} cannot reference {2} as it would create a cycle.")]
+    CyclicReference(ObjectKind, FieldValues, FieldValue),
+    #[error("No route matches {0} {1}.")]
+    RouteNotFound(String, String),
+    #[error("A valid bearer token is required in the Authorization header.")]
+    Unauthorized,
+    #[error("The bearer token does not grant access to this resource.")]
+    Forbidden,
+    #[error("Batch item {0} failed: {1}")]
+    BatchItem(usize, Box<TrackerError>),
     #[error("{0}")]
     SqlError(#[from] sqlx::Error),
     #[error("{0}")]
     JsonError(#[from] actix_web::error::JsonPayloadError),
     #[error("{0}")]
+    CsvError(#[from] csv::Error),
+    #[error("{0}")]
     QueryStringError(#[from] actix_web::error::QueryPayloadError),
     #[error("{0}")]
     PathError(#[from] actix_web::error::PathError),
@@ -62,6 +123,56 @@ pub enum TrackerError {
 
 pub type Result<T> = std::result::Result<T, TrackerError>;
 
+/// Client errors are the caller's mistake, so only server errors log at `error`.
+fn log_level(err: &TrackerError) -> Level {
+    if err.is_internal_server_error() {
+        Level::Error
+    } else {
+        Level::Warn
+    }
+}
+
+pub fn log_lookup_error(object: ObjectKind, id: Uuid) -> impl FnOnce(&TrackerError) {
+    move |err| {
+        log!(
+            log_level(err),
+            entity:% = object, id:% = id, error_code:% = err.error_code();
+            "Failed to lookup {}: {}", object, err
+        )
+    }
+}
+
+pub fn log_error(message: &'static str, object: ObjectKind) -> impl FnOnce(&TrackerError) {
+    move |err| {
+        log!(
+            log_level(err),
+            entity:% = object, error_code:% = err.error_code();
+            "{}: {}", message, err
+        )
+    }
+}
+
+pub fn log_entity_error(
+    message: &'static str,
+    object: ObjectKind,
+    id: Uuid,
+) -> impl FnOnce(&TrackerError) {
+    move |err| {
+        log!(
+            log_level(err),
+            entity:% = object, id:% = id, error_code:% = err.error_code();
+            "{}: {}", message, err
+        )
+    }
+}
+
+pub async fn route_not_found_handler(req: HttpRequest) -> Result<HttpResponse> {
+    Err(TrackerError::RouteNotFound(
+        req.method().to_string(),
+        req.path().to_owned(),
+    ))
+}
+
 impl fmt::Display for ObjectKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -80,6 +191,65 @@ impl fmt::Display for ObjectKind {
     }
 }
 
+fn format_invalid_fields(errors: &[(FieldValue, AllowedValues)]) -> String {
+    errors
+        .iter()
+        .map(|(field, allowed_values)| format!("{0}. {1}", field, allowed_values))
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn check(&mut self, result: Result<()>) -> &mut Self {
+        match result {
+            Ok(()) => {}
+            Err(TrackerError::InvalidFieldValue(field, allowed_values)) => {
+                self.errors.push((field, allowed_values))
+            }
+            Err(TrackerError::Validation(errors)) => self.errors.extend(errors),
+            Err(err) => {
+                self.other.get_or_insert(err);
+            }
+        }
+        self
+    }
+
+    pub fn finish(self) -> Result<()> {
+        if let Some(err) = self.other {
+            return Err(err);
+        }
+
+        let mut errors = self.errors;
+        match errors.len() {
+            0 => Ok(()),
+            1 => {
+                let (field, allowed_values) = errors.remove(0);
+                Err(TrackerError::InvalidFieldValue(field, allowed_values))
+            }
+            _ => Err(TrackerError::Validation(errors)),
+        }
+    }
+}
+
+impl fmt::Display for DependentCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{0}",
+            self.0
+                .iter()
+                .filter(|d| d.count > 0)
+                .map(|d| format!("{0} {1}(s)", d.count, d.object))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
 impl TrackerError {
     pub fn not_found<K: Into<FieldValues>>(object: ObjectKind, keys: K) -> Self {
         Self::NotFound(object, keys.into())
@@ -93,6 +263,26 @@ impl TrackerError {
         Self::ConcurrentUpdate(object, keys.into())
     }
 
+    pub fn precondition_failed<K: Into<FieldValues>>(object: ObjectKind, keys: K) -> Self {
+        Self::PreconditionFailed(object, keys.into())
+    }
+
+    pub fn delete_restricted<K: Into<FieldValues>>(
+        object: ObjectKind,
+        keys: K,
+        dependents: Vec<DependentCount>,
+    ) -> Self {
+        Self::DeleteRestricted(object, keys.into(), DependentCounts(dependents))
+    }
+
+    pub fn cyclic_reference<K: Into<FieldValues>>(
+        object: ObjectKind,
+        keys: K,
+        field: FieldValue,
+    ) -> Self {
+        Self::CyclicReference(object, keys.into(), field)
+    }
+
     pub fn invalid_field(field: FieldValue, allowed_values: AllowedValues) -> Self {
         Self::InvalidFieldValue(field, allowed_values)
     }
@@ -101,9 +291,47 @@ impl TrackerError {
         Self::MissingRequiredField(field.into(), allowed_values)
     }
 
+    pub fn batch_item(index: usize, err: TrackerError) -> Self {
+        Self::BatchItem(index, Box::new(err))
+    }
+
+    /// Appends the attempted and the currently stored version to the keys of a
+    /// `ConcurrentUpdate`; either is null when unknown or the row is gone.
+    pub fn with_versions(
+        self,
+        attempted_version: Option<i32>,
+        current_version: Option<i32>,
+    ) -> Self {
+        let version_field = |name: &str, version: Option<i32>| match version {
+            Some(version) => FieldValue::new(name, version),
+            None => FieldValue::null_value(name),
+        };
+
+        match self {
+            Self::ConcurrentUpdate(object, mut keys) => {
+                keys.0.push(version_field("version", attempted_version));
+                keys.0
+                    .push(version_field("current_version", current_version));
+                Self::ConcurrentUpdate(object, keys)
+            }
+            _ => self,
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::BatchItem(_, err) => err.is_retryable(),
+            _ => matches!(
+                self,
+                Self::ConcurrentUpdate(..) | Self::SqlError(sqlx::Error::PoolTimedOut)
+            ),
+        }
+    }
+
     pub fn is_internal_server_error(&self) -> bool {
         match self {
-            Self::UnexpectedNotFound(..) | Self::SqlError(..) => true,
+            Self::BatchItem(_, err) => err.is_internal_server_error(),
+            Self::UnexpectedNotFound(..) | Self::SqlError(..) | Self::CsvError(..) => true,
             Self::JsonError(json_err) => matches!(json_err, JsonPayloadError::Serialize(..)),
             _ => false,
         }
@@ -119,10 +347,19 @@ impl TrackerError {
     pub fn error_code(&self) -> String {
         match self {
             Self::NotFound(..) => "NotFound",
+            Self::RouteNotFound(..) => "NotFound",
             Self::Duplicate(..) => "Duplicate",
             Self::InvalidFieldValue(..) => "InvalidFieldValue",
+            Self::Validation(..) => "ValidationFailed",
             Self::MissingRequiredField(..) => "MissingRequiredField",
             Self::ConcurrentUpdate(..) => "ConcurrentUpdate",
+            Self::DeleteRestricted(..) => "DeleteRestricted",
+            Self::CyclicReference(..) => "CyclicReference",
+            Self::PreconditionRequired(..) => "PreconditionRequired",
+            Self::PreconditionFailed(..) => "PreconditionFailed",
+            Self::Unauthorized => "Unauthorized",
+            Self::Forbidden => "Forbidden",
+            Self::BatchItem(_, err) => return err.error_code(),
             Self::JsonError(json_err) => match json_err {
                 JsonPayloadError::ContentType => "UnsupportedContentType",
                 JsonPayloadError::Serialize(..) => "InternalServerError",
@@ -140,11 +377,22 @@ impl TrackerError {
     }
 
     pub fn to_error_response(&self) -> ErrorResponse {
+        if let Self::BatchItem(index, err) = self {
+            let mut response = err.to_error_response();
+            if !err.is_internal_server_error() {
+                response.message = self.to_string();
+            }
+            response.index = Some(*index);
+            return response;
+        }
+
         let mut message = self.to_string();
         let mut keys: Option<Vec<FieldValue>> = None;
         let mut object: Option<ObjectKind> = None;
         let mut field: Option<FieldValue> = None;
         let mut allowed_values: Option<AllowedValues> = None;
+        let mut dependents: Option<Vec<DependentCount>> = None;
+        let mut errors: Vec<InvalidField> = Vec::new();
 
         match self {
             Self::NotFound(o, k) => {
@@ -159,6 +407,15 @@ impl TrackerError {
                 field = Some(fv.clone());
                 allowed_values = Some(av.clone());
             }
+            Self::Validation(e) => {
+                errors = e
+                    .iter()
+                    .map(|(fv, av)| InvalidField {
+                        field: fv.clone(),
+                        allowed_values: av.clone(),
+                    })
+                    .collect();
+            }
             Self::MissingRequiredField(name, av) => {
                 field = Some(FieldValue::null_value(name));
                 allowed_values = Some(av.clone());
@@ -167,6 +424,37 @@ impl TrackerError {
                 object = Some(*o);
                 keys = Some(fv.0.clone());
             }
+            Self::PreconditionRequired(o) => {
+                object = Some(*o);
+            }
+            Self::PreconditionFailed(o, k) => {
+                object = Some(*o);
+                keys = Some(k.0.clone());
+            }
+            Self::DeleteRestricted(o, k, d) => {
+                object = Some(*o);
+                keys = Some(k.0.clone());
+                dependents = Some(d.0.clone());
+            }
+            Self::CyclicReference(o, k, fv) => {
+                object = Some(*o);
+                keys = Some(k.0.clone());
+                field = Some(fv.clone());
+            }
+            Self::JsonError(JsonPayloadError::Deserialize(e)) => {
+                if let Some((name, expected)) = unknown_field(e) {
+                    message = if expected.is_empty() {
+                        format!("The field `{name}` is not recognized.")
+                    } else {
+                        format!(
+                            "The field `{name}` is not recognized. Allowed fields are: {}",
+                            expected.join(", ")
+                        )
+                    };
+                    field = Some(FieldValue::null_value(name));
+                    allowed_values = Some(AllowedValues::choice(expected));
+                }
+            }
             _ => {}
         }
 
@@ -181,30 +469,135 @@ impl TrackerError {
             keys,
             field,
             allowed_values,
+            dependents,
+            errors,
+            index: None,
+            request_id: None,
         }
     }
 }
 
+/// Extracts the offending field and the expected fields from the error serde
+/// reports for a `#[serde(deny_unknown_fields)]` struct.
+fn unknown_field(err: &serde_json::Error) -> Option<(String, Vec<String>)> {
+    let message = err.to_string();
+    let (name, rest) = message.strip_prefix("unknown field `")?.split_once('`')?;
+    let expected = rest
+        .split(" at line ")
+        .next()?
+        .split('`')
+        .skip(1)
+        .step_by(2)
+        .map(str::to_owned)
+        .collect();
+    Some((name.to_owned(), expected))
+}
+
 impl ResponseError for TrackerError {
     fn status_code(&self) -> StatusCode {
         match self {
             Self::NotFound(..) => StatusCode::NOT_FOUND,
+            Self::RouteNotFound(..) => StatusCode::NOT_FOUND,
             Self::Duplicate(..) => StatusCode::CONFLICT,
             Self::InvalidFieldValue(..) => StatusCode::BAD_REQUEST,
+            Self::Validation(..) => StatusCode::BAD_REQUEST,
             Self::MissingRequiredField(..) => StatusCode::BAD_REQUEST,
             Self::ConcurrentUpdate(..) => StatusCode::CONFLICT,
+            Self::DeleteRestricted(..) => StatusCode::CONFLICT,
+            Self::CyclicReference(..) => StatusCode::BAD_REQUEST,
+            Self::PreconditionRequired(..) => StatusCode::PRECONDITION_REQUIRED,
+            Self::PreconditionFailed(..) => StatusCode::PRECONDITION_FAILED,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+            Self::BatchItem(_, err) => err.status_code(),
             Self::SqlError(..) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::CsvError(..) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::UnexpectedNotFound(..) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::JsonError(json_err) => match json_err {
                 JsonPayloadError::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
                 _ => json_err.status_code(),
             },
             Self::QueryStringError(..) => StatusCode::BAD_REQUEST,
-            Self::PathError(..) => StatusCode::NOT_FOUND,
+            Self::PathError(..) => StatusCode::BAD_REQUEST,
         }
     }
 
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code()).json(self.to_error_response())
+        self.error_response_with_request_id(None)
+    }
+}
+
+impl TrackerError {
+    pub fn error_response_with_request_id(&self, request_id: Option<&RequestId>) -> HttpResponse {
+        let mut response = self.to_error_response();
+        response.request_id = request_id.map(ToString::to_string);
+
+        let mut builder = HttpResponse::build(self.status_code());
+        if self.is_retryable() {
+            builder.insert_header((header::RETRY_AFTER, RETRY_AFTER_SECS));
+        }
+        if matches!(self, Self::Unauthorized) {
+            builder.insert_header((header::WWW_AUTHENTICATE, "Bearer"));
+        }
+        builder.json(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct Request {
+        name: String,
+        notes: Option<String>,
+        mining_speed: u32,
+    }
+
+    fn deserialize_error(body: &str) -> serde_json::Error {
+        serde_json::from_str::<Request>(body).unwrap_err()
+    }
+
+    /// `unknown_field` parses serde's message, so a change in its wording
+    /// should fail here rather than silently drop the field from responses.
+    #[test]
+    fn serde_unknown_field_wording_is_parsed() {
+        let err = deserialize_error(r#"{"nmae": "Sol"}"#);
+        assert!(
+            err.to_string().starts_with(
+                "unknown field `nmae`, expected one of `name`, `notes`, `mining_speed`"
+            ),
+            "{err}"
+        );
+        assert_eq!(
+            unknown_field(&err),
+            Some((
+                "nmae".to_owned(),
+                vec![
+                    "name".to_owned(),
+                    "notes".to_owned(),
+                    "mining_speed".to_owned()
+                ]
+            ))
+        );
+
+        assert_eq!(unknown_field(&deserialize_error(r#"{"name": 1}"#)), None);
+    }
+
+    #[test]
+    fn misspelled_json_fields_are_named_in_the_response() {
+        let err = TrackerError::JsonError(JsonPayloadError::Deserialize(deserialize_error(
+            r#"{"name": "Sol", "mining_sped": 100}"#,
+        )));
+        let response = err.to_error_response();
+        assert_eq!(
+            response.field.map(|f| f.name).as_deref(),
+            Some("mining_sped")
+        );
+        assert_eq!(
+            response.message,
+            "The field `mining_sped` is not recognized. Allowed fields are: name, notes, mining_speed"
+        );
     }
 }
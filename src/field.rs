@@ -1,9 +1,10 @@
-use crate::data::SortDirection;
+use crate::data::{CountMode, FilterOperator, MatchMode, NullsOrder, SortDirection};
 use chrono::{DateTime, Utc};
 use sea_query::ColumnRef;
 use serde::{Deserialize, Serialize};
 use std::{fmt, str::FromStr};
 use thiserror::Error;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 const ISO_FORMAT: &str = "yyyy-mm-ddTHH:MM:ss[.SSS]Z";
@@ -17,10 +18,22 @@ pub trait Field: ToString + FromStr + Copy + Default {
 
     fn name(&self) -> String;
 
+    fn field_type(&self) -> FieldType;
+
     fn values() -> impl Iterator<Item = Self>;
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone)]
+pub enum FieldType {
+    Uuid,
+    String,
+    Integer,
+    Float,
+    DateTime,
+    Enum(&'static str, fn() -> Vec<String>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(untagged)]
 pub enum Value {
     Uuid(Uuid),
@@ -37,7 +50,7 @@ pub enum Value {
     DateTime(DateTime<Utc>),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FieldValue {
     pub name: String,
     pub value: Option<Value>,
@@ -46,17 +59,19 @@ pub struct FieldValue {
 #[derive(Debug, Clone)]
 pub struct FieldValues(pub Vec<FieldValue>);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Bound {
     pub value: Value,
     pub inclusive: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "type")]
 pub enum AllowedValues {
     Choice {
         values: Vec<Value>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        descriptions: Vec<String>,
     },
     Integer {
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -73,6 +88,8 @@ pub enum AllowedValues {
     DateTime {
         format: String,
     },
+    Uuid,
+    Cursor,
     String {
         #[serde(skip_serializing_if = "Option::is_none")]
         min_length: Option<usize>,
@@ -81,6 +98,25 @@ pub enum AllowedValues {
     },
 }
 
+impl From<Value> for sea_query::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Uuid(v) => v.into(),
+            Value::String(v) => v.into(),
+            Value::Int8(v) => v.into(),
+            Value::Int16(v) => v.into(),
+            Value::Int32(v) => v.into(),
+            Value::Int64(v) => v.into(),
+            Value::Uint8(v) => v.into(),
+            Value::Uint16(v) => v.into(),
+            Value::Uint32(v) => v.into(),
+            Value::Uint64(v) => v.into(),
+            Value::Float(v) => v.into(),
+            Value::DateTime(v) => v.into(),
+        }
+    }
+}
+
 impl From<Uuid> for Value {
     fn from(value: Uuid) -> Self {
         Self::Uuid(value)
@@ -105,6 +141,30 @@ impl From<&str> for Value {
     }
 }
 
+impl From<NullsOrder> for Value {
+    fn from(value: NullsOrder) -> Self {
+        Self::String(value.as_ref().to_owned())
+    }
+}
+
+impl From<CountMode> for Value {
+    fn from(value: CountMode) -> Self {
+        Self::String(value.as_ref().to_owned())
+    }
+}
+
+impl From<MatchMode> for Value {
+    fn from(value: MatchMode) -> Self {
+        Self::String(value.as_ref().to_owned())
+    }
+}
+
+impl From<FilterOperator> for Value {
+    fn from(value: FilterOperator) -> Self {
+        Self::String(value.as_ref().to_owned())
+    }
+}
+
 impl From<SortDirection> for Value {
     fn from(value: SortDirection) -> Self {
         Self::String(value.as_ref().to_owned())
@@ -196,7 +256,7 @@ impl fmt::Display for Value {
             Self::Uint32(v) => write!(f, "{0}", v),
             Self::Uint16(v) => write!(f, "{0}", v),
             Self::Uint8(v) => write!(f, "{0}", v),
-            Self::Float(v) => write!(f, "{0}", v),
+            Self::Float(v) => write!(f, "{0:?}", v),
             Self::DateTime(v) => write!(f, "{0}", v),
         }
     }
@@ -244,10 +304,43 @@ impl fmt::Display for Bound {
     }
 }
 
+struct UpperBound<'a>(&'a Bound);
+
+impl fmt::Display for UpperBound<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.inclusive {
+            write!(f, "<= {0}", self.0.value)
+        } else {
+            write!(f, "< {0}", self.0.value)
+        }
+    }
+}
+
 impl AllowedValues {
     pub fn choice<T: Into<Value>, I: IntoIterator<Item = T>>(values: I) -> AllowedValues {
         AllowedValues::Choice {
             values: values.into_iter().map(|v| v.into()).collect(),
+            descriptions: Vec::new(),
+        }
+    }
+
+    pub fn described_choice<T: Into<Value>, D: Into<String>, I: IntoIterator<Item = (T, D)>>(
+        values: I,
+    ) -> AllowedValues {
+        let (values, descriptions) = values
+            .into_iter()
+            .map(|(v, d)| (v.into(), d.into()))
+            .unzip();
+        AllowedValues::Choice {
+            values,
+            descriptions,
+        }
+    }
+
+    pub fn integer() -> AllowedValues {
+        AllowedValues::Integer {
+            min: None,
+            max: None,
         }
     }
 
@@ -272,6 +365,13 @@ impl AllowedValues {
         }
     }
 
+    pub fn float() -> AllowedValues {
+        AllowedValues::Float {
+            min: None,
+            max: None,
+        }
+    }
+
     pub fn float_between(min: Bound, max: Bound) -> AllowedValues {
         AllowedValues::Float {
             min: Some(min),
@@ -303,6 +403,14 @@ impl AllowedValues {
         AllowedValues::DateTime { format }
     }
 
+    pub fn uuid() -> AllowedValues {
+        AllowedValues::Uuid
+    }
+
+    pub fn cursor() -> AllowedValues {
+        AllowedValues::Cursor
+    }
+
     pub fn string_len_between(min_length: usize, max_length: usize) -> AllowedValues {
         AllowedValues::String {
             min_length: Some(min_length),
@@ -328,44 +436,63 @@ impl AllowedValues {
 impl fmt::Display for AllowedValues {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Choice { values } => {
+            Self::Choice {
+                values,
+                descriptions,
+            } => {
                 write!(
                     f,
                     "Allowed values are: {0}",
                     values
                         .iter()
-                        .map(|e| e.to_string())
+                        .enumerate()
+                        .map(|(i, e)| match descriptions.get(i) {
+                            Some(description) => format!("{0} ({1})", e, description),
+                            None => e.to_string(),
+                        })
                         .collect::<Vec<String>>()
                         .join(", ")
                 )
             }
             Self::Integer { min, max } => match (min, max) {
                 (Some(min), Some(max)) => {
-                    write!(f, "Value must be an integer {0} and {1}.", min, max)
+                    write!(
+                        f,
+                        "Value must be an integer {0} and {1}.",
+                        min,
+                        UpperBound(max)
+                    )
                 }
                 (Some(min), None) => {
-                    write!(f, "Value must be  an integer {0}.", min)
+                    write!(f, "Value must be an integer {0}.", min)
                 }
                 (None, Some(max)) => {
-                    write!(f, "Value must be an integer {0}.", max)
+                    write!(f, "Value must be an integer {0}.", UpperBound(max))
                 }
                 (None, None) => write!(f, "Value must be an integer."),
             },
             Self::Float { min, max } => match (min, max) {
                 (Some(min), Some(max)) => {
-                    write!(f, "Value must be a number {0} and {1}.", min, max)
+                    write!(
+                        f,
+                        "Value must be a number {0} and {1}.",
+                        min,
+                        UpperBound(max)
+                    )
                 }
                 (Some(min), None) => {
                     write!(f, "Value must be a number {0}.", min)
                 }
                 (None, Some(max)) => {
-                    write!(f, "Value must be a number {0}.", max)
+                    write!(f, "Value must be a number {0}.", UpperBound(max))
                 }
                 (None, None) => write!(f, "Value must be a number."),
             },
             Self::DateTime { format } => {
                 write!(f, "Must be a date time in the format `{0}`.", format)
             }
+            Self::Uuid => write!(f, "Value must be a UUID."),
+            Self::Cursor => write!(f, "Value must be a cursor returned by a previous page."),
             Self::String {
                 min_length,
                 max_length,
@@ -471,6 +598,20 @@ macro_rules! field_names {
                 }
             }
 
+            fn field_type(&self) -> $crate::field::FieldType {
+                match self {
+                    $(
+                        field_names!(@variant_match_arm(field) {
+                            $variant_name$( ( $sub_field_type ) )?
+                        })
+                        =>
+                        field_names!(@field_type(field) {
+                            $variant_name$( ( $sub_field_type ) )? => $($variant_args)+
+                        })
+                    ),+
+                }
+            }
+
             fn values() -> impl Iterator<Item = Self> {
                 static VALUES: once_cell::sync::Lazy<Vec<$type_name>> = once_cell::sync::Lazy::new(|| {
                     let mut values = Vec::new();
@@ -554,7 +695,7 @@ macro_rules! field_names {
 
     (
         @column($field:ident, $column_type:ty) {
-            $variant_name:ident => value: $value:literal
+            $variant_name:ident => value: $value:literal $(, kind: $($kind:tt)+)?
         }
     ) => {
         (<$column_type>::Table, <$column_type>::$variant_name).into_column_ref()
@@ -562,7 +703,7 @@ macro_rules! field_names {
 
     (
         @column($field:ident, $column_type:ty) {
-            $variant_name:ident => value: $value:literal, column: $column:ident
+            $variant_name:ident => value: $value:literal, column: $column:ident $(, kind: $($kind:tt)+)?
         }
     ) => {
         (<$column_type>::Table, <$column_type>::$column).into_column_ref()
@@ -576,6 +717,42 @@ macro_rules! field_names {
         $field.column()
     };
 
+    (
+        @field_type($field:ident) {
+            $variant_name:ident => value: $value:literal $(, column: $column:ident)?
+        }
+    ) => {
+        $crate::field::FieldType::String
+    };
+
+    (
+        @field_type($field:ident) {
+            $variant_name:ident => value: $value:literal $(, column: $column:ident)?, kind: Enum($enum_name:literal, $enum_type:ty)
+        }
+    ) => {
+        $crate::field::FieldType::Enum($enum_name, || {
+            <$enum_type as strum::IntoEnumIterator>::iter()
+                .map(|v| v.as_ref().to_owned())
+                .collect()
+        })
+    };
+
+    (
+        @field_type($field:ident) {
+            $variant_name:ident => value: $value:literal $(, column: $column:ident)?, kind: $kind:ident
+        }
+    ) => {
+        $crate::field::FieldType::$kind
+    };
+
+    (
+        @field_type($field:ident) {
+            $variant_name:ident($sub_field_type:ty) => $($rest:tt)+
+        }
+    ) => {
+        $field.field_type()
+    };
+
     (
         @append_values($vec:ident, $type_name:ident) $name:ident ()
     ) => {
@@ -615,7 +792,7 @@ macro_rules! field_names {
     (
         @from_str_cond($arg:ident) $name:ident ($sub_field_type:ty) => { prefix: $prefix:literal $($rest:tt)* }
     ) => {
-        $arg.starts_with($prefix)
+        $arg == $prefix || $arg.starts_with(concat!($prefix, "."))
     };
 
     (
@@ -628,11 +805,12 @@ macro_rules! field_names {
         @from_str_result($arg:ident) $name:ident($sub_field_type:ty) => { prefix: $prefix:literal $($rest:tt)* }
     ) => {
         {
-            let stripped = &$arg[$prefix.len()+1..];
-            Ok(Self::$name(if stripped.is_empty() {
+            // A bare prefix selects the sub-field's default, e.g. `save` sorts by `save.created_at`.
+            Ok(Self::$name(if $arg == $prefix {
                 <$sub_field_type>::default()
             } else {
-                <$sub_field_type>::from_str(stripped)?
+                <$sub_field_type>::from_str(&$arg[$prefix.len() + 1..])
+                    .map_err(|_| $crate::field::InvalidFieldError($arg.to_owned()))?
             }))
         }
     };
@@ -686,3 +864,112 @@ macro_rules! field_names {
         compile_error!("No enum variant tagged with #[default]")
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Field, FieldValue, Value};
+    use crate::{
+        change_feed::api::ChangeFields, game_save::api::SaveFields, item::api::ItemFields,
+        item_recipe::api::ItemRecipeFields, planet::api::PlanetFields,
+        planet_type::api::PlanetTypeFields, solar_system::api::SolarSystemFields,
+        star::api::StarFields,
+    };
+    use std::str::FromStr;
+
+    fn assert_round_trips<T: Field>() {
+        for field in T::values() {
+            let name = field.name();
+            let parsed = T::from_str(&name)
+                .unwrap_or_else(|_| panic!("`{name}` should parse"))
+                .name();
+            assert_eq!(parsed, name);
+
+            let upper = T::from_str(&name.to_ascii_uppercase())
+                .unwrap_or_else(|_| panic!("`{name}` should parse case-insensitively"))
+                .name();
+            assert_eq!(upper, name);
+        }
+    }
+
+    fn assert_rejected<T: Field>(names: &[&str]) {
+        for name in names {
+            assert!(T::from_str(name).is_err(), "`{name}` should be rejected");
+        }
+    }
+
+    #[test]
+    fn every_field_name_round_trips() {
+        assert_round_trips::<SaveFields>();
+        assert_round_trips::<SolarSystemFields>();
+        assert_round_trips::<StarFields>();
+        assert_round_trips::<PlanetFields>();
+        assert_round_trips::<PlanetTypeFields>();
+        assert_round_trips::<ItemFields>();
+        assert_round_trips::<ItemRecipeFields>();
+        assert_round_trips::<ChangeFields>();
+    }
+
+    #[test]
+    fn bare_prefix_selects_the_sub_field_default() {
+        assert_eq!(
+            SolarSystemFields::from_str("save").ok().map(|f| f.name()),
+            Some(format!("save.{0}", SaveFields::default().name()))
+        );
+        assert_eq!(
+            PlanetFields::from_str("solar_system")
+                .ok()
+                .map(|f| f.name()),
+            Some(format!(
+                "solar_system.{0}",
+                SolarSystemFields::default().name()
+            ))
+        );
+    }
+
+    #[test]
+    fn near_miss_prefixes_are_rejected() {
+        assert_rejected::<SolarSystemFields>(&[
+            "saves",
+            "saves.name",
+            "savename",
+            "save.",
+            "save.nam",
+            "save.save.name",
+            "sav.name",
+        ]);
+        assert_rejected::<PlanetFields>(&[
+            "solar_systems.name",
+            "solar_systemname",
+            "solar_system.",
+            "solar_system.save.nam",
+            "solar.name",
+        ]);
+        assert_rejected::<StarFields>(&["solar_systems", "solar_system.nam"]);
+        assert_rejected::<SaveFields>(&["", "name.", "names", "save.name"]);
+    }
+
+    #[test]
+    fn displayed_values_match_their_serialized_form() {
+        let values = [
+            Value::from(3.0f64),
+            Value::from(-2.5f64),
+            Value::from(0.1f64),
+            Value::from(1e-7f64),
+            Value::from(1e21f64),
+            Value::from(1.5f32),
+            Value::from(42i64),
+            Value::from(7u32),
+        ];
+        for value in values {
+            let serialized = serde_json::to_value(&value).unwrap().to_string();
+            assert_eq!(value.to_string(), serialized);
+
+            let field = FieldValue::new("luminosity", value);
+            let json = serde_json::to_value(&field).unwrap();
+            assert_eq!(
+                field.to_string(),
+                format!("{0} `{1}`", json["name"].as_str().unwrap(), json["value"])
+            );
+        }
+    }
+}
@@ -1,34 +1,257 @@
-use crate::field::Field;
-use crate::{field_names, game_save::domain};
-use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
+use crate::data::{MatchMode, PageRequest, PageRequestRaw};
+use crate::error::{TrackerError, Validator};
+use crate::field::{AllowedValues, Bound, Field, FieldValue};
+use crate::{
+    field_names,
+    game_save::domain,
+    star::SpectralClass,
+    utils::{
+        double_option, entity_tag, last_modified, parse_datetime, validate_name,
+        validate_search_term, WholeNumber, MAX_NAME_SEARCH_LENGTH,
+    },
+};
+use actix_web::{
+    body::BoxBody,
+    http::header::{ETag, LastModified},
+    HttpRequest, HttpResponse, Responder,
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-#[derive(Deserialize, Serialize)]
+/// Matches the `mining_speed_at_least_100` check constraint on `saves`.
+pub const MIN_MINING_SPEED: u32 = 100;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(deny_unknown_fields)]
 pub struct CreateGameSaveRequest {
     pub name: String,
     pub notes: Option<String>,
-    pub mining_speed: u32,
+    /// Also accepts integer-valued floats and whole-number strings
+    #[schema(value_type = u32)]
+    pub mining_speed: WholeNumber<u32>,
+    /// Allows several solar systems in the save to share a name
+    #[serde(default)]
+    pub allow_duplicate_names: bool,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(deny_unknown_fields)]
 pub struct UpdateGameSaveRequest {
+    /// The version the changes are based on; it must be the version `If-Match` names
+    pub version: i32,
     pub name: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
     pub notes: Option<Option<String>>,
-    pub mining_speed: Option<u32>,
+    #[schema(value_type = Option<u32>)]
+    pub mining_speed: Option<WholeNumber<u32>>,
+    /// Disabling fails while the save still holds duplicate solar system names
+    pub allow_duplicate_names: Option<bool>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 pub struct GameSave {
     pub id: Uuid,
     pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub version: i32,
     pub name: String,
     pub notes: Option<String>,
     pub mining_speed: u32,
+    #[serde(default)]
+    pub allow_duplicate_names: bool,
 }
 
-impl Responder for GameSave {
+impl CreateGameSaveRequest {
+    pub fn validate(&self, max_mining_speed: u32) -> Result<(), TrackerError> {
+        let mut validator = Validator::new();
+        validator.check(validate_name("name", &self.name)).check(
+            self.mining_speed
+                .check("mining_speed")
+                .and_then(|speed| validate_mining_speed(speed, max_mining_speed)),
+        );
+        validator.finish()
+    }
+}
+
+impl UpdateGameSaveRequest {
+    pub fn validate(&self, max_mining_speed: u32) -> Result<(), TrackerError> {
+        let mut validator = Validator::new();
+        if let Some(name) = &self.name {
+            validator.check(validate_name("name", name));
+        }
+
+        if let Some(mining_speed) = &self.mining_speed {
+            validator.check(
+                mining_speed
+                    .check("mining_speed")
+                    .and_then(|speed| validate_mining_speed(speed, max_mining_speed)),
+            );
+        }
+        validator.finish()
+    }
+}
+
+pub fn validate_mining_speed(mining_speed: u32, max_mining_speed: u32) -> Result<(), TrackerError> {
+    if mining_speed < MIN_MINING_SPEED || mining_speed > max_mining_speed {
+        return Err(TrackerError::invalid_field(
+            FieldValue::new("mining_speed", mining_speed),
+            AllowedValues::integer_between(
+                Bound::inclusive(MIN_MINING_SPEED),
+                Bound::inclusive(max_mining_speed),
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchRequestRaw {
+    #[serde(flatten)]
+    pub page_request: PageRequestRaw,
+    pub created_after: Option<String>,
+    pub created_after_inclusive: Option<String>,
+    pub created_before: Option<String>,
+    pub created_before_inclusive: Option<String>,
+    pub updated_after: Option<String>,
+    pub name: Option<String>,
+    pub match_mode: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchRequest {
+    pub page_request: PageRequest<SaveFields>,
+    pub created_after: Option<Bound>,
+    pub created_before: Option<Bound>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub name: Option<String>,
+    pub match_mode: MatchMode,
+}
+
+impl TryFrom<SearchRequestRaw> for SearchRequest {
+    type Error = TrackerError;
+
+    fn try_from(value: SearchRequestRaw) -> Result<Self, Self::Error> {
+        // Incremental sync results are ordered by modification time, which cursors can't encode.
+        if let (Some(_), Some(cursor)) = (&value.updated_after, &value.page_request.cursor) {
+            return Err(TrackerError::invalid_field(
+                FieldValue::new("cursor", cursor.as_str()),
+                AllowedValues::cursor(),
+            ));
+        }
+
+        if let Some(name) = &value.name {
+            validate_search_term("name", name, MAX_NAME_SEARCH_LENGTH)?;
+        }
+
+        Ok(Self {
+            page_request: PageRequest::try_from(value.page_request)?,
+            created_after: parse_datetime_bound(
+                "created_after",
+                value.created_after,
+                "created_after_inclusive",
+                value.created_after_inclusive,
+            )?,
+            created_before: parse_datetime_bound(
+                "created_before",
+                value.created_before,
+                "created_before_inclusive",
+                value.created_before_inclusive,
+            )?,
+            updated_after: parse_datetime("updated_after", value.updated_after)?,
+            name: value.name,
+            match_mode: MatchMode::try_from(value.match_mode)?,
+        })
+    }
+}
+
+fn parse_datetime_bound(
+    name: &str,
+    value: Option<String>,
+    inclusive_name: &str,
+    inclusive: Option<String>,
+) -> Result<Option<Bound>, TrackerError> {
+    let inclusive = match inclusive.as_deref() {
+        None | Some("true") => true,
+        Some("false") => false,
+        Some(other) => {
+            return Err(TrackerError::invalid_field(
+                FieldValue::new(inclusive_name, other),
+                AllowedValues::choice(["true", "false"]),
+            ))
+        }
+    };
+
+    Ok(parse_datetime(name, value)?.map(|datetime| {
+        if inclusive {
+            Bound::inclusive(datetime)
+        } else {
+            Bound::exclusive(datetime)
+        }
+    }))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteGameSaveRequestRaw {
+    /// When `true`, respond as the delete would, including any error, without deleting the save.
+    #[param(value_type = Option<bool>)]
+    pub dry_run: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DeleteGameSaveRequest {
+    pub dry_run: bool,
+}
+
+impl TryFrom<DeleteGameSaveRequestRaw> for DeleteGameSaveRequest {
+    type Error = TrackerError;
+
+    fn try_from(value: DeleteGameSaveRequestRaw) -> Result<Self, Self::Error> {
+        let dry_run = match value.dry_run.as_deref() {
+            None | Some("false") => false,
+            Some("true") => true,
+            Some(other) => {
+                return Err(TrackerError::invalid_field(
+                    FieldValue::new("dry_run", other),
+                    AllowedValues::choice(["true", "false"]),
+                ))
+            }
+        };
+
+        Ok(Self { dry_run })
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct DeleteGameSaveResponse {
+    pub solar_systems_deleted: u64,
+    pub stars_deleted: u64,
+    pub planets_deleted: u64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SaveStatsResponse {
+    pub solar_system_count: u64,
+    pub star_count: u64,
+    pub solar_systems_without_star: u64,
+    pub average_luminosity: Option<f64>,
+    pub average_radius: Option<f64>,
+    pub spectral_classes: Vec<SpectralClassStatsResponse>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SpectralClassStatsResponse {
+    pub spectral_class: SpectralClass,
+    pub star_count: u64,
+    pub average_luminosity: f64,
+    pub average_radius: f64,
+}
+
+impl Responder for SaveStatsResponse {
     type Body = BoxBody;
 
     fn respond_to(self, _: &HttpRequest) -> HttpResponse<Self::Body> {
@@ -36,24 +259,137 @@ impl Responder for GameSave {
     }
 }
 
+impl From<domain::SaveStats> for SaveStatsResponse {
+    fn from(value: domain::SaveStats) -> Self {
+        Self {
+            solar_system_count: value.solar_system_count,
+            star_count: value.star_count,
+            solar_systems_without_star: value.solar_systems_without_star,
+            average_luminosity: value.average_luminosity,
+            average_radius: value.average_radius,
+            spectral_classes: value
+                .spectral_classes
+                .into_iter()
+                .filter_map(|s| {
+                    Some(SpectralClassStatsResponse {
+                        spectral_class: s.spectral_class?,
+                        star_count: s.star_count as u64,
+                        average_luminosity: s.average_luminosity.unwrap_or_default(),
+                        average_radius: s.average_radius.unwrap_or_default(),
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Responder for DeleteGameSaveResponse {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
+
+impl From<domain::DeleteSummary> for DeleteGameSaveResponse {
+    fn from(value: domain::DeleteSummary) -> Self {
+        Self {
+            solar_systems_deleted: value.solar_systems_deleted,
+            stars_deleted: value.stars_deleted,
+            planets_deleted: value.planets_deleted,
+        }
+    }
+}
+
+impl Responder for GameSave {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok()
+            .insert_header(ETag(entity_tag(self.version)))
+            .insert_header(LastModified(last_modified(
+                self.created_at,
+                self.updated_at,
+            )))
+            .json(self)
+    }
+}
+
 impl From<domain::GameSave> for GameSave {
     fn from(value: domain::GameSave) -> Self {
         Self {
             id: value.id,
             created_at: value.created_at,
+            updated_at: value.updated_at,
+            version: value.version,
             name: value.name,
-            notes: None,
+            notes: value.notes,
             mining_speed: value.mining_speed,
+            allow_duplicate_names: value.allow_duplicate_names,
         }
     }
 }
 
 field_names!(
     SaveFields<domain::GameSaveColumns> {
-        Id => { value: "id" },
+        Id => { value: "id", kind: Uuid },
         #[default]
-        CreatedAt => { value: "created_at" },
+        CreatedAt => { value: "created_at", kind: DateTime },
+        UpdatedAt => { value: "updated_at", kind: DateTime },
         Name => { value: "name" },
         Notes => { value: "notes" }
     }
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MAX_MINING_SPEED: u32 = 1000;
+
+    #[test]
+    fn mining_speed_below_the_minimum_is_rejected() {
+        match validate_mining_speed(50, MAX_MINING_SPEED) {
+            Err(TrackerError::InvalidFieldValue(field, allowed_values)) => {
+                assert_eq!(field.name, "mining_speed");
+                assert_eq!(
+                    serde_json::to_value(allowed_values).unwrap(),
+                    json!({
+                        "type": "Integer",
+                        "min": { "value": MIN_MINING_SPEED, "inclusive": true },
+                        "max": { "value": MAX_MINING_SPEED, "inclusive": true },
+                    })
+                );
+            }
+            other => panic!("expected an invalid mining_speed, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn mining_speed_bounds_are_inclusive() {
+        assert!(validate_mining_speed(MIN_MINING_SPEED, MAX_MINING_SPEED).is_ok());
+        assert!(validate_mining_speed(MAX_MINING_SPEED, MAX_MINING_SPEED).is_ok());
+        assert!(validate_mining_speed(MAX_MINING_SPEED + 1, MAX_MINING_SPEED).is_err());
+    }
+
+    #[test]
+    fn misspelled_fields_are_rejected_by_name() {
+        let err = serde_json::from_value::<CreateGameSaveRequest>(
+            json!({ "nmae": "Sol", "mining_speed": 100 }),
+        )
+        .err()
+        .expect("a misspelled create field should be rejected");
+        assert!(err.to_string().contains("unknown field `nmae`"), "{err}");
+
+        let err = serde_json::from_value::<UpdateGameSaveRequest>(
+            json!({ "version": 0, "mining_sped": 200 }),
+        )
+        .err()
+        .expect("a misspelled update field should be rejected");
+        assert!(
+            err.to_string().contains("unknown field `mining_sped`"),
+            "{err}"
+        );
+    }
+}
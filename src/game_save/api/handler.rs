@@ -1,97 +1,768 @@
-use super::{CreateGameSaveRequest, GameSave, UpdateGameSaveRequest};
+use super::{
+    CreateGameSaveRequest, DeleteGameSaveRequest, DeleteGameSaveRequestRaw, DeleteGameSaveResponse,
+    GameSave, SaveStatsResponse, SearchRequest, SearchRequestRaw, UpdateGameSaveRequest,
+};
 use crate::{
-    data::{Page, PageRequest, PageRequestRaw},
-    error::Result,
+    auth::Owner,
+    data::{begin_update, query_values, with_current_version, Page, PageRequestRaw},
+    error::{log_entity_error, log_error, log_lookup_error, ObjectKind, Result},
     game_save::domain,
+    idempotency::Idempotency,
+    utils::{check_if_match, last_modified, normalize_name, not_modified, ApiResponse},
     AppState,
 };
-use actix_web::{delete, get, patch, post, web, HttpResponse};
-use log::error;
+use actix_web::{delete, get, patch, post, web, HttpRequest, HttpResponse, Responder};
 use uuid::Uuid;
 
+#[utoipa::path(
+    operation_id = "createSave",
+    tag = "saves",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "A client-chosen key; retrying with the same key returns the originally created resource"),
+    ),
+    request_body = CreateGameSaveRequest,
+    responses(
+        (status = 201, description = "The created save", body = GameSave),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 409, description = "A conflicting save already exists", body = ErrorResponse),
+    )
+)]
 #[post("/saves")]
 async fn create_handler(
+    owner: Owner,
+    req: HttpRequest,
     request: web::Json<CreateGameSaveRequest>,
     data: web::Data<AppState>,
-) -> Result<GameSave> {
-    let mut transaction = data.db.begin().await?;
-
-    let save = domain::GameSave::new(
-        request.name.clone(),
-        request.notes.clone(),
-        request.mining_speed,
-    );
-    let response = domain::create(&mut transaction, &save)
-        .await
-        .inspect_err(|err| error!("Failed to create save {}: {}", save.name, err))?;
+) -> Result<ApiResponse<GameSave>> {
+    request.validate(data.max_mining_speed)?;
 
+    let idempotency = Idempotency::from_request(&req, ObjectKind::Save, data.idempotency_key_ttl)?;
+    let mut transaction = data.db.begin().await?;
+    let response = match idempotency.lookup(&mut transaction).await? {
+        Some(id) => domain::lookup(&mut transaction, id).await?,
+        None => {
+            let save = domain::GameSave::new(
+                request.name.clone(),
+                request.notes.clone(),
+                request.mining_speed.value(),
+                request.allow_duplicate_names,
+                owner.id(),
+            );
+            let response =
+                domain::create(&mut transaction, &save)
+                    .await
+                    .inspect_err(log_entity_error(
+                        "Failed to create save",
+                        ObjectKind::Save,
+                        save.id,
+                    ))?;
+            idempotency.record(&mut transaction, response.id).await?;
+            response
+        }
+    };
     transaction.commit().await?;
-    Ok(response.into())
+    Ok(ApiResponse::created_versioned(
+        format!("/saves/{0}", response.id),
+        response.version,
+        GameSave::from(response),
+    ))
 }
 
+#[utoipa::path(
+    operation_id = "lookupSave",
+    tag = "saves",
+    params(
+        ("id" = Uuid, Path, description = "The save id"),
+        ("If-None-Match" = Option<String>, Header, description = "An ETag from a previous response"),
+        ("If-Modified-Since" = Option<String>, Header, description = "The Last-Modified date from a previous response; ignored when If-None-Match is present"),
+    ),
+    responses(
+        (status = 200, description = "The save", body = GameSave),
+        (status = 304, description = "The supplied If-None-Match ETag matches the current version, or the resource is unmodified since If-Modified-Since"),
+        (status = 400, description = "The id is not a valid UUID", body = ErrorResponse),
+        (status = 404, description = "The save does not exist", body = ErrorResponse),
+    )
+)]
 #[get("/saves/{id}")]
-async fn lookup_handler(path: web::Path<Uuid>, data: web::Data<AppState>) -> Result<GameSave> {
+async fn lookup_handler(
+    owner: Owner,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
     let mut transaction = data.db.begin().await?;
 
     let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
     let response = domain::lookup(&mut transaction, id)
         .await
-        .inspect_err(|err| error!("Failed to lookup save with id `{}`: {}", id, err))?;
+        .inspect_err(log_lookup_error(ObjectKind::Save, id))?;
 
     transaction.commit().await?;
-    Ok(response.into())
+    let response = GameSave::from(response);
+    Ok(not_modified(
+        &req,
+        response.version,
+        last_modified(response.created_at, response.updated_at),
+    )
+    .unwrap_or_else(|| response.respond_to(&req)))
 }
 
+#[utoipa::path(
+    operation_id = "deleteSave",
+    tag = "saves",
+    params(
+        ("id" = Uuid, Path, description = "The save id"),
+        DeleteGameSaveRequestRaw,
+    ),
+    responses(
+        (status = 200, description = "What was deleted along with the save, or would be for a dry run", body = DeleteGameSaveResponse),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 404, description = "The save does not exist", body = ErrorResponse),
+        (status = 409, description = "The save is still referenced; a dry run reports the same", body = ErrorResponse),
+    )
+)]
 #[delete("/saves/{id}")]
-async fn delete_handler(path: web::Path<Uuid>, data: web::Data<AppState>) -> Result<HttpResponse> {
+async fn delete_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    query: web::Query<DeleteGameSaveRequestRaw>,
+    data: web::Data<AppState>,
+) -> Result<DeleteGameSaveResponse> {
+    let delete_request = DeleteGameSaveRequest::try_from(query.into_inner())?;
     let mut transaction = data.db.begin().await?;
     let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
 
-    domain::delete(&mut transaction, id).await?;
-    transaction.commit().await?;
+    let summary = domain::delete(&mut transaction, id)
+        .await
+        .inspect_err(log_entity_error(
+            "Failed to delete save",
+            ObjectKind::Save,
+            id,
+        ))?;
 
-    Ok(HttpResponse::NoContent().finish())
+    if delete_request.dry_run {
+        transaction.rollback().await?;
+    } else {
+        transaction.commit().await?;
+    }
+
+    Ok(summary.into())
 }
 
+#[utoipa::path(
+    operation_id = "searchSaves",
+    tag = "saves",
+    params(
+        PageRequestRaw,
+        ("created_after" = Option<DateTime<Utc>>, Query, description = "Only include saves created at or after this time"),
+        ("created_after_inclusive" = Option<bool>, Query, description = "Whether `created_after` is inclusive"),
+        ("created_before" = Option<DateTime<Utc>>, Query, description = "Only include saves created at or before this time"),
+        ("created_before_inclusive" = Option<bool>, Query, description = "Whether `created_before` is inclusive"),
+        ("updated_after" = Option<DateTime<Utc>>, Query, description = "Only include saves created or updated after this time, ordered by that time"),
+        ("name" = Option<String>, Query, description = "Name to match"),
+        ("match_mode" = Option<String>, Query, description = "One of `start`, `prefix`, `contains` or `exact`"),
+        ("filter" = Option<Vec<String>>, Query, description = "Filters in the form `field:operator:value`"),
+    ),
+    responses(
+        (status = 200, description = "A page of saves", body = GameSavePage),
+        (status = 400, description = "The search parameters were invalid", body = ErrorResponse),
+    )
+)]
 #[get("/saves")]
 async fn search_handler(
-    query: web::Query<PageRequestRaw>,
+    owner: Owner,
+    req: HttpRequest,
+    query: web::Query<SearchRequestRaw>,
     data: web::Data<AppState>,
 ) -> Result<Page<GameSave>> {
     let mut transaction = data.db.begin().await?;
-    let page_params = PageRequest::try_from(query.into_inner())?;
+    let mut search_raw = query.into_inner();
+    search_raw.page_request.filters = query_values(req.query_string(), "filter")?;
+    search_raw.page_request.paging = data.paging;
+    let search_params = SearchRequest::try_from(search_raw)?;
 
-    let response = domain::search(&mut transaction, &page_params)
+    let response = domain::search(&mut transaction, owner, &search_params)
         .await
         .map(Page::convert)
-        .inspect_err(|err| error!("Failed to search for saves: {}", err))?;
+        .inspect_err(log_error("Failed to search for saves", ObjectKind::Save))?;
     transaction.commit().await?;
     Ok(response)
 }
 
+#[utoipa::path(
+    operation_id = "updateSave",
+    tag = "saves",
+    params(
+        ("id" = Uuid, Path, description = "The save id"),
+        ("If-Match" = String, Header, description = "The ETag of the current version"),
+    ),
+    request_body = UpdateGameSaveRequest,
+    responses(
+        (status = 200, description = "The updated save", body = GameSave),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 404, description = "The save does not exist", body = ErrorResponse),
+        (status = 409, description = "The save was concurrently modified or conflicts with an existing save", body = ErrorResponse),
+        (status = 412, description = "The save has been modified since it was fetched", body = ErrorResponse),
+        (status = 428, description = "The If-Match header is missing", body = ErrorResponse),
+    )
+)]
 #[patch("/saves/{id}")]
 async fn update_handler(
+    owner: Owner,
+    req: HttpRequest,
     path: web::Path<Uuid>,
     request: web::Json<UpdateGameSaveRequest>,
     data: web::Data<AppState>,
 ) -> Result<GameSave> {
-    let mut transaction = data.db.begin().await?;
+    request.validate(data.max_mining_speed)?;
+
+    let mut transaction = begin_update(&data.db, data.pessimistic_locking).await?;
     let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+
+    let mut save = with_current_version(
+        domain::lookup_for_update(&mut transaction, id).await,
+        &data.db,
+        (
+            domain::GameSaveColumns::Table,
+            domain::GameSaveColumns::Id,
+            domain::GameSaveColumns::Version,
+        ),
+        id,
+        Some(request.version),
+    )
+    .await?;
+    check_if_match(&req, ObjectKind::Save, id, save.version, request.version)?;
+    save.version = request.version;
 
-    let mut save = domain::lookup(&mut transaction, id).await?;
     if let Some(name) = &request.name {
-        save.name = name.clone();
+        save.name = normalize_name(name);
     }
 
-    if let Some(mining_speed) = request.mining_speed {
-        save.mining_speed = mining_speed;
+    if let Some(notes) = &request.notes {
+        save.notes = notes.clone();
     }
 
-    let response = domain::update(&mut transaction, &save)
-        .await
-        .map(GameSave::from)
-        .inspect_err(|err| error!("Failed to update save with id `{}`: {}", id, err))?;
+    if let Some(mining_speed) = &request.mining_speed {
+        save.mining_speed = mining_speed.value();
+    }
+
+    if let Some(allow_duplicate_names) = request.allow_duplicate_names {
+        save.allow_duplicate_names = allow_duplicate_names;
+    }
+
+    let response = with_current_version(
+        domain::update(&mut transaction, &save).await,
+        &data.db,
+        (
+            domain::GameSaveColumns::Table,
+            domain::GameSaveColumns::Id,
+            domain::GameSaveColumns::Version,
+        ),
+        id,
+        Some(save.version),
+    )
+    .await
+    .map(GameSave::from)
+    .inspect_err(log_entity_error(
+        "Failed to update save",
+        ObjectKind::Save,
+        id,
+    ))?;
 
     transaction.commit().await?;
     Ok(response)
 }
+
+#[utoipa::path(
+    operation_id = "saveStats",
+    tag = "saves",
+    params(
+        ("id" = Uuid, Path, description = "The save id"),
+    ),
+    responses(
+        (status = 200, description = "Summary statistics of the save's galaxy", body = SaveStatsResponse),
+        (status = 400, description = "The id is not a valid UUID", body = ErrorResponse),
+        (status = 404, description = "The save does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/saves/{id}/stats")]
+async fn stats_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<SaveStatsResponse> {
+    let mut transaction = data.db.begin().await?;
+
+    let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+    let response = domain::stats(&mut transaction, id)
+        .await
+        .inspect_err(log_entity_error(
+            "Failed to compute stats",
+            ObjectKind::Save,
+            id,
+        ))?;
+
+    transaction.commit().await?;
+    Ok(response.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{
+        app_state, assert_one_wins, create_save, create_solar_system, db_pool, send, test_app,
+        unique_name,
+    };
+    use actix_http::Request;
+    use actix_web::{
+        body::MessageBody,
+        dev::{Service, ServiceResponse},
+        http::StatusCode,
+        test,
+    };
+    use futures_util::future::join;
+    use serde_json::{json, Value};
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn concurrent_updates_of_the_same_version_conflict() {
+        let db = db_pool().await;
+
+        for pessimistic_locking in [false, true] {
+            let mut state = app_state(db.clone());
+            state.pessimistic_locking = pessimistic_locking;
+            let app = test::init_service(test_app(state)).await;
+
+            let save = create_save(&app).await;
+            let uri = format!("/api/1/saves/{0}", save["id"].as_str().unwrap());
+
+            let patch = |name: String| {
+                test::TestRequest::patch()
+                    .uri(&uri)
+                    .insert_header(("If-Match", "\"0\""))
+                    .set_json(json!({ "version": 0, "name": name }))
+                    .to_request()
+            };
+            let ((first, _), (second, _)) = join(
+                send(&app, patch(unique_name("First"))),
+                send(&app, patch(unique_name("Second"))),
+            )
+            .await;
+
+            assert_one_wins(first, second, pessimistic_locking);
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn if_match_is_required_and_must_name_the_stored_version() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let uri = format!("/api/1/saves/{0}", save["id"].as_str().unwrap());
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::patch()
+                .uri(&uri)
+                .set_json(json!({ "version": 0, "notes": "no header" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::PRECONDITION_REQUIRED);
+        assert_eq!(body["error_code"], "PreconditionRequired");
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::patch()
+                .uri(&uri)
+                .insert_header(("If-Match", "\"1\""))
+                .set_json(json!({ "version": 1, "notes": "wrong version" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+        assert_eq!(body["error_code"], "PreconditionFailed");
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::patch()
+                .uri(&uri)
+                .insert_header(("If-Match", "\"1\""))
+                .set_json(json!({ "version": 0, "notes": "disagrees with the header" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        assert_eq!(body["error_code"], "InvalidFieldValue");
+        assert_eq!(body["field"]["name"], "version");
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::patch()
+                .uri(&uri)
+                .insert_header(("If-Match", "\"0\""))
+                .set_json(json!({ "version": 0, "notes": "current" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["version"], 1);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn stale_update_reports_the_attempted_and_current_versions() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let id = save["id"].as_str().unwrap();
+        let uri = format!("/api/1/saves/{id}");
+
+        let (status, _) = send(
+            &app,
+            test::TestRequest::patch()
+                .uri(&uri)
+                .insert_header(("If-Match", "\"0\""))
+                .set_json(json!({ "version": 0, "notes": "first" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::patch()
+                .uri(&uri)
+                .insert_header(("If-Match", "\"0\""))
+                .set_json(json!({ "version": 0, "notes": "stale" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED, "{body}");
+        assert_eq!(body["error_code"], "PreconditionFailed");
+        assert_eq!(
+            body["keys"],
+            json!([
+                { "name": "id", "value": id },
+                { "name": "version", "value": 0 },
+                { "name": "current_version", "value": 1 },
+            ])
+        );
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn mining_speed_accepts_whole_numbers_in_any_json_form() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+
+        for mining_speed in [json!(200), json!(200.0), json!("200")] {
+            let (status, save) = send(
+                &app,
+                test::TestRequest::post()
+                    .uri("/api/1/saves")
+                    .set_json(json!({ "name": unique_name("Speed"), "mining_speed": mining_speed }))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED, "{mining_speed}: {save}");
+            assert_eq!(save["mining_speed"], 200);
+        }
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/1/saves")
+                .set_json(json!({ "name": unique_name("Speed"), "mining_speed": 200.5 }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error_code"], "InvalidFieldValue");
+        assert_eq!(body["field"]["name"], "mining_speed");
+    }
+
+    /// Follows `next_cursor` one save at a time and returns the saves named
+    /// `tag ...` in the order they were returned.
+    async fn saves_by_cursor<S, B>(app: &S, tag: &str, sorts: &str) -> Vec<Value>
+    where
+        S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody,
+    {
+        let mut saves = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut query = form_urlencoded::Serializer::new(String::new());
+            query
+                .append_pair("name", tag)
+                .append_pair("match_mode", "start")
+                .append_pair("sorts", sorts)
+                .append_pair("size", "1");
+            if let Some(cursor) = &cursor {
+                query.append_pair("cursor", cursor);
+            }
+
+            let (status, page) = send(
+                app,
+                test::TestRequest::get()
+                    .uri(&format!("/api/1/saves?{0}", query.finish()))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "sorts = {sorts}: {page}");
+            saves.extend(page["data"].as_array().unwrap().iter().cloned());
+            match page["metadata"]["next_cursor"].as_str() {
+                Some(next) => cursor = Some(next.to_owned()),
+                None => return saves,
+            }
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn cursor_paging_includes_null_sort_keys() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let tag = unique_name("Cursor");
+        let mut ids = Vec::new();
+        for (i, notes) in [json!("b"), Value::Null, json!("a"), Value::Null]
+            .into_iter()
+            .enumerate()
+        {
+            let (status, save) = send(
+                &app,
+                test::TestRequest::post()
+                    .uri("/api/1/saves")
+                    .set_json(json!({ "name": format!("{tag} {i}"), "notes": notes, "mining_speed": 200 }))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED, "{save}");
+            ids.push(save["id"].as_str().unwrap().to_owned());
+        }
+
+        let (a, b, null) = (json!("a"), json!("b"), Value::Null);
+        let expected = [
+            ("notes", [&a, &b, &null, &null]),
+            ("notes:desc", [&null, &null, &b, &a]),
+            ("notes:asc:nulls_first", [&null, &null, &a, &b]),
+            ("notes:desc:nulls_last", [&b, &a, &null, &null]),
+        ];
+        for (sorts, notes) in expected {
+            let saves = saves_by_cursor(&app, &tag, sorts).await;
+            let actual: Vec<&Value> = saves.iter().map(|save| &save["notes"]).collect();
+            assert_eq!(actual, notes, "sorts = {sorts}");
+        }
+
+        // No save has been updated, so every cursor key is null.
+        let saves = saves_by_cursor(&app, &tag, "updated_at").await;
+        let mut actual: Vec<&str> = saves.iter().map(|s| s["id"].as_str().unwrap()).collect();
+        actual.sort();
+        ids.sort();
+        assert_eq!(actual, ids);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn save_names_differing_in_trailing_whitespace_collide() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/1/saves")
+                .set_json(json!({
+                    "name": format!("{0} ", save["name"].as_str().unwrap()),
+                    "mining_speed": 200,
+                }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error_code"], "Duplicate");
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn dry_run_delete_agrees_with_the_delete() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let delete = |uri: &str, dry_run: bool| {
+            test::TestRequest::delete()
+                .uri(&format!("{uri}?dry_run={dry_run}"))
+                .to_request()
+        };
+
+        let referenced = create_save(&app).await;
+        let uri = format!("/api/1/saves/{0}", referenced["id"].as_str().unwrap());
+        create_solar_system(&app, referenced["id"].as_str().unwrap(), "Sol").await;
+        let (dry_status, dry_body) = send(&app, delete(&uri, true)).await;
+        let (status, body) = send(&app, delete(&uri, false)).await;
+        assert_eq!(dry_status, StatusCode::CONFLICT, "{dry_body}");
+        assert_eq!(status, dry_status);
+        assert_eq!(dry_body["error_code"], "DeleteRestricted");
+        assert_eq!(body["dependents"], dry_body["dependents"]);
+
+        let empty = create_save(&app).await;
+        let uri = format!("/api/1/saves/{0}", empty["id"].as_str().unwrap());
+        let (status, body) = send(&app, delete(&uri, true)).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["solar_systems_deleted"], 0);
+        let (status, _) = send(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(status, StatusCode::OK, "a dry run must not delete the save");
+        let (status, deleted) = send(&app, delete(&uri, false)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(deleted, body);
+
+        for dry_run in [true, false] {
+            let (status, body) = send(&app, delete(&uri, dry_run)).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "dry_run = {dry_run}");
+            assert_eq!(body["error_code"], "NotFound");
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn deleting_a_save_reports_what_was_removed() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let uri = format!("/api/1/saves/{0}", save["id"].as_str().unwrap());
+
+        let (status, body) = send(&app, test::TestRequest::delete().uri(&uri).to_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({ "solar_systems_deleted": 0, "stars_deleted": 0, "planets_deleted": 0 })
+        );
+
+        let (status, _) = send(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn notes_round_trip_from_create_to_lookup() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let (status, save) = send(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/1/saves")
+                .set_json(json!({
+                    "name": unique_name("Notes"),
+                    "notes": "Dyson sphere around the home star",
+                    "mining_speed": 200,
+                }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED, "{save}");
+
+        let (status, fetched) = send(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!("/api/1/saves/{0}", save["id"].as_str().unwrap()))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(fetched["notes"], "Dyson sphere around the home star");
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn notes_update_distinguishes_absent_null_and_value() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let (status, save) = send(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/1/saves")
+                .set_json(
+                    json!({ "name": unique_name("Notes"), "notes": "keep", "mining_speed": 200 }),
+                )
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED, "{save}");
+        let uri = format!("/api/1/saves/{0}", save["id"].as_str().unwrap());
+
+        let steps = [
+            (json!({ "mining_speed": 300 }), json!("keep")),
+            (json!({ "notes": null }), Value::Null),
+            (json!({ "notes": "x" }), json!("x")),
+        ];
+        for (version, (mut changes, expected_notes)) in steps.into_iter().enumerate() {
+            changes["version"] = json!(version);
+            let (status, updated) = send(
+                &app,
+                test::TestRequest::patch()
+                    .uri(&uri)
+                    .insert_header(("If-Match", format!("\"{version}\"")))
+                    .set_json(&changes)
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{changes}: {updated}");
+            assert_eq!(updated["notes"], expected_notes, "{changes}");
+
+            let (_, fetched) = send(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(fetched["notes"], expected_notes, "{changes}");
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn paging_through_equal_created_at_never_repeats_a_save() {
+        let db = db_pool().await;
+        let app = test::init_service(test_app(app_state(db.clone()))).await;
+        let tag = unique_name("Tied");
+        for i in 0..5 {
+            let (status, save) = send(
+                &app,
+                test::TestRequest::post()
+                    .uri("/api/1/saves")
+                    .set_json(json!({ "name": format!("{tag} {i}"), "mining_speed": 200 }))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED, "{save}");
+        }
+        sqlx::query("UPDATE saves SET created_at = '2024-01-01T00:00:00Z' WHERE name LIKE $1")
+            .bind(format!("{tag} %"))
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let mut ids = Vec::new();
+        for page in 1..=5 {
+            let mut query = form_urlencoded::Serializer::new(String::new());
+            query
+                .append_pair("name", &tag)
+                .append_pair("match_mode", "start")
+                .append_pair("sorts", "created_at")
+                .append_pair("size", "1")
+                .append_pair("page", &page.to_string());
+            let (status, body) = send(
+                &app,
+                test::TestRequest::get()
+                    .uri(&format!("/api/1/saves?{0}", query.finish()))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{body}");
+            ids.extend(
+                body["data"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|save| save["id"].as_str().unwrap().to_owned()),
+            );
+        }
+
+        let mut unique = ids.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(ids.len(), 5);
+        assert_eq!(unique.len(), 5, "{ids:?}");
+        assert_eq!(ids, unique, "ties should fall back to id order");
+    }
+}
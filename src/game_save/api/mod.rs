@@ -3,11 +3,35 @@ mod handler;
 
 use actix_web::web;
 pub use data::*;
+use utoipa::OpenApi;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(handler::create_handler)
         .service(handler::lookup_handler)
         .service(handler::search_handler)
         .service(handler::update_handler)
-        .service(handler::delete_handler);
+        .service(handler::delete_handler)
+        .service(handler::stats_handler);
 }
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        handler::create_handler,
+        handler::lookup_handler,
+        handler::search_handler,
+        handler::update_handler,
+        handler::delete_handler,
+        handler::stats_handler,
+    ),
+    components(schemas(
+        GameSave,
+        CreateGameSaveRequest,
+        UpdateGameSaveRequest,
+        DeleteGameSaveResponse,
+        SaveStatsResponse,
+        SpectralClassStatsResponse,
+        crate::data::GameSavePage,
+    ))
+)]
+pub struct ApiDoc;
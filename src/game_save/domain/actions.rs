@@ -1,11 +1,19 @@
-use super::data::{GameSave, GameSaveColumns};
-use crate::data::{Page, PageMetadata, PageRequest, Sort};
-use crate::error::{ObjectKind, Result, TrackerError};
-use crate::field::{Field, FieldValue};
-use crate::game_save::api::SaveFields;
-use sea_query::{Asterisk, Expr, Func, PostgresQueryBuilder, Query, SelectStatement};
+use super::data::{DeleteSummary, GameSave, GameSaveColumns, SaveStats, SpectralClassStats};
+use crate::auth::Owner;
+use crate::data::{add_sorts, count_rows, is_serialization_failure, paginate, Cursor, Page, Sort};
+use crate::error::{DependentCount, ObjectKind, Result, TrackerError};
+use crate::field::{AllowedValues, Bound, FieldValue};
+use crate::game_save::api::{SaveFields, SearchRequest, MIN_MINING_SPEED};
+use crate::planet::PlanetColumns;
+use crate::solar_system::{self, SolarSystemColumns};
+use crate::star::domain::StarColumns;
+use crate::utils::MAX_NAME_LENGTH;
+use sea_query::{
+    Alias, Asterisk, Expr, Func, LockType, Order, PostgresQueryBuilder, Query, SelectStatement,
+    SimpleExpr,
+};
 use sea_query_binder::SqlxBinder;
-use sqlx::{Postgres, Row, Transaction};
+use sqlx::{error::ErrorKind, Postgres, Transaction};
 use uuid::Uuid;
 
 pub async fn create<'a>(tx: &mut Transaction<'a, Postgres>, save: &GameSave) -> Result<GameSave> {
@@ -16,21 +24,27 @@ pub async fn create<'a>(tx: &mut Transaction<'a, Postgres>, save: &GameSave) ->
             GameSaveColumns::CreatedAt,
             GameSaveColumns::Version,
             GameSaveColumns::Name,
+            GameSaveColumns::Notes,
             GameSaveColumns::MiningSpeed,
+            GameSaveColumns::AllowDuplicateNames,
+            GameSaveColumns::OwnerId,
         ])
         .values_panic([
             save.id.into(),
-            Expr::current_timestamp().into(),
+            Expr::cust("clock_timestamp()"),
             save.version.into(),
             (&save.name).into(),
+            save.notes.as_deref().into(),
             save.mining_speed.into(),
+            save.allow_duplicate_names.into(),
+            save.owner_id.into(),
         ])
         .build_sqlx(PostgresQueryBuilder);
 
     sqlx::query_with(&sql, values.clone())
         .execute(&mut **tx)
         .await
-        .map_err(|err| map_constraint_errors(err, &save))?;
+        .map_err(|err| map_constraint_errors(err, save))?;
 
     lookup(tx, save.id)
         .await
@@ -47,7 +61,12 @@ pub async fn update<'a>(tx: &mut Transaction<'a, Postgres>, save: &GameSave) ->
                 Expr::col(GameSaveColumns::Version).add(1),
             ),
             (GameSaveColumns::Name, save.name.clone().into()),
+            (GameSaveColumns::Notes, save.notes.clone().into()),
             (GameSaveColumns::MiningSpeed, save.mining_speed.into()),
+            (
+                GameSaveColumns::AllowDuplicateNames,
+                save.allow_duplicate_names.into(),
+            ),
         ])
         .and_where(Expr::col(GameSaveColumns::Id).eq(save.id))
         .and_where(Expr::col(GameSaveColumns::Version).eq(save.version))
@@ -60,13 +79,14 @@ pub async fn update<'a>(tx: &mut Transaction<'a, Postgres>, save: &GameSave) ->
         .rows_affected();
 
     if rows_updated == 0 {
-        Err(TrackerError::concurrent_update(
+        return Err(TrackerError::concurrent_update(
             ObjectKind::Save,
             FieldValue::new(GameSaveColumns::Id, save.id),
-        ))
-    } else {
-        lookup(tx, save.id).await
+        ));
     }
+
+    solar_system::set_allow_duplicate_names(tx, save.id, save.allow_duplicate_names).await?;
+    lookup(tx, save.id).await
 }
 
 pub async fn lookup_optional<'a>(
@@ -85,6 +105,51 @@ pub async fn lookup_optional<'a>(
         .await?)
 }
 
+/// Fails with `NotFound` when the save belongs to another owner, so that the
+/// existence of other owners' saves isn't revealed.
+pub async fn check_owner<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    owner: Owner,
+    id: Uuid,
+) -> Result<()> {
+    let select_stmt = Query::select()
+        .expr(Expr::val(1))
+        .from(GameSaveColumns::Table)
+        .and_where(Expr::col((GameSaveColumns::Table, GameSaveColumns::Id)).eq(id))
+        .to_owned();
+
+    ensure_owned(tx, owner, select_stmt, || {
+        TrackerError::not_found(ObjectKind::Save, FieldValue::new(GameSaveColumns::Id, id))
+    })
+    .await
+}
+
+/// Ownership check shared with the descendants of a save; `select_stmt` must
+/// join `saves` and select the row being checked.
+pub async fn ensure_owned<'a, F>(
+    tx: &mut Transaction<'a, Postgres>,
+    owner: Owner,
+    mut select_stmt: SelectStatement,
+    not_found: F,
+) -> Result<()>
+where
+    F: FnOnce() -> TrackerError,
+{
+    let Some(owner_id) = owner.id() else {
+        return Ok(());
+    };
+
+    let (sql, values) = select_stmt
+        .and_where(Expr::col((GameSaveColumns::Table, GameSaveColumns::OwnerId)).eq(owner_id))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values.clone())
+        .fetch_optional(&mut **tx)
+        .await?
+        .map(|_| ())
+        .ok_or_else(not_found)
+}
+
 pub async fn lookup<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<GameSave> {
     lookup_optional(tx, id)
         .await
@@ -97,61 +162,302 @@ pub async fn lookup<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<
         })
 }
 
+pub async fn lookup_for_update<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    id: Uuid,
+) -> Result<GameSave> {
+    let (sql, values) = Query::select()
+        .column(Asterisk)
+        .from(GameSaveColumns::Table)
+        .and_where(Expr::col(GameSaveColumns::Id).eq(id))
+        .lock(LockType::Update)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_as_with::<_, GameSave, _>(&sql, values.clone())
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|err| {
+            if is_serialization_failure(&err) {
+                TrackerError::concurrent_update(
+                    ObjectKind::Save,
+                    FieldValue::new(GameSaveColumns::Id, id),
+                )
+            } else {
+                TrackerError::from(err)
+            }
+        })?
+        .ok_or_else(|| {
+            TrackerError::not_found(ObjectKind::Save, FieldValue::new(GameSaveColumns::Id, id))
+        })
+}
+
+pub async fn stats<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<SaveStats> {
+    lookup(tx, id).await?;
+
+    let (sql, values) = Query::select()
+        .column((StarColumns::Table, StarColumns::SpectralClass))
+        .expr_as(
+            Func::count(Expr::col((
+                SolarSystemColumns::Table,
+                SolarSystemColumns::Id,
+            ))),
+            Alias::new("solar_system_count"),
+        )
+        .expr_as(
+            Func::count(Expr::col((StarColumns::Table, StarColumns::Id))),
+            Alias::new("star_count"),
+        )
+        .expr_as(
+            Func::avg(Expr::col((StarColumns::Table, StarColumns::Luminosity))),
+            Alias::new("average_luminosity"),
+        )
+        .expr_as(
+            Func::avg(Expr::col((StarColumns::Table, StarColumns::Radius))),
+            Alias::new("average_radius"),
+        )
+        .from(SolarSystemColumns::Table)
+        .left_join(
+            StarColumns::Table,
+            Expr::col((StarColumns::Table, StarColumns::SolarSystemId))
+                .equals((SolarSystemColumns::Table, SolarSystemColumns::Id)),
+        )
+        .and_where(Expr::col((SolarSystemColumns::Table, SolarSystemColumns::SaveId)).eq(id))
+        .group_by_col((StarColumns::Table, StarColumns::SpectralClass))
+        .order_by((StarColumns::Table, StarColumns::SpectralClass), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let (without_star, spectral_classes): (Vec<SpectralClassStats>, Vec<SpectralClassStats>) =
+        sqlx::query_as_with::<_, SpectralClassStats, _>(&sql, values.clone())
+            .fetch_all(&mut **tx)
+            .await?
+            .into_iter()
+            .partition(|s| s.spectral_class.is_none());
+
+    let solar_systems_without_star: i64 = without_star.iter().map(|s| s.solar_system_count).sum();
+    let star_count: i64 = spectral_classes.iter().map(|s| s.star_count).sum();
+    let weighted_average = |value: fn(&SpectralClassStats) -> Option<f64>| {
+        (star_count > 0).then(|| {
+            spectral_classes
+                .iter()
+                .map(|s| value(s).unwrap_or_default() * s.star_count as f64)
+                .sum::<f64>()
+                / star_count as f64
+        })
+    };
+
+    Ok(SaveStats {
+        solar_system_count: (star_count + solar_systems_without_star) as u64,
+        star_count: star_count as u64,
+        solar_systems_without_star: solar_systems_without_star as u64,
+        average_luminosity: weighted_average(|s| s.average_luminosity),
+        average_radius: weighted_average(|s| s.average_radius),
+        spectral_classes,
+    })
+}
+
 pub async fn search<'a>(
     tx: &mut Transaction<'a, Postgres>,
-    page_params: &PageRequest<SaveFields>,
+    owner: Owner,
+    search_params: &SearchRequest,
 ) -> Result<Page<GameSave>> {
-    let (count_sql, count_values) = Query::select()
+    let page_params = &search_params.page_request;
+    let mut select_count_stmt = Query::select()
         .expr(Func::count(Expr::col(Asterisk)))
         .from(GameSaveColumns::Table)
-        .build_sqlx(PostgresQueryBuilder);
-
-    let total_results: i64 = sqlx::query_with(&count_sql, count_values.clone())
-        .fetch_one(&mut **tx)
-        .await?
-        .get(0);
+        .to_owned();
+    add_where_clause(&mut select_count_stmt, owner, search_params);
 
     let mut select_stmt = Query::select()
         .expr(Expr::col(Asterisk))
         .from(GameSaveColumns::Table)
-        .limit(page_params.size)
-        .offset(page_params.offset())
         .to_owned();
-    add_sorts(&mut select_stmt, &page_params.sorts);
-
-    let (sql, values) = select_stmt.build_sqlx(PostgresQueryBuilder);
+    add_where_clause(&mut select_stmt, owner, search_params);
+    if let Some(cursor_predicate) = page_params.cursor_predicate(GameSaveColumns::Id)? {
+        select_stmt.and_where(cursor_predicate);
+    }
+    if search_params.updated_after.is_some() {
+        select_stmt.order_by_expr(last_modified(), Order::Asc);
+    }
+    add_sorts(
+        &mut select_stmt,
+        &page_params.sorts,
+        (GameSaveColumns::Table, GameSaveColumns::Id),
+    );
 
-    Ok(sqlx::query_as_with::<_, GameSave, _>(&sql, values.clone())
-        .fetch_all(&mut **tx)
-        .await
-        .map(|result| {
-            Page::new(
-                result,
-                PageMetadata::new(page_params.page, page_params.size, total_results as u64),
-            )
-        })?)
+    let mut page = paginate(tx, select_count_stmt, select_stmt, page_params).await?;
+    let next_cursor = match search_params.updated_after {
+        Some(_) => None,
+        None => page_params.next_cursor(&page.data, cursor_for),
+    };
+    page.metadata = page.metadata.with_next_cursor(next_cursor);
+    Ok(page)
 }
 
-pub async fn delete<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<()> {
+/// Deletes the save and returns what was removed with it. A dry run calls
+/// this too and rolls back, so the preview can't diverge from the delete.
+pub async fn delete<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<DeleteSummary> {
+    let summary = count_dependents(tx, id).await?;
+
     let (sql, values) = Query::delete()
         .from_table(GameSaveColumns::Table)
         .and_where(Expr::col(GameSaveColumns::Id).eq(id))
         .build_sqlx(PostgresQueryBuilder);
 
-    sqlx::query_with(&sql, values.clone())
+    let rows_deleted = sqlx::query_with(&sql, values.clone())
         .execute(&mut **tx)
-        .await?;
-    Ok(())
+        .await
+        .map_err(|err| match &err {
+            sqlx::Error::Database(db_err) if db_err.kind() == ErrorKind::ForeignKeyViolation => {
+                TrackerError::delete_restricted(
+                    ObjectKind::Save,
+                    FieldValue::new(GameSaveColumns::Id, id),
+                    vec![
+                        DependentCount {
+                            object: ObjectKind::SolarSystem,
+                            count: summary.solar_systems_deleted,
+                        },
+                        DependentCount {
+                            object: ObjectKind::Star,
+                            count: summary.stars_deleted,
+                        },
+                        DependentCount {
+                            object: ObjectKind::Planet,
+                            count: summary.planets_deleted,
+                        },
+                    ],
+                )
+            }
+            _ => TrackerError::from(err),
+        })?
+        .rows_affected();
+
+    if rows_deleted == 0 {
+        return Err(TrackerError::not_found(
+            ObjectKind::Save,
+            FieldValue::new(GameSaveColumns::Id, id),
+        ));
+    }
+    Ok(summary)
+}
+
+/// Counts the rows that reference the save, any of which keeps it from being
+/// deleted.
+async fn count_dependents<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    id: Uuid,
+) -> Result<DeleteSummary> {
+    let solar_systems_deleted = count_rows(
+        tx,
+        Query::select()
+            .expr(Func::count(Expr::col(Asterisk)))
+            .from(SolarSystemColumns::Table)
+            .and_where(Expr::col(SolarSystemColumns::SaveId).eq(id))
+            .to_owned(),
+    )
+    .await?;
+
+    let stars_deleted = count_rows(
+        tx,
+        Query::select()
+            .expr(Func::count(Expr::col(Asterisk)))
+            .from(StarColumns::Table)
+            .inner_join(
+                SolarSystemColumns::Table,
+                Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Id))
+                    .equals((StarColumns::Table, StarColumns::SolarSystemId)),
+            )
+            .and_where(Expr::col((SolarSystemColumns::Table, SolarSystemColumns::SaveId)).eq(id))
+            .to_owned(),
+    )
+    .await?;
+
+    let planets_deleted = count_rows(
+        tx,
+        Query::select()
+            .expr(Func::count(Expr::col(Asterisk)))
+            .from(PlanetColumns::Table)
+            .inner_join(
+                SolarSystemColumns::Table,
+                Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Id))
+                    .equals((PlanetColumns::Table, PlanetColumns::SolarSystemId)),
+            )
+            .and_where(Expr::col((SolarSystemColumns::Table, SolarSystemColumns::SaveId)).eq(id))
+            .to_owned(),
+    )
+    .await?;
+
+    Ok(DeleteSummary {
+        solar_systems_deleted,
+        stars_deleted,
+        planets_deleted,
+    })
 }
 
-fn add_sorts(select_stmt: &mut SelectStatement, sorts: &[Sort<SaveFields>]) {
-    for sort in sorts {
-        select_stmt.order_by(sort.field.column(), sort.direction.into());
+fn add_where_clause(select_stmt: &mut SelectStatement, owner: Owner, req: &SearchRequest) {
+    if let Some(owner_id) = owner.id() {
+        select_stmt
+            .and_where(Expr::col((GameSaveColumns::Table, GameSaveColumns::OwnerId)).eq(owner_id));
+    }
+
+    for filter in &req.page_request.filters {
+        select_stmt.and_where(filter.predicate());
+    }
+
+    if let Some(bound) = &req.created_after {
+        let created_at = Expr::col((GameSaveColumns::Table, GameSaveColumns::CreatedAt));
+        select_stmt.and_where(if bound.inclusive {
+            created_at.gte(bound.value.clone())
+        } else {
+            created_at.gt(bound.value.clone())
+        });
+    }
+
+    if let Some(bound) = &req.created_before {
+        let created_at = Expr::col((GameSaveColumns::Table, GameSaveColumns::CreatedAt));
+        select_stmt.and_where(if bound.inclusive {
+            created_at.lte(bound.value.clone())
+        } else {
+            created_at.lt(bound.value.clone())
+        });
+    }
+
+    if let Some(updated_after) = req.updated_after {
+        select_stmt.and_where(Expr::expr(last_modified()).gt(updated_after));
+    }
+
+    if let Some(name) = &req.name {
+        select_stmt.and_where(
+            req.match_mode
+                .predicate((GameSaveColumns::Table, GameSaveColumns::Name), name),
+        );
     }
 }
 
+fn last_modified() -> SimpleExpr {
+    Func::coalesce([
+        Expr::col((GameSaveColumns::Table, GameSaveColumns::UpdatedAt)).into(),
+        Expr::col((GameSaveColumns::Table, GameSaveColumns::CreatedAt)).into(),
+    ])
+    .into()
+}
+
+fn cursor_for(save: &GameSave, sort: &Sort<SaveFields>) -> Cursor {
+    let key = match sort.field {
+        SaveFields::Id => Some(save.id.to_string()),
+        SaveFields::CreatedAt => Some(save.created_at.to_rfc3339()),
+        SaveFields::UpdatedAt => save.updated_at.map(|updated_at| updated_at.to_rfc3339()),
+        SaveFields::Name => Some(save.name.clone()),
+        SaveFields::Notes => save.notes.clone(),
+    };
+    Cursor::new(sort, key, save.id)
+}
+
 fn map_constraint_errors(err: sqlx::Error, save: &GameSave) -> TrackerError {
     match &err {
+        _ if is_serialization_failure(&err) => TrackerError::concurrent_update(
+            ObjectKind::Save,
+            FieldValue::new(GameSaveColumns::Id, save.id),
+        ),
         sqlx::Error::Database(db_err) => {
             if db_err.is_unique_violation() {
                 match db_err.constraint() {
@@ -165,6 +471,18 @@ fn map_constraint_errors(err: sqlx::Error, save: &GameSave) -> TrackerError {
                     ),
                     _ => TrackerError::from(err),
                 }
+            } else if db_err.kind() == ErrorKind::CheckViolation {
+                match db_err.constraint() {
+                    Some("saves_name_length") => TrackerError::invalid_field(
+                        FieldValue::new(GameSaveColumns::Name, &save.name),
+                        AllowedValues::string_len_between(1, MAX_NAME_LENGTH),
+                    ),
+                    Some("mining_speed_at_least_100") => TrackerError::invalid_field(
+                        FieldValue::new(GameSaveColumns::MiningSpeed, save.mining_speed),
+                        AllowedValues::integer_min(Bound::inclusive(MIN_MINING_SPEED)),
+                    ),
+                    _ => TrackerError::from(err),
+                }
             } else {
                 TrackerError::from(err)
             }
@@ -1,3 +1,4 @@
+use crate::{star::SpectralClass, utils::normalize_name};
 use chrono::{DateTime, Utc};
 use sea_query::Iden;
 use uuid::Uuid;
@@ -12,6 +13,34 @@ pub struct GameSave {
     pub notes: Option<String>,
     #[sqlx(try_from = "i32")]
     pub mining_speed: u32,
+    pub allow_duplicate_names: bool,
+    pub owner_id: Option<Uuid>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct DeleteSummary {
+    pub solar_systems_deleted: u64,
+    pub stars_deleted: u64,
+    pub planets_deleted: u64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct SpectralClassStats {
+    pub spectral_class: Option<SpectralClass>,
+    pub solar_system_count: i64,
+    pub star_count: i64,
+    pub average_luminosity: Option<f64>,
+    pub average_radius: Option<f64>,
+}
+
+#[derive(Debug)]
+pub struct SaveStats {
+    pub solar_system_count: u64,
+    pub star_count: u64,
+    pub solar_systems_without_star: u64,
+    pub average_luminosity: Option<f64>,
+    pub average_radius: Option<f64>,
+    pub spectral_classes: Vec<SpectralClassStats>,
 }
 
 #[derive(Debug, Copy, Clone, Iden)]
@@ -26,6 +55,8 @@ pub enum GameSaveColumns {
     Name,
     Notes,
     MiningSpeed,
+    AllowDuplicateNames,
+    OwnerId,
 }
 
 impl From<GameSaveColumns> for String {
@@ -35,15 +66,23 @@ impl From<GameSaveColumns> for String {
 }
 
 impl GameSave {
-    pub fn new(name: String, notes: Option<String>, mining_speed: u32) -> Self {
+    pub fn new(
+        name: String,
+        notes: Option<String>,
+        mining_speed: u32,
+        allow_duplicate_names: bool,
+        owner_id: Option<Uuid>,
+    ) -> Self {
         Self {
             id: Uuid::new_v4(),
             created_at: Utc::now(),
             updated_at: None,
             version: 0,
-            name,
+            name: normalize_name(&name),
             notes,
             mining_speed,
+            allow_duplicate_names,
+            owner_id,
         }
     }
 }
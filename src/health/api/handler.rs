@@ -0,0 +1,35 @@
+use super::{HealthStatus, LiveResponse, ReadyResponse};
+use crate::AppState;
+use actix_web::{get, web, HttpResponse};
+use log::error;
+
+#[get("/health/live")]
+async fn live_handler() -> HttpResponse {
+    HttpResponse::Ok().json(LiveResponse {
+        status: HealthStatus::Ok,
+    })
+}
+
+#[get("/health/ready")]
+async fn ready_handler(data: web::Data<AppState>) -> HttpResponse {
+    let result = sqlx::query("SELECT 1").execute(&data.db).await;
+
+    let status = match &result {
+        Ok(_) => HealthStatus::Ok,
+        Err(err) => {
+            error!("Readiness check failed: {}", err);
+            HealthStatus::Unavailable
+        }
+    };
+
+    let response = ReadyResponse {
+        status,
+        pool_size: data.db.size(),
+        idle_connections: data.db.num_idle(),
+    };
+
+    match result {
+        Ok(_) => HttpResponse::Ok().json(response),
+        Err(_) => HttpResponse::ServiceUnavailable().json(response),
+    }
+}
@@ -0,0 +1,10 @@
+mod data;
+mod handler;
+
+use actix_web::web;
+pub use data::*;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(handler::live_handler)
+        .service(handler::ready_handler);
+}
@@ -0,0 +1,182 @@
+use crate::auth::Owner;
+use crate::error::{ObjectKind, Result, TrackerError};
+use crate::field::{AllowedValues, FieldValue};
+use actix_web::{http::header::HeaderName, rt, HttpMessage, HttpRequest};
+use chrono::{DateTime, Duration, Utc};
+use log::{error, info};
+use sea_query::{Expr, Iden, OnConflict, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use uuid::Uuid;
+
+pub const IDEMPOTENCY_KEY_HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+const CLEANUP_INTERVAL_SECS: u64 = 60 * 60;
+
+#[derive(Debug, Copy, Clone, Iden)]
+enum IdempotencyKeyColumns {
+    #[iden(rename = "idempotency_keys")]
+    Table,
+    Endpoint,
+    Key,
+    ResourceId,
+    CreatedAt,
+}
+
+#[derive(Debug, Clone)]
+pub struct Idempotency {
+    object: ObjectKind,
+    endpoint: String,
+    key: Option<String>,
+    ttl: Duration,
+}
+
+impl Idempotency {
+    pub fn from_request(req: &HttpRequest, object: ObjectKind, ttl: Duration) -> Result<Self> {
+        let key = req
+            .headers()
+            .get(IDEMPOTENCY_KEY_HEADER)
+            .map(|value| {
+                value
+                    .to_str()
+                    .ok()
+                    .filter(|key| is_valid(key))
+                    .map(str::to_owned)
+                    .ok_or_else(|| {
+                        TrackerError::invalid_field(
+                            FieldValue::new(
+                                IDEMPOTENCY_KEY_HEADER.as_str(),
+                                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                            ),
+                            AllowedValues::string_len_between(1, MAX_IDEMPOTENCY_KEY_LEN),
+                        )
+                    })
+            })
+            .transpose()?;
+
+        // Keys are scoped per owner so that owners can't replay each other's requests.
+        let owner = req.extensions().get::<Owner>().and_then(Owner::id);
+        let endpoint = match owner {
+            Some(owner_id) => format!("{0} {1} {2}", owner_id, req.method(), req.path()),
+            None => format!("{0} {1}", req.method(), req.path()),
+        };
+
+        Ok(Self {
+            object,
+            endpoint,
+            key,
+            ttl,
+        })
+    }
+
+    fn expires_before(&self) -> DateTime<Utc> {
+        Utc::now() - self.ttl
+    }
+
+    pub async fn lookup<'a>(&self, tx: &mut Transaction<'a, Postgres>) -> Result<Option<Uuid>> {
+        let Some(key) = &self.key else {
+            return Ok(None);
+        };
+
+        let (sql, values) = Query::select()
+            .column(IdempotencyKeyColumns::ResourceId)
+            .from(IdempotencyKeyColumns::Table)
+            .and_where(Expr::col(IdempotencyKeyColumns::Endpoint).eq(&self.endpoint))
+            .and_where(Expr::col(IdempotencyKeyColumns::Key).eq(key))
+            .and_where(Expr::col(IdempotencyKeyColumns::CreatedAt).gte(self.expires_before()))
+            .build_sqlx(PostgresQueryBuilder);
+
+        Ok(sqlx::query_with(&sql, values.clone())
+            .fetch_optional(&mut **tx)
+            .await?
+            .map(|row| row.get(0)))
+    }
+
+    pub async fn record<'a>(
+        &self,
+        tx: &mut Transaction<'a, Postgres>,
+        resource_id: Uuid,
+    ) -> Result<()> {
+        let Some(key) = &self.key else {
+            return Ok(());
+        };
+
+        let (sql, values) = Query::insert()
+            .into_table(IdempotencyKeyColumns::Table)
+            .columns([
+                IdempotencyKeyColumns::Endpoint,
+                IdempotencyKeyColumns::Key,
+                IdempotencyKeyColumns::ResourceId,
+                IdempotencyKeyColumns::CreatedAt,
+            ])
+            .values_panic([
+                self.endpoint.as_str().into(),
+                key.as_str().into(),
+                resource_id.into(),
+                Expr::current_timestamp().into(),
+            ])
+            .on_conflict(
+                OnConflict::columns([IdempotencyKeyColumns::Endpoint, IdempotencyKeyColumns::Key])
+                    .update_columns([
+                        IdempotencyKeyColumns::ResourceId,
+                        IdempotencyKeyColumns::CreatedAt,
+                    ])
+                    .action_and_where(
+                        Expr::col((
+                            IdempotencyKeyColumns::Table,
+                            IdempotencyKeyColumns::CreatedAt,
+                        ))
+                        .lt(self.expires_before()),
+                    )
+                    .to_owned(),
+            )
+            .build_sqlx(PostgresQueryBuilder);
+
+        let rows_inserted = sqlx::query_with(&sql, values.clone())
+            .execute(&mut **tx)
+            .await?
+            .rows_affected();
+
+        if rows_inserted == 0 {
+            Err(TrackerError::concurrent_update(
+                self.object,
+                FieldValue::new(IDEMPOTENCY_KEY_HEADER.as_str(), key.as_str()),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn is_valid(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= MAX_IDEMPOTENCY_KEY_LEN
+        && key.chars().all(|c| c.is_ascii_graphic())
+}
+
+async fn delete_expired(pool: &PgPool, ttl: Duration) -> Result<u64> {
+    let (sql, values) = Query::delete()
+        .from_table(IdempotencyKeyColumns::Table)
+        .and_where(Expr::col(IdempotencyKeyColumns::CreatedAt).lt(Utc::now() - ttl))
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_with(&sql, values)
+        .execute(pool)
+        .await?
+        .rows_affected())
+}
+
+pub fn spawn_cleanup(pool: PgPool, ttl: Duration) {
+    rt::spawn(async move {
+        let mut interval =
+            rt::time::interval(std::time::Duration::from_secs(CLEANUP_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            match delete_expired(&pool, ttl).await {
+                Ok(0) => {}
+                Ok(deleted) => info!("Deleted {} expired idempotency keys", deleted),
+                Err(err) => error!("Failed to delete expired idempotency keys: {}", err),
+            }
+        }
+    });
+}
@@ -0,0 +1,171 @@
+use crate::{
+    data::{MatchMode, PageRequest, PageRequestRaw},
+    error::{TrackerError, Validator},
+    field::Field,
+    field_names,
+    item::domain::{self, ItemSubType, ItemType},
+    utils::{
+        double_option, entity_tag, last_modified, validate_finite_positive, validate_search_term,
+        validate_small_count, MAX_NAME_SEARCH_LENGTH,
+    },
+};
+use actix_web::{
+    body::BoxBody,
+    http::header::{ETag, LastModified},
+    HttpResponse, Responder,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Item {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub version: i32,
+    pub name: String,
+    pub item_type: ItemType,
+    pub item_sub_type: Option<ItemSubType>,
+    pub stack_size: u16,
+    pub production_multiplier: Option<f32>,
+    pub image_path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CreateItemRequest {
+    pub name: String,
+    pub item_type: ItemType,
+    pub item_sub_type: Option<ItemSubType>,
+    pub stack_size: u16,
+    pub production_multiplier: Option<f32>,
+    pub image_path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateItemRequest {
+    /// The version the changes are based on; it must be the version `If-Match` names
+    pub version: i32,
+    pub name: Option<String>,
+    pub item_type: Option<ItemType>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub item_sub_type: Option<Option<ItemSubType>>,
+    pub stack_size: Option<u16>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub production_multiplier: Option<Option<f32>>,
+    pub image_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchRequestRaw {
+    #[serde(flatten)]
+    pub page_request: PageRequestRaw,
+    pub name: Option<String>,
+    pub match_mode: Option<String>,
+    pub item_type: Option<ItemType>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchRequest {
+    pub page_request: PageRequest<ItemFields>,
+    pub name: Option<String>,
+    pub match_mode: MatchMode,
+    pub item_type: Option<ItemType>,
+}
+
+impl CreateItemRequest {
+    pub fn validate(&self) -> Result<(), TrackerError> {
+        let mut validator = Validator::new();
+        validator.check(validate_small_count("stack_size", self.stack_size));
+        if let Some(production_multiplier) = self.production_multiplier {
+            validator.check(validate_finite_positive(
+                "production_multiplier",
+                production_multiplier,
+            ));
+        }
+        validator.finish()
+    }
+}
+
+impl UpdateItemRequest {
+    pub fn validate(&self) -> Result<(), TrackerError> {
+        let mut validator = Validator::new();
+        if let Some(stack_size) = self.stack_size {
+            validator.check(validate_small_count("stack_size", stack_size));
+        }
+        if let Some(Some(production_multiplier)) = self.production_multiplier {
+            validator.check(validate_finite_positive(
+                "production_multiplier",
+                production_multiplier,
+            ));
+        }
+        validator.finish()
+    }
+}
+
+impl From<domain::Item> for Item {
+    fn from(value: domain::Item) -> Self {
+        Self {
+            id: value.id,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            version: value.version,
+            name: value.name,
+            item_type: value.item_type,
+            item_sub_type: value.item_sub_type,
+            stack_size: value.stack_size,
+            production_multiplier: value.production_multiplier,
+            image_path: value.image_path,
+        }
+    }
+}
+
+impl Responder for Item {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
+        HttpResponse::Ok()
+            .insert_header(ETag(entity_tag(self.version)))
+            .insert_header(LastModified(last_modified(
+                self.created_at,
+                self.updated_at,
+            )))
+            .json(self)
+    }
+}
+
+impl TryFrom<SearchRequestRaw> for SearchRequest {
+    type Error = TrackerError;
+
+    fn try_from(value: SearchRequestRaw) -> Result<Self, Self::Error> {
+        value.page_request.reject_cursor()?;
+
+        if let Some(name) = &value.name {
+            validate_search_term("name", name, MAX_NAME_SEARCH_LENGTH)?;
+        }
+
+        Ok(Self {
+            page_request: PageRequest::try_from(value.page_request)?,
+            name: value.name,
+            match_mode: MatchMode::try_from(value.match_mode)?,
+            item_type: value.item_type,
+        })
+    }
+}
+
+field_names!(
+    ItemFields<domain::ItemColumns> {
+        Id => { value: "id", column: Id, kind: Uuid },
+        CreatedAt => { value: "created_at", column: CreatedAt, kind: DateTime },
+        #[default]
+        Name => { value: "name", column: Name },
+        ItemType => { value: "item_type", column: ItemType, kind: Enum("item_type", ItemType) },
+        ItemSubType => { value: "item_sub_type", column: ItemSubType, kind: Enum("item_sub_type", ItemSubType) },
+        StackSize => { value: "stack_size", column: StackSize, kind: Integer }
+    }
+);
@@ -0,0 +1,242 @@
+use super::{CreateItemRequest, Item, UpdateItemRequest};
+use crate::item::api::{SearchRequest, SearchRequestRaw};
+use crate::item::domain;
+use crate::{
+    data::{begin_repeatable_read, with_current_version, Page, PageRequestRaw},
+    error::{log_entity_error, log_error, log_lookup_error, ObjectKind, Result},
+    idempotency::Idempotency,
+    utils::{check_if_match, last_modified, not_modified, ApiResponse},
+    AppState,
+};
+use actix_web::{delete, get, patch, post, web, HttpRequest, HttpResponse, Responder};
+use uuid::Uuid;
+
+#[utoipa::path(
+    operation_id = "createItem",
+    tag = "items",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "A client-chosen key; retrying with the same key returns the originally created resource"),
+    ),
+    request_body = CreateItemRequest,
+    responses(
+        (status = 201, description = "The created item", body = Item),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 409, description = "A conflicting item already exists", body = ErrorResponse),
+    )
+)]
+#[post("/items")]
+async fn create_handler(
+    req: HttpRequest,
+    request: web::Json<CreateItemRequest>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<Item>> {
+    request.validate()?;
+
+    let idempotency = Idempotency::from_request(&req, ObjectKind::Item, data.idempotency_key_ttl)?;
+    let mut transaction = data.db.begin().await?;
+    let response = match idempotency.lookup(&mut transaction).await? {
+        Some(id) => domain::lookup(&mut transaction, id).await?,
+        None => {
+            let item = domain::Item::new(
+                request.name.clone(),
+                request.item_type,
+                request.item_sub_type,
+                request.stack_size,
+                request.production_multiplier,
+                request.image_path.clone(),
+            );
+
+            let response =
+                domain::create(&mut transaction, &item)
+                    .await
+                    .inspect_err(log_entity_error(
+                        "Failed to create item",
+                        ObjectKind::Item,
+                        item.id,
+                    ))?;
+            idempotency.record(&mut transaction, response.id).await?;
+            response
+        }
+    };
+    transaction.commit().await?;
+
+    Ok(ApiResponse::created_versioned(
+        format!("/items/{0}", response.id),
+        response.version,
+        Item::from(response),
+    ))
+}
+
+#[utoipa::path(
+    operation_id = "lookupItem",
+    tag = "items",
+    params(
+        ("id" = Uuid, Path, description = "The item id"),
+        ("If-None-Match" = Option<String>, Header, description = "An ETag from a previous response"),
+        ("If-Modified-Since" = Option<String>, Header, description = "The Last-Modified date from a previous response; ignored when If-None-Match is present"),
+    ),
+    responses(
+        (status = 200, description = "The item", body = Item),
+        (status = 304, description = "The supplied If-None-Match ETag matches the current version, or the resource is unmodified since If-Modified-Since"),
+        (status = 400, description = "The id is not a valid UUID", body = ErrorResponse),
+        (status = 404, description = "The item does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/items/{id}")]
+async fn lookup_handler(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut transaction = data.db.begin().await?;
+
+    let id = path.into_inner();
+    let response = domain::lookup(&mut transaction, id)
+        .await
+        .inspect_err(log_lookup_error(ObjectKind::Item, id))
+        .map(Item::from)?;
+
+    transaction.commit().await?;
+    Ok(not_modified(
+        &req,
+        response.version,
+        last_modified(response.created_at, response.updated_at),
+    )
+    .unwrap_or_else(|| response.respond_to(&req)))
+}
+
+#[utoipa::path(
+    operation_id = "deleteItem",
+    tag = "items",
+    params(
+        ("id" = Uuid, Path, description = "The item id"),
+    ),
+    responses(
+        (status = 204, description = "The item was deleted"),
+        (status = 409, description = "The item is still used by item recipes", body = ErrorResponse),
+    )
+)]
+#[delete("/items/{id}")]
+async fn delete_handler(
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<()>> {
+    let mut transaction = data.db.begin().await?;
+    let id = path.into_inner();
+
+    domain::delete(&mut transaction, id).await?;
+    transaction.commit().await?;
+
+    Ok(ApiResponse::NoContent)
+}
+
+#[utoipa::path(
+    operation_id = "searchItems",
+    tag = "items",
+    params(
+        PageRequestRaw,
+        ("match_mode" = Option<String>, Query, description = "One of `start`, `prefix`, `contains` or `exact`"),
+        ("name" = Option<String>, Query, description = "Name to match"),
+        ("item_type" = Option<ItemType>, Query, description = "Only include items of this type"),
+    ),
+    responses(
+        (status = 200, description = "A page of items", body = ItemPage),
+        (status = 400, description = "The search parameters were invalid", body = ErrorResponse),
+    )
+)]
+#[get("/items")]
+async fn search_handler(
+    query: web::Query<SearchRequestRaw>,
+    data: web::Data<AppState>,
+) -> Result<Page<Item>> {
+    let mut transaction = data.db.begin().await?;
+    let mut search_raw = query.into_inner();
+    search_raw.page_request.paging = data.paging;
+    let search_params = SearchRequest::try_from(search_raw)?;
+
+    let response = domain::search(&mut transaction, &search_params)
+        .await
+        .map(Page::convert)
+        .inspect_err(log_error("Failed to search for items", ObjectKind::Item))?;
+    transaction.commit().await?;
+    Ok(response)
+}
+
+#[utoipa::path(
+    operation_id = "updateItem",
+    tag = "items",
+    params(
+        ("id" = Uuid, Path, description = "The item id"),
+        ("If-Match" = String, Header, description = "The ETag of the current version"),
+    ),
+    request_body = UpdateItemRequest,
+    responses(
+        (status = 200, description = "The updated item", body = Item),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 404, description = "The item does not exist", body = ErrorResponse),
+        (status = 409, description = "The item was concurrently modified or conflicts with an existing item", body = ErrorResponse),
+        (status = 412, description = "The item has been modified since it was fetched", body = ErrorResponse),
+        (status = 428, description = "The If-Match header is missing", body = ErrorResponse),
+    )
+)]
+#[patch("/items/{id}")]
+async fn update_handler(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    request: web::Json<UpdateItemRequest>,
+    data: web::Data<AppState>,
+) -> Result<Item> {
+    request.validate()?;
+
+    let mut transaction = begin_repeatable_read(&data.db).await?;
+    let id = path.into_inner();
+
+    let mut item = domain::lookup(&mut transaction, id).await?;
+    check_if_match(&req, ObjectKind::Item, id, item.version, request.version)?;
+    item.version = request.version;
+
+    if let Some(name) = &request.name {
+        item.name = name.clone();
+    }
+
+    if let Some(item_type) = request.item_type {
+        item.item_type = item_type;
+    }
+
+    if let Some(item_sub_type) = request.item_sub_type {
+        item.item_sub_type = item_sub_type;
+    }
+
+    if let Some(stack_size) = request.stack_size {
+        item.stack_size = stack_size;
+    }
+
+    if let Some(production_multiplier) = request.production_multiplier {
+        item.production_multiplier = production_multiplier;
+    }
+
+    if let Some(image_path) = &request.image_path {
+        item.image_path = image_path.clone();
+    }
+
+    let response = with_current_version(
+        domain::update(&mut transaction, &item).await,
+        &data.db,
+        (
+            domain::ItemColumns::Table,
+            domain::ItemColumns::Id,
+            domain::ItemColumns::Version,
+        ),
+        id,
+        Some(item.version),
+    )
+    .await
+    .inspect_err(log_entity_error(
+        "Failed to update item",
+        ObjectKind::Item,
+        id,
+    ))?;
+
+    transaction.commit().await?;
+    Ok(response.into())
+}
@@ -0,0 +1,233 @@
+use super::{Item, ItemColumns};
+use crate::{
+    data::{add_sorts, count_rows, is_serialization_failure, paginate, Page},
+    error::{DependentCount, ObjectKind, Result, TrackerError},
+    field::FieldValue,
+    item::api::SearchRequest,
+    item_recipe::{ItemRecipeInputColumns, ItemRecipeOutputColumns},
+};
+use sea_query::{
+    Alias, Asterisk, Expr, Func, PostgresQueryBuilder, Query, SelectStatement, SimpleExpr,
+    UnionType,
+};
+use sea_query_binder::SqlxBinder;
+use sqlx::{error::ErrorKind, Postgres, Transaction};
+use uuid::Uuid;
+
+pub async fn create<'a>(tx: &mut Transaction<'a, Postgres>, item: &Item) -> Result<Item> {
+    let (sql, values) = Query::insert()
+        .into_table(ItemColumns::Table)
+        .columns([
+            ItemColumns::Id,
+            ItemColumns::CreatedAt,
+            ItemColumns::Version,
+            ItemColumns::Name,
+            ItemColumns::ItemType,
+            ItemColumns::ItemSubType,
+            ItemColumns::StackSize,
+            ItemColumns::ProductionMultiplier,
+            ItemColumns::ImagePath,
+        ])
+        .values_panic([
+            item.id.into(),
+            Expr::current_timestamp().into(),
+            item.version.into(),
+            (&item.name).into(),
+            item_type_expr(item),
+            item_sub_type_expr(item),
+            item.stack_size.into(),
+            item.production_multiplier.into(),
+            (&item.image_path).into(),
+        ])
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| map_constraint_errors(err, item))?;
+
+    lookup(tx, item.id)
+        .await
+        .map_err(TrackerError::not_found_unexpected)
+}
+
+pub async fn update<'a>(tx: &mut Transaction<'a, Postgres>, item: &Item) -> Result<Item> {
+    let (sql, values) = Query::update()
+        .table(ItemColumns::Table)
+        .values([
+            (ItemColumns::UpdatedAt, Expr::current_timestamp().into()),
+            (ItemColumns::Version, Expr::col(ItemColumns::Version).add(1)),
+            (ItemColumns::Name, item.name.clone().into()),
+            (ItemColumns::ItemType, item_type_expr(item)),
+            (ItemColumns::ItemSubType, item_sub_type_expr(item)),
+            (ItemColumns::StackSize, item.stack_size.into()),
+            (
+                ItemColumns::ProductionMultiplier,
+                item.production_multiplier.into(),
+            ),
+            (ItemColumns::ImagePath, item.image_path.clone().into()),
+        ])
+        .and_where(Expr::col(ItemColumns::Id).eq(item.id))
+        .and_where(Expr::col(ItemColumns::Version).eq(item.version))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows_updated = sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| map_constraint_errors(err, item))?
+        .rows_affected();
+
+    if rows_updated == 0 {
+        Err(TrackerError::concurrent_update(
+            ObjectKind::Item,
+            FieldValue::new(ItemColumns::Id, item.id),
+        ))
+    } else {
+        lookup(tx, item.id).await
+    }
+}
+
+pub async fn lookup_optional<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    id: Uuid,
+) -> Result<Option<Item>> {
+    let (sql, values) = Query::select()
+        .column((Alias::new("item"), Asterisk))
+        .from_as(ItemColumns::Table, Alias::new("item"))
+        .and_where(Expr::col(ItemColumns::Id).eq(id))
+        .limit(1)
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_as_with::<_, Item, _>(&sql, values.clone())
+        .fetch_optional(&mut **tx)
+        .await?)
+}
+
+pub async fn lookup<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<Item> {
+    lookup_optional(tx, id)
+        .await
+        .transpose()
+        .unwrap_or_else(|| {
+            Err(TrackerError::not_found(
+                ObjectKind::Item,
+                FieldValue::new(ItemColumns::Id, id),
+            ))
+        })
+}
+
+pub async fn search<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    search_params: &SearchRequest,
+) -> Result<Page<Item>> {
+    let page_req = &search_params.page_request;
+
+    let mut select_count_stmt = Query::select()
+        .expr(Func::count(Expr::col(Asterisk)))
+        .from(ItemColumns::Table)
+        .to_owned();
+    add_where_clause(&mut select_count_stmt, search_params);
+
+    let mut select_stmt = Query::select()
+        .expr(Expr::col(Asterisk))
+        .from(ItemColumns::Table)
+        .to_owned();
+    add_where_clause(&mut select_stmt, search_params);
+    add_sorts(
+        &mut select_stmt,
+        &page_req.sorts,
+        (ItemColumns::Table, ItemColumns::Id),
+    );
+
+    paginate(tx, select_count_stmt, select_stmt, page_req).await
+}
+
+pub async fn delete<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<()> {
+    let recipes = count_rows(
+        tx,
+        Query::select()
+            .expr(Func::count_distinct(Expr::col(Alias::new("recipe_id"))))
+            .from_subquery(
+                Query::select()
+                    .column(ItemRecipeInputColumns::RecipeId)
+                    .from(ItemRecipeInputColumns::Table)
+                    .and_where(Expr::col(ItemRecipeInputColumns::ItemId).eq(id))
+                    .union(
+                        UnionType::All,
+                        Query::select()
+                            .column(ItemRecipeOutputColumns::RecipeId)
+                            .from(ItemRecipeOutputColumns::Table)
+                            .and_where(Expr::col(ItemRecipeOutputColumns::ItemId).eq(id))
+                            .to_owned(),
+                    )
+                    .to_owned(),
+                Alias::new("components"),
+            )
+            .to_owned(),
+    )
+    .await?;
+
+    let (sql, values) = Query::delete()
+        .from_table(ItemColumns::Table)
+        .and_where(Expr::col(ItemColumns::Id).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| match &err {
+            sqlx::Error::Database(db_err) if db_err.kind() == ErrorKind::ForeignKeyViolation => {
+                TrackerError::delete_restricted(
+                    ObjectKind::Item,
+                    FieldValue::new(ItemColumns::Id, id),
+                    vec![DependentCount {
+                        object: ObjectKind::ItemRecipe,
+                        count: recipes,
+                    }],
+                )
+            }
+            _ => TrackerError::from(err),
+        })?;
+    Ok(())
+}
+
+fn item_type_expr(item: &Item) -> SimpleExpr {
+    Expr::val(item.item_type.as_ref()).as_enum(Alias::new("item_type"))
+}
+
+fn item_sub_type_expr(item: &Item) -> SimpleExpr {
+    Expr::val(item.item_sub_type.as_ref().map(AsRef::<str>::as_ref))
+        .as_enum(Alias::new("item_sub_type"))
+}
+
+fn add_where_clause(select_stmt: &mut SelectStatement, req: &SearchRequest) {
+    if let Some(name) = &req.name {
+        select_stmt.and_where(req.match_mode.predicate(ItemColumns::Name, name));
+    }
+
+    if let Some(item_type) = req.item_type {
+        select_stmt.and_where(
+            Expr::col(ItemColumns::ItemType)
+                .eq(Expr::val(item_type.as_ref()).as_enum(Alias::new("item_type"))),
+        );
+    }
+}
+
+fn map_constraint_errors(err: sqlx::Error, item: &Item) -> TrackerError {
+    match &err {
+        _ if is_serialization_failure(&err) => TrackerError::concurrent_update(
+            ObjectKind::Item,
+            FieldValue::new(ItemColumns::Id, item.id),
+        ),
+        sqlx::Error::Database(db_err) => match (db_err.kind(), db_err.constraint()) {
+            (ErrorKind::UniqueViolation, Some("items_name_key")) => TrackerError::duplicate(
+                ObjectKind::Item,
+                FieldValue::new(ItemColumns::Name, &item.name),
+            ),
+            (ErrorKind::UniqueViolation, Some("items_pkey")) => {
+                TrackerError::duplicate(ObjectKind::Item, FieldValue::new(ItemColumns::Id, item.id))
+            }
+            _ => TrackerError::from(err),
+        },
+        _ => TrackerError::from(err),
+    }
+}
@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use sea_query::Iden;
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumIter};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Copy, Clone, sqlx::Type, AsRefStr, EnumIter, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "item_type", rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ItemType {
+    Component,
+    Building,
+}
+
+#[derive(Debug, Copy, Clone, sqlx::Type, AsRefStr, EnumIter, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "item_sub_type", rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ItemSubType {
+    CommonResource,
+    RareResource,
+    Miner,
+    AssemblingMachine,
+    ChemicalPlant,
+    MatrixLab,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Item {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub version: i32,
+    pub name: String,
+    pub item_type: ItemType,
+    pub item_sub_type: Option<ItemSubType>,
+    #[sqlx(try_from = "i16")]
+    pub stack_size: u16,
+    pub production_multiplier: Option<f32>,
+    pub image_path: String,
+}
+
+#[derive(Debug, Copy, Clone, Iden)]
+#[allow(dead_code)]
+pub enum ItemColumns {
+    #[iden(rename = "items")]
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Version,
+    Name,
+    ItemType,
+    ItemSubType,
+    StackSize,
+    ProductionMultiplier,
+    ImagePath,
+}
+
+impl Item {
+    pub fn new(
+        name: String,
+        item_type: ItemType,
+        item_sub_type: Option<ItemSubType>,
+        stack_size: u16,
+        production_multiplier: Option<f32>,
+        image_path: String,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: None,
+            version: 0,
+            name,
+            item_type,
+            item_sub_type,
+            stack_size,
+            production_multiplier,
+            image_path,
+        }
+    }
+}
+
+impl From<ItemColumns> for String {
+    fn from(value: ItemColumns) -> Self {
+        value.to_string()
+    }
+}
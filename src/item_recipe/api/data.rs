@@ -0,0 +1,307 @@
+use crate::{
+    data::{MatchMode, PageRequest, PageRequestRaw},
+    error::{TrackerError, Validator},
+    field::Field,
+    field_names,
+    item_recipe::domain,
+    utils::{
+        entity_tag, last_modified, validate_finite_positive, validate_search_term,
+        validate_small_count, MAX_NAME_SEARCH_LENGTH,
+    },
+};
+use actix_web::{
+    body::BoxBody,
+    http::header::{ETag, LastModified},
+    HttpResponse, Responder,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ItemRecipe {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub version: i32,
+    pub name: String,
+    pub craft_time_secs: f32,
+    pub inputs: Vec<ItemRecipeInput>,
+    pub outputs: Vec<ItemRecipeOutput>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ItemRecipeInput {
+    pub item_id: Uuid,
+    pub amount: u16,
+    #[serde(default)]
+    pub extra_products: bool,
+    #[serde(default)]
+    pub production_speedup: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ItemRecipeOutput {
+    pub item_id: Uuid,
+    pub amount: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ItemRecipeGraph {
+    pub id: Uuid,
+    pub version: i32,
+    pub name: String,
+    pub craft_time_secs: f32,
+    /// Empty for raw extraction recipes
+    pub inputs: Vec<ItemRecipeGraphInput>,
+    pub outputs: Vec<ItemRecipeGraphOutput>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ItemRecipeGraphInput {
+    pub item_id: Uuid,
+    pub item_name: String,
+    pub amount: u16,
+    pub extra_products: bool,
+    pub production_speedup: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ItemRecipeGraphOutput {
+    pub item_id: Uuid,
+    pub item_name: String,
+    pub amount: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CreateItemRecipeRequest {
+    pub name: String,
+    pub craft_time_secs: f32,
+    #[serde(default)]
+    pub inputs: Vec<ItemRecipeInput>,
+    pub outputs: Vec<ItemRecipeOutput>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateItemRecipeRequest {
+    /// The version the changes are based on; it must be the version `If-Match` names
+    pub version: i32,
+    pub name: Option<String>,
+    pub craft_time_secs: Option<f32>,
+    pub inputs: Option<Vec<ItemRecipeInput>>,
+    pub outputs: Option<Vec<ItemRecipeOutput>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchRequestRaw {
+    #[serde(flatten)]
+    pub page_request: PageRequestRaw,
+    pub name: Option<String>,
+    pub match_mode: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchRequest {
+    pub page_request: PageRequest<ItemRecipeFields>,
+    pub name: Option<String>,
+    pub match_mode: MatchMode,
+    /// Only recipes with this item among their outputs
+    pub output_item_id: Option<Uuid>,
+    /// Only recipes with this item among their inputs
+    pub input_item_id: Option<Uuid>,
+}
+
+impl CreateItemRecipeRequest {
+    pub fn validate(&self) -> Result<(), TrackerError> {
+        let mut validator = Validator::new();
+        validator.check(validate_finite_positive(
+            "craft_time_secs",
+            self.craft_time_secs,
+        ));
+        validate_component_amounts(&mut validator, &self.inputs, &self.outputs);
+        validator.finish()
+    }
+}
+
+impl UpdateItemRecipeRequest {
+    pub fn validate(&self) -> Result<(), TrackerError> {
+        let mut validator = Validator::new();
+        if let Some(craft_time_secs) = self.craft_time_secs {
+            validator.check(validate_finite_positive("craft_time_secs", craft_time_secs));
+        }
+        validate_component_amounts(
+            &mut validator,
+            self.inputs.as_deref().unwrap_or_default(),
+            self.outputs.as_deref().unwrap_or_default(),
+        );
+        validator.finish()
+    }
+}
+
+fn validate_component_amounts(
+    validator: &mut Validator,
+    inputs: &[ItemRecipeInput],
+    outputs: &[ItemRecipeOutput],
+) {
+    for input in inputs {
+        validator.check(validate_small_count("inputs.amount", input.amount));
+    }
+    for output in outputs {
+        validator.check(validate_small_count("outputs.amount", output.amount));
+    }
+}
+
+impl From<domain::ItemRecipe> for ItemRecipe {
+    fn from(value: domain::ItemRecipe) -> Self {
+        Self {
+            id: value.id,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            version: value.version,
+            name: value.name,
+            craft_time_secs: value.craft_time_secs,
+            inputs: value
+                .inputs
+                .into_iter()
+                .map(ItemRecipeInput::from)
+                .collect(),
+            outputs: value
+                .outputs
+                .into_iter()
+                .map(ItemRecipeOutput::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<domain::ItemRecipeInput> for ItemRecipeInput {
+    fn from(value: domain::ItemRecipeInput) -> Self {
+        Self {
+            item_id: value.item_id,
+            amount: value.amount,
+            extra_products: value.extra_products,
+            production_speedup: value.production_speedup,
+        }
+    }
+}
+
+impl From<domain::ItemRecipeOutput> for ItemRecipeOutput {
+    fn from(value: domain::ItemRecipeOutput) -> Self {
+        Self {
+            item_id: value.item_id,
+            amount: value.amount,
+        }
+    }
+}
+
+impl From<domain::ItemRecipeGraph> for ItemRecipeGraph {
+    fn from(value: domain::ItemRecipeGraph) -> Self {
+        Self {
+            id: value.recipe.id,
+            version: value.recipe.version,
+            name: value.recipe.name,
+            craft_time_secs: value.recipe.craft_time_secs,
+            inputs: value
+                .inputs
+                .into_iter()
+                .map(ItemRecipeGraphInput::from)
+                .collect(),
+            outputs: value
+                .outputs
+                .into_iter()
+                .map(ItemRecipeGraphOutput::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<domain::ItemRecipeGraphInput> for ItemRecipeGraphInput {
+    fn from(value: domain::ItemRecipeGraphInput) -> Self {
+        Self {
+            item_id: value.item_id,
+            item_name: value.item_name,
+            amount: value.amount,
+            extra_products: value.extra_products,
+            production_speedup: value.production_speedup,
+        }
+    }
+}
+
+impl From<domain::ItemRecipeGraphOutput> for ItemRecipeGraphOutput {
+    fn from(value: domain::ItemRecipeGraphOutput) -> Self {
+        Self {
+            item_id: value.item_id,
+            item_name: value.item_name,
+            amount: value.amount,
+        }
+    }
+}
+
+impl ItemRecipeInput {
+    pub fn to_domain(&self, recipe_id: Uuid) -> domain::ItemRecipeInput {
+        domain::ItemRecipeInput::new(
+            recipe_id,
+            self.item_id,
+            self.amount,
+            self.extra_products,
+            self.production_speedup,
+        )
+    }
+}
+
+impl ItemRecipeOutput {
+    pub fn to_domain(&self, recipe_id: Uuid) -> domain::ItemRecipeOutput {
+        domain::ItemRecipeOutput::new(recipe_id, self.item_id, self.amount)
+    }
+}
+
+impl Responder for ItemRecipe {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
+        HttpResponse::Ok()
+            .insert_header(ETag(entity_tag(self.version)))
+            .insert_header(LastModified(last_modified(
+                self.created_at,
+                self.updated_at,
+            )))
+            .json(self)
+    }
+}
+
+impl TryFrom<SearchRequestRaw> for SearchRequest {
+    type Error = TrackerError;
+
+    fn try_from(value: SearchRequestRaw) -> Result<Self, Self::Error> {
+        value.page_request.reject_cursor()?;
+
+        if let Some(name) = &value.name {
+            validate_search_term("name", name, MAX_NAME_SEARCH_LENGTH)?;
+        }
+
+        Ok(Self {
+            page_request: PageRequest::try_from(value.page_request)?,
+            name: value.name,
+            match_mode: MatchMode::try_from(value.match_mode)?,
+            output_item_id: None,
+            input_item_id: None,
+        })
+    }
+}
+
+field_names!(
+    ItemRecipeFields<domain::ItemRecipeColumns> {
+        Id => { value: "id", column: Id, kind: Uuid },
+        CreatedAt => { value: "created_at", column: CreatedAt, kind: DateTime },
+        #[default]
+        Name => { value: "name", column: Name },
+        CraftTimeSecs => { value: "craft_time_secs", column: CraftTimeSecs, kind: Float }
+    }
+);
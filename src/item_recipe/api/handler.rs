@@ -0,0 +1,392 @@
+use super::{CreateItemRecipeRequest, ItemRecipe, ItemRecipeGraph, UpdateItemRecipeRequest};
+use crate::item_recipe::api::{SearchRequest, SearchRequestRaw};
+use crate::item_recipe::domain;
+use crate::{
+    data::{begin_repeatable_read, with_current_version, Page, PageRequestRaw},
+    error::{log_entity_error, log_error, log_lookup_error, ObjectKind, Result},
+    idempotency::Idempotency,
+    item,
+    utils::{check_if_match, last_modified, not_modified, ApiResponse},
+    AppState,
+};
+use actix_web::{delete, get, patch, post, web, HttpRequest, HttpResponse, Responder};
+use uuid::Uuid;
+
+#[utoipa::path(
+    operation_id = "createItemRecipe",
+    tag = "item-recipes",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "A client-chosen key; retrying with the same key returns the originally created resource"),
+    ),
+    request_body = CreateItemRecipeRequest,
+    responses(
+        (status = 201, description = "The created item recipe", body = ItemRecipe),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 404, description = "A referenced item does not exist", body = ErrorResponse),
+        (status = 409, description = "A conflicting item recipe already exists", body = ErrorResponse),
+    )
+)]
+#[post("/item-recipes")]
+async fn create_handler(
+    req: HttpRequest,
+    request: web::Json<CreateItemRecipeRequest>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<ItemRecipe>> {
+    request.validate()?;
+
+    let idempotency =
+        Idempotency::from_request(&req, ObjectKind::ItemRecipe, data.idempotency_key_ttl)?;
+    let mut transaction = data.db.begin().await?;
+    let response = match idempotency.lookup(&mut transaction).await? {
+        Some(id) => domain::lookup(&mut transaction, id).await?,
+        None => {
+            let mut recipe = domain::ItemRecipe::new(request.name.clone(), request.craft_time_secs);
+            recipe.inputs = request
+                .inputs
+                .iter()
+                .map(|input| input.to_domain(recipe.id))
+                .collect();
+            recipe.outputs = request
+                .outputs
+                .iter()
+                .map(|output| output.to_domain(recipe.id))
+                .collect();
+
+            let response = domain::create(&mut transaction, &recipe)
+                .await
+                .inspect_err(log_entity_error(
+                    "Failed to create item recipe",
+                    ObjectKind::ItemRecipe,
+                    recipe.id,
+                ))?;
+            idempotency.record(&mut transaction, response.id).await?;
+            response
+        }
+    };
+    transaction.commit().await?;
+
+    Ok(ApiResponse::created_versioned(
+        format!("/item-recipes/{0}", response.id),
+        response.version,
+        ItemRecipe::from(response),
+    ))
+}
+
+#[utoipa::path(
+    operation_id = "lookupItemRecipe",
+    tag = "item-recipes",
+    params(
+        ("id" = Uuid, Path, description = "The item recipe id"),
+        ("If-None-Match" = Option<String>, Header, description = "An ETag from a previous response"),
+        ("If-Modified-Since" = Option<String>, Header, description = "The Last-Modified date from a previous response; ignored when If-None-Match is present"),
+    ),
+    responses(
+        (status = 200, description = "The item recipe", body = ItemRecipe),
+        (status = 304, description = "The supplied If-None-Match ETag matches the current version, or the resource is unmodified since If-Modified-Since"),
+        (status = 400, description = "The id is not a valid UUID", body = ErrorResponse),
+        (status = 404, description = "The item recipe does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/item-recipes/{id}")]
+async fn lookup_handler(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut transaction = data.db.begin().await?;
+
+    let id = path.into_inner();
+    let response = domain::lookup(&mut transaction, id)
+        .await
+        .inspect_err(log_lookup_error(ObjectKind::ItemRecipe, id))
+        .map(ItemRecipe::from)?;
+
+    transaction.commit().await?;
+    Ok(not_modified(
+        &req,
+        response.version,
+        last_modified(response.created_at, response.updated_at),
+    )
+    .unwrap_or_else(|| response.respond_to(&req)))
+}
+
+#[utoipa::path(
+    operation_id = "itemRecipeGraph",
+    tag = "item-recipes",
+    params(
+        ("id" = Uuid, Path, description = "The item recipe id"),
+    ),
+    responses(
+        (status = 200, description = "The item recipe with its input and output items resolved", body = ItemRecipeGraph),
+        (status = 400, description = "The id is not a valid UUID", body = ErrorResponse),
+        (status = 404, description = "The item recipe does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/item-recipes/{id}/graph")]
+async fn graph_handler(path: web::Path<Uuid>, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let mut transaction = data.db.begin().await?;
+
+    let id = path.into_inner();
+    let response = domain::graph(&mut transaction, id)
+        .await
+        .inspect_err(log_lookup_error(ObjectKind::ItemRecipe, id))
+        .map(ItemRecipeGraph::from)?;
+
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    operation_id = "deleteItemRecipe",
+    tag = "item-recipes",
+    params(
+        ("id" = Uuid, Path, description = "The item recipe id"),
+    ),
+    responses(
+        (status = 204, description = "The item recipe was deleted"),
+    )
+)]
+#[delete("/item-recipes/{id}")]
+async fn delete_handler(
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<()>> {
+    let mut transaction = data.db.begin().await?;
+    let id = path.into_inner();
+
+    domain::delete(&mut transaction, id).await?;
+    transaction.commit().await?;
+
+    Ok(ApiResponse::NoContent)
+}
+
+#[utoipa::path(
+    operation_id = "searchItemRecipes",
+    tag = "item-recipes",
+    params(
+        PageRequestRaw,
+        ("match_mode" = Option<String>, Query, description = "One of `start`, `prefix`, `contains` or `exact`"),
+        ("name" = Option<String>, Query, description = "Name to match"),
+    ),
+    responses(
+        (status = 200, description = "A page of item recipes", body = ItemRecipePage),
+        (status = 400, description = "The search parameters were invalid", body = ErrorResponse),
+    )
+)]
+#[get("/item-recipes")]
+async fn search_handler(
+    query: web::Query<SearchRequestRaw>,
+    data: web::Data<AppState>,
+) -> Result<Page<ItemRecipe>> {
+    let mut transaction = data.db.begin().await?;
+    let mut search_raw = query.into_inner();
+    search_raw.page_request.paging = data.paging;
+    let search_params = SearchRequest::try_from(search_raw)?;
+
+    let response = domain::search(&mut transaction, &search_params)
+        .await
+        .map(Page::convert)
+        .inspect_err(log_error(
+            "Failed to search for item recipes",
+            ObjectKind::ItemRecipe,
+        ))?;
+    transaction.commit().await?;
+    Ok(response)
+}
+
+#[utoipa::path(
+    operation_id = "itemProducedBy",
+    tag = "item-recipes",
+    params(
+        ("id" = Uuid, Path, description = "The item id"),
+        PageRequestRaw,
+        ("match_mode" = Option<String>, Query, description = "One of `start`, `prefix`, `contains` or `exact`"),
+        ("name" = Option<String>, Query, description = "Name to match"),
+    ),
+    responses(
+        (status = 200, description = "A page of item recipes that output the item", body = ItemRecipePage),
+        (status = 400, description = "The search parameters were invalid", body = ErrorResponse),
+        (status = 404, description = "The item does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/items/{id}/produced-by")]
+async fn produced_by_handler(
+    path: web::Path<Uuid>,
+    query: web::Query<SearchRequestRaw>,
+    data: web::Data<AppState>,
+) -> Result<Page<ItemRecipe>> {
+    let mut transaction = data.db.begin().await?;
+    let item_id = path.into_inner();
+    item::lookup(&mut transaction, item_id).await?;
+    let mut search_raw = query.into_inner();
+    search_raw.page_request.paging = data.paging;
+    let mut search_params = SearchRequest::try_from(search_raw)?;
+    search_params.output_item_id = Some(item_id);
+
+    let response = domain::search(&mut transaction, &search_params)
+        .await
+        .map(Page::convert)
+        .inspect_err(log_entity_error(
+            "Failed to search for recipes producing item",
+            ObjectKind::Item,
+            item_id,
+        ))?;
+    transaction.commit().await?;
+    Ok(response)
+}
+
+#[utoipa::path(
+    operation_id = "itemConsumedBy",
+    tag = "item-recipes",
+    params(
+        ("id" = Uuid, Path, description = "The item id"),
+        PageRequestRaw,
+        ("match_mode" = Option<String>, Query, description = "One of `start`, `prefix`, `contains` or `exact`"),
+        ("name" = Option<String>, Query, description = "Name to match"),
+    ),
+    responses(
+        (status = 200, description = "A page of item recipes that take the item as an input", body = ItemRecipePage),
+        (status = 400, description = "The search parameters were invalid", body = ErrorResponse),
+        (status = 404, description = "The item does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/items/{id}/consumed-by")]
+async fn consumed_by_handler(
+    path: web::Path<Uuid>,
+    query: web::Query<SearchRequestRaw>,
+    data: web::Data<AppState>,
+) -> Result<Page<ItemRecipe>> {
+    let mut transaction = data.db.begin().await?;
+    let item_id = path.into_inner();
+    item::lookup(&mut transaction, item_id).await?;
+    let mut search_raw = query.into_inner();
+    search_raw.page_request.paging = data.paging;
+    let mut search_params = SearchRequest::try_from(search_raw)?;
+    search_params.input_item_id = Some(item_id);
+
+    let response = domain::search(&mut transaction, &search_params)
+        .await
+        .map(Page::convert)
+        .inspect_err(log_entity_error(
+            "Failed to search for recipes consuming item",
+            ObjectKind::Item,
+            item_id,
+        ))?;
+    transaction.commit().await?;
+    Ok(response)
+}
+
+#[utoipa::path(
+    operation_id = "updateItemRecipe",
+    tag = "item-recipes",
+    params(
+        ("id" = Uuid, Path, description = "The item recipe id"),
+        ("If-Match" = String, Header, description = "The ETag of the current version"),
+    ),
+    request_body = UpdateItemRecipeRequest,
+    responses(
+        (status = 200, description = "The updated item recipe", body = ItemRecipe),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 404, description = "The item recipe does not exist", body = ErrorResponse),
+        (status = 409, description = "The item recipe was concurrently modified or conflicts with an existing item recipe", body = ErrorResponse),
+        (status = 412, description = "The item recipe has been modified since it was fetched", body = ErrorResponse),
+        (status = 428, description = "The If-Match header is missing", body = ErrorResponse),
+    )
+)]
+#[patch("/item-recipes/{id}")]
+async fn update_handler(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    request: web::Json<UpdateItemRecipeRequest>,
+    data: web::Data<AppState>,
+) -> Result<ItemRecipe> {
+    request.validate()?;
+
+    let mut transaction = begin_repeatable_read(&data.db).await?;
+    let id = path.into_inner();
+
+    let mut recipe = domain::lookup(&mut transaction, id).await?;
+    check_if_match(
+        &req,
+        ObjectKind::ItemRecipe,
+        id,
+        recipe.version,
+        request.version,
+    )?;
+    recipe.version = request.version;
+
+    if let Some(name) = &request.name {
+        recipe.name = name.clone();
+    }
+
+    if let Some(craft_time_secs) = request.craft_time_secs {
+        recipe.craft_time_secs = craft_time_secs;
+    }
+
+    if let Some(inputs) = &request.inputs {
+        recipe.inputs = inputs.iter().map(|input| input.to_domain(id)).collect();
+    }
+
+    if let Some(outputs) = &request.outputs {
+        recipe.outputs = outputs.iter().map(|output| output.to_domain(id)).collect();
+    }
+
+    let response = with_current_version(
+        domain::update(&mut transaction, &recipe).await,
+        &data.db,
+        (
+            domain::ItemRecipeColumns::Table,
+            domain::ItemRecipeColumns::Id,
+            domain::ItemRecipeColumns::Version,
+        ),
+        id,
+        Some(recipe.version),
+    )
+    .await
+    .inspect_err(log_entity_error(
+        "Failed to update item recipe",
+        ObjectKind::ItemRecipe,
+        id,
+    ))?;
+
+    transaction.commit().await?;
+    Ok(response.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{app_state, db_pool, send, test_app, unique_name};
+    use actix_web::{http::StatusCode, test};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn repeated_component_items_are_invalid() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let item_id = Uuid::new_v4();
+        let create = |inputs, outputs| {
+            test::TestRequest::post()
+                .uri("/api/1/item-recipes")
+                .set_json(json!({
+                    "name": unique_name("Recipe"),
+                    "craft_time_secs": 1.0,
+                    "inputs": inputs,
+                    "outputs": outputs,
+                }))
+                .to_request()
+        };
+        let component = json!({ "item_id": item_id, "amount": 1 });
+
+        for (inputs, outputs, field) in [
+            (json!([component, component]), json!([]), "inputs.item_id"),
+            (json!([]), json!([component, component]), "outputs.item_id"),
+        ] {
+            let (status, body) = send(&app, create(inputs, outputs)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+            assert_eq!(body["error_code"], "InvalidFieldValue");
+            assert_eq!(body["field"]["name"], field);
+            assert_eq!(body["field"]["value"], item_id.to_string());
+        }
+    }
+}
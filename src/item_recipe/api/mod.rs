@@ -0,0 +1,43 @@
+mod data;
+mod handler;
+
+use actix_web::web;
+pub use data::*;
+use utoipa::OpenApi;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(handler::create_handler)
+        .service(handler::lookup_handler)
+        .service(handler::graph_handler)
+        .service(handler::search_handler)
+        .service(handler::produced_by_handler)
+        .service(handler::consumed_by_handler)
+        .service(handler::delete_handler)
+        .service(handler::update_handler);
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        handler::create_handler,
+        handler::lookup_handler,
+        handler::graph_handler,
+        handler::search_handler,
+        handler::produced_by_handler,
+        handler::consumed_by_handler,
+        handler::update_handler,
+        handler::delete_handler,
+    ),
+    components(schemas(
+        ItemRecipe,
+        ItemRecipeInput,
+        ItemRecipeOutput,
+        ItemRecipeGraph,
+        ItemRecipeGraphInput,
+        ItemRecipeGraphOutput,
+        CreateItemRecipeRequest,
+        UpdateItemRecipeRequest,
+        crate::data::ItemRecipePage,
+    ))
+)]
+pub struct ApiDoc;
@@ -0,0 +1,502 @@
+use super::{
+    ItemRecipe, ItemRecipeColumns, ItemRecipeGraph, ItemRecipeGraphInput, ItemRecipeGraphOutput,
+    ItemRecipeInput, ItemRecipeInputColumns, ItemRecipeOutput, ItemRecipeOutputColumns,
+};
+use crate::{
+    data::{add_sorts, is_serialization_failure, paginate, Page},
+    error::{ObjectKind, Result, TrackerError},
+    field::{AllowedValues, FieldValue},
+    item::ItemColumns,
+    item_recipe::api::SearchRequest,
+};
+use sea_query::{Alias, Asterisk, Expr, Func, Order, PostgresQueryBuilder, Query, SelectStatement};
+use sea_query_binder::SqlxBinder;
+use sqlx::{error::ErrorKind, Postgres, Row, Transaction};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+pub async fn create<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    recipe: &ItemRecipe,
+) -> Result<ItemRecipe> {
+    let (sql, values) = Query::insert()
+        .into_table(ItemRecipeColumns::Table)
+        .columns([
+            ItemRecipeColumns::Id,
+            ItemRecipeColumns::CreatedAt,
+            ItemRecipeColumns::Version,
+            ItemRecipeColumns::Name,
+            ItemRecipeColumns::CraftTimeSecs,
+        ])
+        .values_panic([
+            recipe.id.into(),
+            Expr::cust("clock_timestamp()"),
+            recipe.version.into(),
+            (&recipe.name).into(),
+            recipe.craft_time_secs.into(),
+        ])
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| map_constraint_errors(err, recipe))?;
+    replace_components(tx, recipe).await?;
+
+    lookup(tx, recipe.id)
+        .await
+        .map_err(TrackerError::not_found_unexpected)
+}
+
+pub async fn update<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    recipe: &ItemRecipe,
+) -> Result<ItemRecipe> {
+    let (sql, values) = Query::update()
+        .table(ItemRecipeColumns::Table)
+        .values([
+            (
+                ItemRecipeColumns::UpdatedAt,
+                Expr::cust("clock_timestamp()"),
+            ),
+            (
+                ItemRecipeColumns::Version,
+                Expr::col(ItemRecipeColumns::Version).add(1),
+            ),
+            (ItemRecipeColumns::Name, recipe.name.clone().into()),
+            (
+                ItemRecipeColumns::CraftTimeSecs,
+                recipe.craft_time_secs.into(),
+            ),
+        ])
+        .and_where(Expr::col(ItemRecipeColumns::Id).eq(recipe.id))
+        .and_where(Expr::col(ItemRecipeColumns::Version).eq(recipe.version))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows_updated = sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| map_constraint_errors(err, recipe))?
+        .rows_affected();
+
+    if rows_updated == 0 {
+        Err(TrackerError::concurrent_update(
+            ObjectKind::ItemRecipe,
+            FieldValue::new(ItemRecipeColumns::Id, recipe.id),
+        ))
+    } else {
+        replace_components(tx, recipe).await?;
+        lookup(tx, recipe.id).await
+    }
+}
+
+pub async fn lookup_optional<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    id: Uuid,
+) -> Result<Option<ItemRecipe>> {
+    let (sql, values) = Query::select()
+        .column((Alias::new("item_recipe"), Asterisk))
+        .from_as(ItemRecipeColumns::Table, Alias::new("item_recipe"))
+        .and_where(Expr::col(ItemRecipeColumns::Id).eq(id))
+        .limit(1)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let recipe = sqlx::query_as_with::<_, ItemRecipe, _>(&sql, values.clone())
+        .fetch_optional(&mut **tx)
+        .await?;
+
+    match recipe {
+        Some(recipe) => {
+            let mut recipes = [recipe];
+            load_components(tx, &mut recipes).await?;
+            let [recipe] = recipes;
+            Ok(Some(recipe))
+        }
+        None => Ok(None),
+    }
+}
+
+pub async fn lookup<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<ItemRecipe> {
+    lookup_optional(tx, id)
+        .await
+        .transpose()
+        .unwrap_or_else(|| {
+            Err(TrackerError::not_found(
+                ObjectKind::ItemRecipe,
+                FieldValue::new(ItemRecipeColumns::Id, id),
+            ))
+        })
+}
+
+pub async fn search<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    search_params: &SearchRequest,
+) -> Result<Page<ItemRecipe>> {
+    let page_req = &search_params.page_request;
+
+    let mut select_count_stmt = Query::select()
+        .expr(Func::count(Expr::col(Asterisk)))
+        .from(ItemRecipeColumns::Table)
+        .to_owned();
+    add_where_clause(&mut select_count_stmt, search_params);
+
+    let mut select_stmt = Query::select()
+        .expr(Expr::col(Asterisk))
+        .from(ItemRecipeColumns::Table)
+        .to_owned();
+    add_where_clause(&mut select_stmt, search_params);
+    add_sorts(
+        &mut select_stmt,
+        &page_req.sorts,
+        (ItemRecipeColumns::Table, ItemRecipeColumns::Id),
+    );
+
+    let mut page = paginate(tx, select_count_stmt, select_stmt, page_req).await?;
+    load_components(tx, &mut page.data).await?;
+    Ok(page)
+}
+
+pub async fn delete<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<()> {
+    let (sql, values) = Query::delete()
+        .from_table(ItemRecipeColumns::Table)
+        .and_where(Expr::col(ItemRecipeColumns::Id).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Resolves the items a recipe consumes and produces. Raw extraction recipes
+/// have no inputs, which leaves `inputs` empty.
+pub async fn graph<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<ItemRecipeGraph> {
+    let recipe = lookup(tx, id).await?;
+
+    let (inputs_sql, inputs_values) = Query::select()
+        .columns([
+            (
+                ItemRecipeInputColumns::Table,
+                ItemRecipeInputColumns::ItemId,
+            ),
+            (
+                ItemRecipeInputColumns::Table,
+                ItemRecipeInputColumns::Amount,
+            ),
+            (
+                ItemRecipeInputColumns::Table,
+                ItemRecipeInputColumns::ExtraProducts,
+            ),
+            (
+                ItemRecipeInputColumns::Table,
+                ItemRecipeInputColumns::ProductionSpeedup,
+            ),
+        ])
+        .expr_as(
+            Expr::col((ItemColumns::Table, ItemColumns::Name)),
+            Alias::new("item_name"),
+        )
+        .from(ItemRecipeInputColumns::Table)
+        .inner_join(
+            ItemColumns::Table,
+            Expr::col((ItemColumns::Table, ItemColumns::Id)).equals((
+                ItemRecipeInputColumns::Table,
+                ItemRecipeInputColumns::ItemId,
+            )),
+        )
+        .and_where(
+            Expr::col((
+                ItemRecipeInputColumns::Table,
+                ItemRecipeInputColumns::RecipeId,
+            ))
+            .eq(id),
+        )
+        .order_by((ItemColumns::Table, ItemColumns::Name), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let inputs =
+        sqlx::query_as_with::<_, ItemRecipeGraphInput, _>(&inputs_sql, inputs_values.clone())
+            .fetch_all(&mut **tx)
+            .await?;
+
+    let (outputs_sql, outputs_values) = Query::select()
+        .columns([
+            (
+                ItemRecipeOutputColumns::Table,
+                ItemRecipeOutputColumns::ItemId,
+            ),
+            (
+                ItemRecipeOutputColumns::Table,
+                ItemRecipeOutputColumns::Amount,
+            ),
+        ])
+        .expr_as(
+            Expr::col((ItemColumns::Table, ItemColumns::Name)),
+            Alias::new("item_name"),
+        )
+        .from(ItemRecipeOutputColumns::Table)
+        .inner_join(
+            ItemColumns::Table,
+            Expr::col((ItemColumns::Table, ItemColumns::Id)).equals((
+                ItemRecipeOutputColumns::Table,
+                ItemRecipeOutputColumns::ItemId,
+            )),
+        )
+        .and_where(
+            Expr::col((
+                ItemRecipeOutputColumns::Table,
+                ItemRecipeOutputColumns::RecipeId,
+            ))
+            .eq(id),
+        )
+        .order_by((ItemColumns::Table, ItemColumns::Name), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let outputs =
+        sqlx::query_as_with::<_, ItemRecipeGraphOutput, _>(&outputs_sql, outputs_values.clone())
+            .fetch_all(&mut **tx)
+            .await?;
+
+    Ok(ItemRecipeGraph {
+        recipe,
+        inputs,
+        outputs,
+    })
+}
+
+async fn load_components<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    recipes: &mut [ItemRecipe],
+) -> Result<()> {
+    if recipes.is_empty() {
+        return Ok(());
+    }
+
+    let recipe_ids: Vec<Uuid> = recipes.iter().map(|r| r.id).collect();
+
+    let (inputs_sql, inputs_values) = Query::select()
+        .expr(Expr::col(Asterisk))
+        .from(ItemRecipeInputColumns::Table)
+        .and_where(Expr::col(ItemRecipeInputColumns::RecipeId).is_in(recipe_ids.clone()))
+        .order_by(ItemRecipeInputColumns::ItemId, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let inputs = sqlx::query_as_with::<_, ItemRecipeInput, _>(&inputs_sql, inputs_values.clone())
+        .fetch_all(&mut **tx)
+        .await?;
+
+    let (outputs_sql, outputs_values) = Query::select()
+        .expr(Expr::col(Asterisk))
+        .from(ItemRecipeOutputColumns::Table)
+        .and_where(Expr::col(ItemRecipeOutputColumns::RecipeId).is_in(recipe_ids))
+        .order_by(ItemRecipeOutputColumns::ItemId, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let outputs =
+        sqlx::query_as_with::<_, ItemRecipeOutput, _>(&outputs_sql, outputs_values.clone())
+            .fetch_all(&mut **tx)
+            .await?;
+
+    for recipe in recipes.iter_mut() {
+        recipe.inputs.clear();
+        recipe.outputs.clear();
+    }
+
+    for input in inputs {
+        if let Some(recipe) = recipes.iter_mut().find(|r| r.id == input.recipe_id) {
+            recipe.inputs.push(input);
+        }
+    }
+
+    for output in outputs {
+        if let Some(recipe) = recipes.iter_mut().find(|r| r.id == output.recipe_id) {
+            recipe.outputs.push(output);
+        }
+    }
+
+    Ok(())
+}
+
+async fn replace_components<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    recipe: &ItemRecipe,
+) -> Result<()> {
+    validate_components(tx, recipe).await?;
+
+    let (sql, values) = Query::delete()
+        .from_table(ItemRecipeInputColumns::Table)
+        .and_where(Expr::col(ItemRecipeInputColumns::RecipeId).eq(recipe.id))
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await?;
+
+    let (sql, values) = Query::delete()
+        .from_table(ItemRecipeOutputColumns::Table)
+        .and_where(Expr::col(ItemRecipeOutputColumns::RecipeId).eq(recipe.id))
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await?;
+
+    if !recipe.inputs.is_empty() {
+        let mut insert_stmt = Query::insert()
+            .into_table(ItemRecipeInputColumns::Table)
+            .columns([
+                ItemRecipeInputColumns::RecipeId,
+                ItemRecipeInputColumns::ItemId,
+                ItemRecipeInputColumns::Amount,
+                ItemRecipeInputColumns::ExtraProducts,
+                ItemRecipeInputColumns::ProductionSpeedup,
+            ])
+            .to_owned();
+        for input in &recipe.inputs {
+            insert_stmt.values_panic([
+                recipe.id.into(),
+                input.item_id.into(),
+                input.amount.into(),
+                input.extra_products.into(),
+                input.production_speedup.into(),
+            ]);
+        }
+
+        let (sql, values) = insert_stmt.build_sqlx(PostgresQueryBuilder);
+        sqlx::query_with(&sql, values.clone())
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    if !recipe.outputs.is_empty() {
+        let mut insert_stmt = Query::insert()
+            .into_table(ItemRecipeOutputColumns::Table)
+            .columns([
+                ItemRecipeOutputColumns::RecipeId,
+                ItemRecipeOutputColumns::ItemId,
+                ItemRecipeOutputColumns::Amount,
+            ])
+            .to_owned();
+        for output in &recipe.outputs {
+            insert_stmt.values_panic([
+                recipe.id.into(),
+                output.item_id.into(),
+                output.amount.into(),
+            ]);
+        }
+
+        let (sql, values) = insert_stmt.build_sqlx(PostgresQueryBuilder);
+        sqlx::query_with(&sql, values.clone())
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn validate_components<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    recipe: &ItemRecipe,
+) -> Result<()> {
+    let mut seen_inputs = HashSet::new();
+    for input in &recipe.inputs {
+        if !seen_inputs.insert(input.item_id) {
+            return Err(TrackerError::invalid_field(
+                FieldValue::new("inputs.item_id", input.item_id),
+                AllowedValues::uuid(),
+            ));
+        }
+    }
+
+    let mut seen_outputs = HashSet::new();
+    for output in &recipe.outputs {
+        if !seen_outputs.insert(output.item_id) {
+            return Err(TrackerError::invalid_field(
+                FieldValue::new("outputs.item_id", output.item_id),
+                AllowedValues::uuid(),
+            ));
+        }
+    }
+
+    let item_ids: HashSet<Uuid> = seen_inputs.union(&seen_outputs).copied().collect();
+    if item_ids.is_empty() {
+        return Ok(());
+    }
+
+    let (sql, values) = Query::select()
+        .column(ItemColumns::Id)
+        .from(ItemColumns::Table)
+        .and_where(Expr::col(ItemColumns::Id).is_in(item_ids.iter().copied()))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let existing_ids: HashSet<Uuid> = sqlx::query_with(&sql, values.clone())
+        .fetch_all(&mut **tx)
+        .await?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+
+    let missing_item_id = recipe
+        .inputs
+        .iter()
+        .map(|i| i.item_id)
+        .chain(recipe.outputs.iter().map(|o| o.item_id))
+        .find(|id| !existing_ids.contains(id));
+
+    match missing_item_id {
+        Some(id) => Err(TrackerError::not_found(
+            ObjectKind::Item,
+            FieldValue::new(ItemColumns::Id, id),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn add_where_clause(select_stmt: &mut SelectStatement, req: &SearchRequest) {
+    if let Some(name) = &req.name {
+        select_stmt.and_where(req.match_mode.predicate(ItemRecipeColumns::Name, name));
+    }
+
+    if let Some(item_id) = req.output_item_id {
+        select_stmt.and_where(
+            Expr::col(ItemRecipeColumns::Id).in_subquery(
+                Query::select()
+                    .column(ItemRecipeOutputColumns::RecipeId)
+                    .from(ItemRecipeOutputColumns::Table)
+                    .and_where(Expr::col(ItemRecipeOutputColumns::ItemId).eq(item_id))
+                    .to_owned(),
+            ),
+        );
+    }
+
+    if let Some(item_id) = req.input_item_id {
+        select_stmt.and_where(
+            Expr::col(ItemRecipeColumns::Id).in_subquery(
+                Query::select()
+                    .column(ItemRecipeInputColumns::RecipeId)
+                    .from(ItemRecipeInputColumns::Table)
+                    .and_where(Expr::col(ItemRecipeInputColumns::ItemId).eq(item_id))
+                    .to_owned(),
+            ),
+        );
+    }
+}
+
+fn map_constraint_errors(err: sqlx::Error, recipe: &ItemRecipe) -> TrackerError {
+    match &err {
+        _ if is_serialization_failure(&err) => TrackerError::concurrent_update(
+            ObjectKind::ItemRecipe,
+            FieldValue::new(ItemRecipeColumns::Id, recipe.id),
+        ),
+        sqlx::Error::Database(db_err) => match (db_err.kind(), db_err.constraint()) {
+            (ErrorKind::UniqueViolation, Some("item_recipes_name_key")) => TrackerError::duplicate(
+                ObjectKind::ItemRecipe,
+                FieldValue::new(ItemRecipeColumns::Name, &recipe.name),
+            ),
+            (ErrorKind::UniqueViolation, Some("item_recipes_pkey")) => TrackerError::duplicate(
+                ObjectKind::ItemRecipe,
+                FieldValue::new(ItemRecipeColumns::Id, recipe.id),
+            ),
+            _ => TrackerError::from(err),
+        },
+        _ => TrackerError::from(err),
+    }
+}
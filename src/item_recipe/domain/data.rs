@@ -0,0 +1,157 @@
+use chrono::{DateTime, Utc};
+use sea_query::Iden;
+use uuid::Uuid;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ItemRecipe {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub version: i32,
+    pub name: String,
+    pub craft_time_secs: f32,
+    #[sqlx(skip)]
+    pub inputs: Vec<ItemRecipeInput>,
+    #[sqlx(skip)]
+    pub outputs: Vec<ItemRecipeOutput>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ItemRecipeInput {
+    pub recipe_id: Uuid,
+    pub item_id: Uuid,
+    #[sqlx(try_from = "i16")]
+    pub amount: u16,
+    pub extra_products: bool,
+    pub production_speedup: bool,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ItemRecipeOutput {
+    pub recipe_id: Uuid,
+    pub item_id: Uuid,
+    #[sqlx(try_from = "i16")]
+    pub amount: u16,
+}
+
+/// A recipe with the items it consumes and produces resolved by name.
+#[derive(Debug)]
+pub struct ItemRecipeGraph {
+    pub recipe: ItemRecipe,
+    pub inputs: Vec<ItemRecipeGraphInput>,
+    pub outputs: Vec<ItemRecipeGraphOutput>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ItemRecipeGraphInput {
+    pub item_id: Uuid,
+    pub item_name: String,
+    #[sqlx(try_from = "i16")]
+    pub amount: u16,
+    pub extra_products: bool,
+    pub production_speedup: bool,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ItemRecipeGraphOutput {
+    pub item_id: Uuid,
+    pub item_name: String,
+    #[sqlx(try_from = "i16")]
+    pub amount: u16,
+}
+
+#[derive(Debug, Copy, Clone, Iden)]
+#[allow(dead_code)]
+pub enum ItemRecipeColumns {
+    #[iden(rename = "item_recipes")]
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Version,
+    Name,
+    CraftTimeSecs,
+}
+
+#[derive(Debug, Copy, Clone, Iden)]
+#[allow(dead_code)]
+pub enum ItemRecipeInputColumns {
+    #[iden(rename = "item_recipe_inputs")]
+    Table,
+    RecipeId,
+    ItemId,
+    Amount,
+    ExtraProducts,
+    ProductionSpeedup,
+}
+
+#[derive(Debug, Copy, Clone, Iden)]
+#[allow(dead_code)]
+pub enum ItemRecipeOutputColumns {
+    #[iden(rename = "item_recipe_outputs")]
+    Table,
+    RecipeId,
+    ItemId,
+    Amount,
+}
+
+impl ItemRecipe {
+    pub fn new(name: String, craft_time_secs: f32) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: None,
+            version: 0,
+            name,
+            craft_time_secs,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+}
+
+impl ItemRecipeInput {
+    pub fn new(
+        recipe_id: Uuid,
+        item_id: Uuid,
+        amount: u16,
+        extra_products: bool,
+        production_speedup: bool,
+    ) -> Self {
+        Self {
+            recipe_id,
+            item_id,
+            amount,
+            extra_products,
+            production_speedup,
+        }
+    }
+}
+
+impl ItemRecipeOutput {
+    pub fn new(recipe_id: Uuid, item_id: Uuid, amount: u16) -> Self {
+        Self {
+            recipe_id,
+            item_id,
+            amount,
+        }
+    }
+}
+
+impl From<ItemRecipeColumns> for String {
+    fn from(value: ItemRecipeColumns) -> Self {
+        value.to_string()
+    }
+}
+
+impl From<ItemRecipeInputColumns> for String {
+    fn from(value: ItemRecipeInputColumns) -> Self {
+        value.to_string()
+    }
+}
+
+impl From<ItemRecipeOutputColumns> for String {
+    fn from(value: ItemRecipeOutputColumns) -> Self {
+        value.to_string()
+    }
+}
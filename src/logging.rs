@@ -0,0 +1,38 @@
+use env_logger::fmt::Formatter;
+use log::{
+    kv::{self, Key, Value, VisitSource},
+    Record,
+};
+use std::io::{self, Write};
+
+struct KeyValueWriter<'a>(&'a mut Formatter);
+
+impl<'kvs> VisitSource<'kvs> for KeyValueWriter<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        write!(self.0, " {}={:?}", key, value.to_string())
+            .map_err(|_| kv::Error::msg("failed to write the key-value pair"))
+    }
+}
+
+fn format(buf: &mut Formatter, record: &Record) -> io::Result<()> {
+    let level_style = buf.default_level_style(record.level());
+    write!(
+        buf,
+        "[{} {level_style}{:<5}{level_style:#} {}] {}",
+        buf.timestamp(),
+        record.level(),
+        record.target(),
+        record.args()
+    )?;
+    record
+        .key_values()
+        .visit(&mut KeyValueWriter(buf))
+        .map_err(io::Error::other)?;
+    writeln!(buf)
+}
+
+pub fn init() {
+    env_logger::Builder::from_default_env()
+        .format(format)
+        .init();
+}
@@ -1,44 +1,302 @@
+mod admin;
+mod auth;
+mod change_feed;
 mod data;
 mod error;
 mod field;
 mod game_save;
+mod health;
+mod idempotency;
+mod item;
+mod item_recipe;
+mod logging;
+mod metrics;
+mod openapi;
 mod planet;
+mod planet_type;
+mod reference;
+mod request_id;
+mod save_export;
 mod solar_system;
 mod star;
+#[cfg(test)]
+mod test_support;
 mod utils;
 
 use actix_cors::Cors;
 use actix_web::middleware::Logger;
-use actix_web::{http::header, web, App, HttpServer};
+use actix_web::{
+    dev::{ServerHandle, Service},
+    http::{
+        header::{self, HeaderName},
+        Uri,
+    },
+    rt, web, App, HttpServer,
+};
+use data::{PagingConfig, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
 use dotenvy::dotenv;
 use error::TrackerError;
+use log::info;
 use sqlx::postgres::{PgPool, PgPoolOptions};
+use star::SpectralClass;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
 
+pub const API_PREFIX: &str = "/api/1";
+const DEFAULT_LISTEN_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
 const DEFAULT_LISTEN_PORT: u16 = 8080;
+const DEFAULT_DB_MAX_CONNECTIONS: u32 = 10;
+const DEFAULT_DB_ACQUIRE_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_MAX_MINING_SPEED: u32 = 1000;
+const DEFAULT_MAX_JSON_BODY_BYTES: usize = 1024 * 1024;
+const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_IDEMPOTENCY_KEY_TTL_SECS: u32 = 24 * 60 * 60;
+const LOG_FORMAT: &str = r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T %{x-request-id}o"#;
 
 pub struct AppState {
     db: PgPool,
+    base_url: Option<String>,
+    max_mining_speed: u32,
+    idempotency_key_ttl: chrono::Duration,
+    pessimistic_locking: bool,
+    admin_token: Option<String>,
+    api_tokens: Vec<auth::ApiToken>,
+    allowed_spectral_classes: Option<Vec<SpectralClass>>,
+    paging: PagingConfig,
 }
 
 fn config(cfg: &mut web::ServiceConfig) {
-    let scope = web::scope("/api/1")
+    let scope = web::scope(API_PREFIX)
+        .wrap_fn(auth::require_api_token)
         .configure(game_save::config)
-        .configure(solar_system::config);
-    cfg.service(scope);
+        .configure(save_export::config)
+        .configure(change_feed::config)
+        .configure(solar_system::config)
+        .configure(star::config)
+        .configure(planet::config)
+        .configure(planet_type::config)
+        .configure(item::config)
+        .configure(item_recipe::config)
+        .configure(reference::config);
+    cfg.service(scope)
+        .configure(admin::config)
+        .configure(health::config)
+        .configure(metrics::config)
+        .configure(openapi::config);
+}
+
+/// Limits request bodies and reports extractor failures as [`TrackerError`]s.
+fn extractor_config(max_json_body_bytes: usize) -> impl FnOnce(&mut web::ServiceConfig) {
+    move |cfg| {
+        cfg.app_data(
+            web::JsonConfig::default()
+                .limit(max_json_body_bytes)
+                .error_handler(|err, _req| TrackerError::from(err).into()),
+        )
+        .app_data(web::PayloadConfig::default().limit(max_json_body_bytes))
+        .app_data(
+            web::QueryConfig::default().error_handler(|err, _req| TrackerError::from(err).into()),
+        )
+        .app_data(
+            web::PathConfig::default().error_handler(|err, _req| TrackerError::from(err).into()),
+        );
+    }
+}
+
+/// Parses a comma separated list of origins, each of which must be exactly
+/// `scheme://host[:port]` as browsers send it in the `Origin` header.
+fn parse_allowed_origins(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(|origin| {
+            let uri = origin
+                .parse::<Uri>()
+                .expect("Env var CORS_ALLOWED_ORIGINS is invalid");
+            match (uri.scheme_str(), uri.authority()) {
+                (Some(scheme @ ("http" | "https")), Some(authority))
+                    if origin == format!("{scheme}://{authority}") =>
+                {
+                    origin.to_owned()
+                }
+                _ => panic!("Env var CORS_ALLOWED_ORIGINS is invalid: {origin} is not an origin"),
+            }
+        })
+        .collect()
+}
+
+/// Parses a comma separated list of `owner_id:token` pairs, scoping each token
+/// to the saves of its owner.
+fn parse_api_tokens(value: &str) -> Vec<auth::ApiToken> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (owner_id, token) = entry
+                .split_once(':')
+                .filter(|(_, token)| !token.is_empty())
+                .expect("Env var API_TOKENS is invalid");
+            auth::ApiToken {
+                token: token.to_owned(),
+                owner_id: Some(
+                    owner_id
+                        .parse::<Uuid>()
+                        .expect("Env var API_TOKENS is invalid"),
+                ),
+            }
+        })
+        .collect()
+}
+
+fn restricted_cors(allowed_origins: &[String]) -> Cors {
+    allowed_origins
+        .iter()
+        .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+        .allowed_methods(["GET", "POST", "PATCH", "DELETE"])
+        .allowed_headers([
+            header::ACCEPT,
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::IF_MATCH,
+            header::IF_NONE_MATCH,
+            header::IF_MODIFIED_SINCE,
+            idempotency::IDEMPOTENCY_KEY_HEADER,
+            request_id::REQUEST_ID_HEADER,
+        ])
+        .expose_headers([
+            header::ETAG,
+            header::LOCATION,
+            header::LINK,
+            header::RETRY_AFTER,
+            request_id::REQUEST_ID_HEADER,
+            save_export::api::CONTENT_SHA256_HEADER,
+            HeaderName::from_static("x-total-count"),
+            HeaderName::from_static("x-total-pages"),
+        ])
+        .max_age(3600)
+}
+
+fn stop_on_signals(handle: ServerHandle, shutdown_timeout_secs: u64) {
+    let stop = move |signal: &'static str| {
+        let handle = handle.clone();
+        async move {
+            info!(
+                "Received {}; shutting down, draining in-flight requests for up to {}s",
+                signal, shutdown_timeout_secs
+            );
+            handle.stop(true).await;
+        }
+    };
+
+    let on_interrupt = stop.clone();
+    rt::spawn(async move {
+        if rt::signal::ctrl_c().await.is_ok() {
+            on_interrupt("SIGINT").await;
+        }
+    });
+
+    #[cfg(unix)]
+    rt::spawn(async move {
+        use rt::signal::unix::{signal, SignalKind};
+        let mut terminate =
+            signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+        if terminate.recv().await.is_some() {
+            stop("SIGTERM").await;
+        }
+    });
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
-    env_logger::init();
+    logging::init();
 
     let conn_str = std::env::var("DATABASE_URL").expect("Env var DATABASE_URL is required.");
+    let base_url = std::env::var("PUBLIC_BASE_URL")
+        .ok()
+        .map(|v| v.trim_end_matches('/').to_owned());
     let cors_permissive = std::env::var("CORS_PERMISSIVE").map_or(false, |v| v.eq("true"));
+    let cors_allowed_origins = std::env::var("CORS_ALLOWED_ORIGINS")
+        .ok()
+        .map(|v| parse_allowed_origins(&v));
+    let admin_token = std::env::var("ADMIN_TOKEN").ok().filter(|v| !v.is_empty());
+    let mut api_tokens = std::env::var("API_TOKENS").map_or(Vec::new(), |v| parse_api_tokens(&v));
+    if let Some(token) = std::env::var("API_TOKEN").ok().filter(|v| !v.is_empty()) {
+        api_tokens.push(auth::ApiToken {
+            token,
+            owner_id: None,
+        });
+    }
+    let pessimistic_locking = std::env::var("PESSIMISTIC_LOCKING").is_ok_and(|v| v.eq("true"));
+    let listen_address = std::env::var("LISTEN_ADDRESS").map_or(DEFAULT_LISTEN_ADDRESS, |v| {
+        v.parse::<IpAddr>()
+            .expect("Env var LISTEN_ADDRESS is invalid")
+    });
     let listen_port = std::env::var("LISTEN_PORT").map_or(DEFAULT_LISTEN_PORT, |v| {
         u16::from_str_radix(&v, 10).expect("Env var LISTEN_PORT is invalid")
     });
+    let max_mining_speed =
+        std::env::var("MAX_MINING_SPEED").map_or(DEFAULT_MAX_MINING_SPEED, |v| {
+            v.parse::<u32>()
+                .expect("Env var MAX_MINING_SPEED is invalid")
+        });
+    let allowed_spectral_classes = std::env::var("ALLOWED_SPECTRAL_CLASSES").ok().map(|v| {
+        v.split(',')
+            .map(|class| class.trim().parse::<SpectralClass>())
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Env var ALLOWED_SPECTRAL_CLASSES is invalid")
+    });
+    let default_page_size = std::env::var("DEFAULT_PAGE_SIZE").map_or(DEFAULT_PAGE_SIZE, |v| {
+        v.parse::<u64>()
+            .expect("Env var DEFAULT_PAGE_SIZE is invalid")
+    });
+    let max_page_size = std::env::var("MAX_PAGE_SIZE").map_or(MAX_PAGE_SIZE, |v| {
+        v.parse::<u64>().expect("Env var MAX_PAGE_SIZE is invalid")
+    });
+    assert!(
+        default_page_size > 0,
+        "Env var DEFAULT_PAGE_SIZE must be at least 1"
+    );
+    assert!(
+        default_page_size <= max_page_size,
+        "Env var DEFAULT_PAGE_SIZE must not exceed MAX_PAGE_SIZE"
+    );
+    let paging = PagingConfig {
+        default_size: default_page_size,
+        max_size: max_page_size,
+    };
+    let max_json_body_bytes =
+        std::env::var("MAX_JSON_BODY_BYTES").map_or(DEFAULT_MAX_JSON_BODY_BYTES, |v| {
+            v.parse::<usize>()
+                .expect("Env var MAX_JSON_BODY_BYTES is invalid")
+        });
+    let idempotency_key_ttl_secs =
+        std::env::var("IDEMPOTENCY_KEY_TTL_SECS").map_or(DEFAULT_IDEMPOTENCY_KEY_TTL_SECS, |v| {
+            v.parse::<u32>()
+                .expect("Env var IDEMPOTENCY_KEY_TTL_SECS is invalid")
+        });
+    let idempotency_key_ttl = chrono::Duration::seconds(idempotency_key_ttl_secs.into());
+    let db_max_connections =
+        std::env::var("DB_MAX_CONNECTIONS").map_or(DEFAULT_DB_MAX_CONNECTIONS, |v| {
+            v.parse::<u32>()
+                .expect("Env var DB_MAX_CONNECTIONS is invalid")
+        });
+    let db_acquire_timeout_secs =
+        std::env::var("DB_ACQUIRE_TIMEOUT_SECS").map_or(DEFAULT_DB_ACQUIRE_TIMEOUT_SECS, |v| {
+            v.parse::<u64>()
+                .expect("Env var DB_ACQUIRE_TIMEOUT_SECS is invalid")
+        });
+    let shutdown_timeout_secs =
+        std::env::var("SHUTDOWN_TIMEOUT_SECS").map_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS, |v| {
+            v.parse::<u64>()
+                .expect("Env var SHUTDOWN_TIMEOUT_SECS is invalid")
+        });
     let pool = PgPoolOptions::new()
-        .max_connections(10)
+        .max_connections(db_max_connections)
+        .acquire_timeout(Duration::from_secs(db_acquire_timeout_secs))
         .connect(&conn_str)
         .await
         .expect("Failed to connect to the database");
@@ -47,31 +305,148 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("Failed to run sql migrations");
 
-    HttpServer::new(move || {
-        let cors = if cors_permissive {
-            Cors::permissive()
-        } else {
-            Cors::default()
+    idempotency::spawn_cleanup(pool.clone(), idempotency_key_ttl);
+
+    let metrics = web::Data::new(metrics::Metrics::new());
+
+    let server_pool = pool.clone();
+    let server = HttpServer::new(move || {
+        let cors = match &cors_allowed_origins {
+            Some(allowed_origins) => restricted_cors(allowed_origins),
+            None if cors_permissive => Cors::permissive(),
+            None => Cors::default(),
         };
         App::new()
-            .app_data(web::Data::new(AppState { db: pool.clone() }))
-            .app_data(
-                web::JsonConfig::default()
-                    .error_handler(|err, _req| TrackerError::from(err).into()),
-            )
-            .app_data(
-                web::QueryConfig::default()
-                    .error_handler(|err, _req| TrackerError::from(err).into()),
-            )
-            .app_data(
-                web::PathConfig::default()
-                    .error_handler(|err, _req| TrackerError::from(err).into()),
-            )
+            .app_data(web::Data::new(AppState {
+                db: server_pool.clone(),
+                base_url: base_url.clone(),
+                max_mining_speed,
+                idempotency_key_ttl,
+                pessimistic_locking,
+                admin_token: admin_token.clone(),
+                api_tokens: api_tokens.clone(),
+                allowed_spectral_classes: allowed_spectral_classes.clone(),
+                paging,
+            }))
+            .app_data(metrics.clone())
+            .configure(extractor_config(max_json_body_bytes))
             .configure(config)
+            .default_service(web::to(error::route_not_found_handler))
             .wrap(cors)
-            .wrap(Logger::default())
+            .wrap_fn(|req, srv| {
+                let request_id = request_id::assign(&req);
+                let fut = srv.call(req);
+                async move { fut.await.map(|res| request_id::attach(res, &request_id)) }
+            })
+            .wrap_fn({
+                let metrics = metrics.clone();
+                move |req, srv| {
+                    let metrics = metrics.clone();
+                    let method = req.method().to_string();
+                    let started = Instant::now();
+                    let fut = srv.call(req);
+                    async move {
+                        let res = fut.await?;
+                        metrics.record(
+                            &method,
+                            res.request().match_pattern(),
+                            res.status().as_u16(),
+                            started.elapsed(),
+                        );
+                        Ok(res)
+                    }
+                }
+            })
+            .wrap(Logger::new(LOG_FORMAT))
     })
-    .bind(("127.0.0.1", listen_port))?
-    .run()
-    .await
+    .shutdown_timeout(shutdown_timeout_secs)
+    .disable_signals()
+    .bind((listen_address, listen_port))?
+    .run();
+
+    stop_on_signals(server.handle(), shutdown_timeout_secs);
+    server.await?;
+
+    info!("Server stopped; closing database connections");
+    pool.close().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DEFAULT_MAX_JSON_BODY_BYTES;
+    use crate::test_support::{app_state, create_save, db_pool, send, test_app, unique_name};
+    use actix_web::{http::StatusCode, test};
+    use serde_json::json;
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn malformed_uuid_paths_are_bad_requests() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+
+        for entity in [
+            "saves",
+            "solar-systems",
+            "stars",
+            "planets",
+            "planet-types",
+            "items",
+            "item-recipes",
+        ] {
+            let (status, body) = send(
+                &app,
+                test::TestRequest::get()
+                    .uri(&format!("/api/1/{entity}/not-a-uuid"))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{entity}: {body}");
+            assert_eq!(body["error_code"], "InvalidUrlPath", "{entity}");
+        }
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::get()
+                .uri("/api/1/moons/not-a-uuid")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error_code"], "NotFound");
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn oversized_json_bodies_are_payload_too_large() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let notes = "x".repeat(DEFAULT_MAX_JSON_BODY_BYTES);
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/1/saves")
+                .set_json(
+                    json!({ "name": unique_name("Large"), "notes": notes, "mining_speed": 200 }),
+                )
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE, "{body}");
+        assert_eq!(body["error_code"], "PayloadTooLarge");
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!(
+                    "/api/1/saves/{0}/solar-systems/batch",
+                    save["id"].as_str().unwrap()
+                ))
+                .set_json(json!({ "solar_systems": [{ "name": "Sol", "notes": notes }] }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE, "{body}");
+        assert_eq!(body["error_code"], "PayloadTooLarge");
+    }
 }
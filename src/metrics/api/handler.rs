@@ -0,0 +1,9 @@
+use crate::{metrics::Metrics, AppState};
+use actix_web::{get, web, HttpResponse};
+
+#[get("/metrics")]
+async fn metrics_handler(metrics: web::Data<Metrics>, data: web::Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics.render(&data.db))
+}
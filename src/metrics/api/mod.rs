@@ -0,0 +1,7 @@
+mod handler;
+
+use actix_web::web;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(handler::metrics_handler);
+}
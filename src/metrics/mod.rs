@@ -0,0 +1,5 @@
+pub mod api;
+mod registry;
+
+pub use api::config;
+pub use registry::Metrics;
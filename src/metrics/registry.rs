@@ -0,0 +1,166 @@
+use sqlx::PgPool;
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex, time::Duration};
+
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RequestKey {
+    method: String,
+    route: String,
+    status: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RouteKey {
+    method: String,
+    route: String,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    requests: BTreeMap<RequestKey, u64>,
+    latencies: BTreeMap<RouteKey, Histogram>,
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    registry: Mutex<Registry>,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, upper_bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if value <= upper_bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, method: &str, route: Option<String>, status: u16, elapsed: Duration) {
+        let route = route.unwrap_or_else(|| UNMATCHED_ROUTE.to_owned());
+        let mut registry = self.registry.lock().unwrap_or_else(|e| e.into_inner());
+
+        *registry
+            .requests
+            .entry(RequestKey {
+                method: method.to_owned(),
+                route: route.clone(),
+                status,
+            })
+            .or_default() += 1;
+
+        registry
+            .latencies
+            .entry(RouteKey {
+                method: method.to_owned(),
+                route,
+            })
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn render(&self, pool: &PgPool) -> String {
+        let registry = self.registry.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+
+        writeln!(
+            out,
+            "# HELP http_requests_total Total number of HTTP requests."
+        )
+        .unwrap();
+        writeln!(out, "# TYPE http_requests_total counter").unwrap();
+        for (key, count) in &registry.requests {
+            writeln!(
+                out,
+                "http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                escape(&key.method),
+                escape(&key.route),
+                key.status,
+                count
+            )
+            .unwrap();
+        }
+
+        writeln!(
+            out,
+            "# HELP http_request_duration_seconds HTTP request latency in seconds."
+        )
+        .unwrap();
+        writeln!(out, "# TYPE http_request_duration_seconds histogram").unwrap();
+        for (key, histogram) in &registry.latencies {
+            let labels = format!(
+                "method=\"{}\",route=\"{}\"",
+                escape(&key.method),
+                escape(&key.route)
+            );
+            for (count, upper_bound) in histogram.buckets.iter().zip(LATENCY_BUCKETS) {
+                writeln!(
+                    out,
+                    "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, upper_bound, count
+                )
+                .unwrap();
+            }
+            writeln!(
+                out,
+                "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, histogram.count
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "http_request_duration_seconds_sum{{{}}} {}",
+                labels, histogram.sum
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "http_request_duration_seconds_count{{{}}} {}",
+                labels, histogram.count
+            )
+            .unwrap();
+        }
+
+        writeln!(
+            out,
+            "# HELP db_pool_connections Number of open database connections."
+        )
+        .unwrap();
+        writeln!(out, "# TYPE db_pool_connections gauge").unwrap();
+        writeln!(out, "db_pool_connections {}", pool.size()).unwrap();
+        writeln!(
+            out,
+            "# HELP db_pool_idle_connections Number of idle database connections."
+        )
+        .unwrap();
+        writeln!(out, "# TYPE db_pool_idle_connections gauge").unwrap();
+        writeln!(out, "db_pool_idle_connections {}", pool.num_idle()).unwrap();
+
+        out
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
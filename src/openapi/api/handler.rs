@@ -0,0 +1,44 @@
+use super::openapi;
+use actix_web::{get, HttpResponse};
+use once_cell::sync::Lazy;
+
+static OPENAPI_JSON: Lazy<String> = Lazy::new(|| {
+    openapi()
+        .to_json()
+        .expect("Failed to serialize the OpenAPI document")
+});
+
+/// The Swagger UI assets are pinned to an exact release so the docs page
+/// can't change underneath us when a new version is published.
+const SWAGGER_UI_HTML: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>DSP Tracker API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5.17.14/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5.17.14/swagger-ui-bundle.js"></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "/api-docs/openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##;
+
+#[get("/api-docs/openapi.json")]
+async fn openapi_handler() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .body(OPENAPI_JSON.as_str())
+}
+
+#[get("/api-docs")]
+async fn swagger_ui_handler() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(SWAGGER_UI_HTML)
+}
@@ -0,0 +1,76 @@
+mod handler;
+
+use crate::{
+    change_feed,
+    data::PageMetadata,
+    error::{DependentCount, ErrorResponse, InvalidField, ObjectKind},
+    field::{AllowedValues, Bound, FieldValue, Value},
+    game_save, item, item_recipe, planet, planet_type, reference, save_export, solar_system, star,
+};
+use actix_web::web;
+use utoipa::OpenApi;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(handler::openapi_handler)
+        .service(handler::swagger_ui_handler);
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    servers((url = "/api/1")),
+    components(schemas(
+        ErrorResponse,
+        InvalidField,
+        DependentCount,
+        ObjectKind,
+        FieldValue,
+        Value,
+        Bound,
+        AllowedValues,
+        PageMetadata,
+    ))
+)]
+struct ApiDoc;
+
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    let mut doc = ApiDoc::openapi();
+    for module_doc in [
+        game_save::api::ApiDoc::openapi(),
+        save_export::api::ApiDoc::openapi(),
+        change_feed::api::ApiDoc::openapi(),
+        solar_system::api::ApiDoc::openapi(),
+        star::api::ApiDoc::openapi(),
+        planet::api::ApiDoc::openapi(),
+        planet_type::api::ApiDoc::openapi(),
+        item::api::ApiDoc::openapi(),
+        item_recipe::api::ApiDoc::openapi(),
+        reference::api::ApiDoc::openapi(),
+    ] {
+        doc.merge(module_doc);
+    }
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::openapi;
+    use std::collections::HashSet;
+
+    #[test]
+    fn every_operation_has_a_unique_operation_id() {
+        let doc = openapi();
+        let mut seen = HashSet::new();
+        for (path, item) in &doc.paths.paths {
+            for operation in item.operations.values() {
+                let operation_id = operation
+                    .operation_id
+                    .as_deref()
+                    .unwrap_or_else(|| panic!("{path} has an operation without an id"));
+                assert!(
+                    seen.insert(operation_id.to_owned()),
+                    "{operation_id} is used more than once"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{
+    data::{MatchMode, PageRequest, PageRequestRaw},
+    error::{TrackerError, Validator},
+    field::{AllowedValues, Bound, Field, FieldValue},
+    field_names,
+    planet::domain::{self, RotationDirection},
+    solar_system::api::SolarSystemFields,
+    utils::{
+        double_option, entity_tag, last_modified, validate_finite_positive, validate_name,
+        validate_search_term, WholeNumber, MAX_NAME_SEARCH_LENGTH,
+    },
+};
+use actix_web::{
+    body::BoxBody,
+    http::header::{ETag, LastModified},
+    HttpResponse, Responder,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub const MAX_PERCENT: u16 = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Planet {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub version: i32,
+    pub solar_system_id: Uuid,
+    pub parent_planet_id: Option<Uuid>,
+    pub planet_type_id: Uuid,
+    pub name: String,
+    pub orbital_resonance: Option<f32>,
+    pub rotation_direction: RotationDirection,
+    pub solar_energy_percent: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CreatePlanetRequest {
+    pub name: String,
+    pub planet_type_id: Uuid,
+    pub parent_planet_id: Option<Uuid>,
+    pub orbital_resonance: Option<f32>,
+    pub rotation_direction: RotationDirection,
+    /// Also accepts integer-valued floats and whole-number strings
+    #[schema(value_type = u16)]
+    pub solar_energy_percent: WholeNumber<u16>,
+}
+
+/// Replaces every resource available on a planet.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SetPlanetResourcesRequest {
+    pub item_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdatePlanetRequest {
+    /// The version the changes are based on; it must be the version `If-Match` names
+    pub version: i32,
+    pub name: Option<String>,
+    pub planet_type_id: Option<Uuid>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub parent_planet_id: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub orbital_resonance: Option<Option<f32>>,
+    pub rotation_direction: Option<RotationDirection>,
+    #[schema(value_type = Option<u16>)]
+    pub solar_energy_percent: Option<WholeNumber<u16>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchRequestRaw {
+    #[serde(flatten)]
+    pub page_request: PageRequestRaw,
+    pub name: Option<String>,
+    pub match_mode: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchRequest {
+    pub page_request: PageRequest<PlanetFields>,
+    pub name: Option<String>,
+    pub match_mode: MatchMode,
+}
+
+impl CreatePlanetRequest {
+    pub fn validate(&self) -> Result<(), TrackerError> {
+        let mut validator = Validator::new();
+        validator.check(validate_name("name", &self.name));
+        if let Some(orbital_resonance) = self.orbital_resonance {
+            validator.check(validate_finite_positive(
+                "orbital_resonance",
+                orbital_resonance,
+            ));
+        }
+
+        validator.check(
+            self.solar_energy_percent
+                .check("solar_energy_percent")
+                .and_then(|percent| validate_percent("solar_energy_percent", percent)),
+        );
+        validator.finish()
+    }
+}
+
+impl UpdatePlanetRequest {
+    pub fn validate(&self) -> Result<(), TrackerError> {
+        let mut validator = Validator::new();
+        if let Some(name) = &self.name {
+            validator.check(validate_name("name", name));
+        }
+
+        if let Some(Some(orbital_resonance)) = self.orbital_resonance {
+            validator.check(validate_finite_positive(
+                "orbital_resonance",
+                orbital_resonance,
+            ));
+        }
+
+        if let Some(solar_energy_percent) = &self.solar_energy_percent {
+            validator.check(
+                solar_energy_percent
+                    .check("solar_energy_percent")
+                    .and_then(|percent| validate_percent("solar_energy_percent", percent)),
+            );
+        }
+        validator.finish()
+    }
+}
+
+pub fn validate_percent(name: &str, value: u16) -> Result<(), TrackerError> {
+    if value > MAX_PERCENT {
+        return Err(TrackerError::invalid_field(
+            FieldValue::new(name, value),
+            AllowedValues::integer_between(Bound::inclusive(0), Bound::inclusive(MAX_PERCENT)),
+        ));
+    }
+    Ok(())
+}
+
+impl From<domain::Planet> for Planet {
+    fn from(value: domain::Planet) -> Self {
+        Self {
+            id: value.id,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            version: value.version,
+            solar_system_id: value.solar_system_id,
+            parent_planet_id: value.parent_planet_id,
+            planet_type_id: value.planet_type_id,
+            name: value.name,
+            orbital_resonance: value.orbital_resonance,
+            rotation_direction: value.rotation_direction,
+            solar_energy_percent: value.solar_energy_percent,
+        }
+    }
+}
+
+impl Responder for Planet {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
+        HttpResponse::Ok()
+            .insert_header(ETag(entity_tag(self.version)))
+            .insert_header(LastModified(last_modified(
+                self.created_at,
+                self.updated_at,
+            )))
+            .json(self)
+    }
+}
+
+impl TryFrom<SearchRequestRaw> for SearchRequest {
+    type Error = TrackerError;
+
+    fn try_from(value: SearchRequestRaw) -> Result<Self, Self::Error> {
+        value.page_request.reject_cursor()?;
+
+        if let Some(name) = &value.name {
+            validate_search_term("name", name, MAX_NAME_SEARCH_LENGTH)?;
+        }
+
+        Ok(Self {
+            page_request: PageRequest::try_from(value.page_request)?,
+            name: value.name,
+            match_mode: MatchMode::try_from(value.match_mode)?,
+        })
+    }
+}
+
+field_names!(
+    PlanetFields<domain::PlanetColumns> {
+        Id => { value: "id", column: Id, kind: Uuid },
+        SolarSystem(SolarSystemFields) => { prefix: "solar_system" },
+        #[default]
+        CreatedAt => { value: "created_at", column: CreatedAt, kind: DateTime },
+        Name => { value: "name", column: Name },
+        OrbitalResonance => { value: "orbital_resonance", column: OrbitalResonance, kind: Float },
+        RotationDirection => { value: "rotation_direction", column: RotationDirection, kind: Enum("rotation_direction", RotationDirection) },
+        SolarEnergyPercent => { value: "solar_energy_percent", column: SolarEnergyPercent, kind: Integer }
+    }
+);
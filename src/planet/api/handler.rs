@@ -0,0 +1,608 @@
+use super::{CreatePlanetRequest, Planet, SetPlanetResourcesRequest, UpdatePlanetRequest};
+use crate::planet::api::{SearchRequest, SearchRequestRaw};
+use crate::planet::domain;
+use crate::{
+    auth::Owner,
+    data::{begin_repeatable_read, begin_update, with_current_version, Page, PageRequestRaw},
+    error::{log_entity_error, log_error, log_lookup_error, ObjectKind, Result},
+    idempotency::Idempotency,
+    item::api::Item,
+    solar_system,
+    utils::{check_if_match, last_modified, normalize_name, not_modified, ApiResponse},
+    AppState,
+};
+use actix_web::{delete, get, patch, post, put, web, HttpRequest, HttpResponse, Responder};
+use uuid::Uuid;
+
+#[utoipa::path(
+    operation_id = "createPlanet",
+    tag = "planets",
+    params(
+        ("solarSystemId" = Uuid, Path, description = "The solar system id"),
+        ("Idempotency-Key" = Option<String>, Header, description = "A client-chosen key; retrying with the same key returns the originally created resource"),
+    ),
+    request_body = CreatePlanetRequest,
+    responses(
+        (status = 201, description = "The created planet", body = Planet),
+        (status = 400, description = "The request was invalid or the parent planet is in another solar system", body = ErrorResponse),
+        (status = 404, description = "The solar system, parent planet or planet type does not exist", body = ErrorResponse),
+        (status = 409, description = "A conflicting planet already exists", body = ErrorResponse),
+    )
+)]
+#[post("/solar-systems/{solarSystemId}/planets")]
+async fn create_handler(
+    owner: Owner,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    request: web::Json<CreatePlanetRequest>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<Planet>> {
+    request.validate()?;
+
+    let idempotency =
+        Idempotency::from_request(&req, ObjectKind::Planet, data.idempotency_key_ttl)?;
+    let mut transaction = data.db.begin().await?;
+    solar_system::check_owner(&mut transaction, owner, *path).await?;
+    if let Some(parent_planet_id) = request.parent_planet_id {
+        domain::check_owner(&mut transaction, owner, parent_planet_id).await?;
+    }
+    let response = match idempotency.lookup(&mut transaction).await? {
+        Some(id) => domain::lookup(&mut transaction, id).await?,
+        None => {
+            let solar_system_id = path.into_inner();
+
+            let planet = domain::Planet::new(
+                solar_system_id,
+                request.parent_planet_id,
+                request.planet_type_id,
+                request.name.clone(),
+                request.orbital_resonance,
+                request.rotation_direction,
+                request.solar_energy_percent.value(),
+            );
+
+            let response = domain::create(&mut transaction, &planet)
+                .await
+                .inspect_err(log_entity_error(
+                    "Failed to create planet",
+                    ObjectKind::Planet,
+                    planet.id,
+                ))?;
+            idempotency.record(&mut transaction, response.id).await?;
+            response
+        }
+    };
+    transaction.commit().await?;
+
+    Ok(ApiResponse::created_versioned(
+        format!("/planets/{0}", response.id),
+        response.version,
+        Planet::from(response),
+    ))
+}
+
+#[utoipa::path(
+    operation_id = "lookupPlanet",
+    tag = "planets",
+    params(
+        ("id" = Uuid, Path, description = "The planet id"),
+        ("If-None-Match" = Option<String>, Header, description = "An ETag from a previous response"),
+        ("If-Modified-Since" = Option<String>, Header, description = "The Last-Modified date from a previous response; ignored when If-None-Match is present"),
+    ),
+    responses(
+        (status = 200, description = "The planet", body = Planet),
+        (status = 304, description = "The supplied If-None-Match ETag matches the current version, or the resource is unmodified since If-Modified-Since"),
+        (status = 400, description = "The id is not a valid UUID", body = ErrorResponse),
+        (status = 404, description = "The planet does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/planets/{id}")]
+async fn lookup_handler(
+    owner: Owner,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut transaction = data.db.begin().await?;
+
+    let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+    let response = domain::lookup(&mut transaction, id)
+        .await
+        .inspect_err(log_lookup_error(ObjectKind::Planet, id))
+        .map(Planet::from)?;
+
+    transaction.commit().await?;
+    Ok(not_modified(
+        &req,
+        response.version,
+        last_modified(response.created_at, response.updated_at),
+    )
+    .unwrap_or_else(|| response.respond_to(&req)))
+}
+
+#[utoipa::path(
+    operation_id = "planetMoons",
+    tag = "planets",
+    params(
+        ("id" = Uuid, Path, description = "The planet id"),
+    ),
+    responses(
+        (status = 200, description = "The direct moons of the planet", body = [Planet]),
+        (status = 400, description = "The id is not a valid UUID", body = ErrorResponse),
+        (status = 404, description = "The planet does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/planets/{id}/moons")]
+async fn moons_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut transaction = data.db.begin().await?;
+
+    let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+    let response: Vec<Planet> = domain::moons(&mut transaction, id)
+        .await
+        .inspect_err(log_entity_error(
+            "Failed to lookup moons",
+            ObjectKind::Planet,
+            id,
+        ))?
+        .into_iter()
+        .map(Planet::from)
+        .collect();
+
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    operation_id = "planetAncestry",
+    tag = "planets",
+    params(
+        ("id" = Uuid, Path, description = "The planet id"),
+    ),
+    responses(
+        (status = 200, description = "The parent planets, nearest first, up to the root planet", body = [Planet]),
+        (status = 400, description = "The id is not a valid UUID", body = ErrorResponse),
+        (status = 404, description = "The planet does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/planets/{id}/ancestry")]
+async fn ancestry_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut transaction = data.db.begin().await?;
+
+    let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+    let response: Vec<Planet> = domain::ancestry(&mut transaction, id)
+        .await
+        .inspect_err(log_entity_error(
+            "Failed to lookup ancestry",
+            ObjectKind::Planet,
+            id,
+        ))?
+        .into_iter()
+        .map(Planet::from)
+        .collect();
+
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    operation_id = "planetResources",
+    tag = "planets",
+    params(
+        ("id" = Uuid, Path, description = "The planet id"),
+    ),
+    responses(
+        (status = 200, description = "The items available on the planet, ordered by name", body = [Item]),
+        (status = 400, description = "The id is not a valid UUID", body = ErrorResponse),
+        (status = 404, description = "The planet does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/planets/{id}/resources")]
+async fn resources_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut transaction = data.db.begin().await?;
+
+    let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+    let response: Vec<Item> = domain::resources(&mut transaction, id)
+        .await
+        .inspect_err(log_entity_error(
+            "Failed to lookup resources",
+            ObjectKind::Planet,
+            id,
+        ))?
+        .into_iter()
+        .map(Item::from)
+        .collect();
+
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    operation_id = "planetAllowedResources",
+    tag = "planets",
+    params(
+        ("id" = Uuid, Path, description = "The planet id"),
+    ),
+    responses(
+        (status = 200, description = "The items the planet's type allows around its star, ordered by name", body = [Item]),
+        (status = 400, description = "The id is not a valid UUID", body = ErrorResponse),
+        (status = 404, description = "The planet does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/planets/{id}/allowed-resources")]
+async fn allowed_resources_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut transaction = data.db.begin().await?;
+
+    let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+    let response: Vec<Item> = domain::allowed_resources(&mut transaction, id)
+        .await
+        .inspect_err(log_entity_error(
+            "Failed to lookup allowed resources",
+            ObjectKind::Planet,
+            id,
+        ))?
+        .into_iter()
+        .map(Item::from)
+        .collect();
+
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    operation_id = "setPlanetResources",
+    tag = "planets",
+    params(
+        ("id" = Uuid, Path, description = "The planet id"),
+    ),
+    request_body = SetPlanetResourcesRequest,
+    responses(
+        (status = 200, description = "The items now available on the planet, ordered by name", body = [Item]),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 404, description = "The planet or one of the items does not exist", body = ErrorResponse),
+        (status = 409, description = "The resources were concurrently modified", body = ErrorResponse),
+    )
+)]
+#[put("/planets/{id}/resources")]
+async fn set_resources_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    request: web::Json<SetPlanetResourcesRequest>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<Vec<Item>>> {
+    let mut transaction = begin_repeatable_read(&data.db).await?;
+
+    let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+    let response: Vec<Item> = domain::replace_resources(&mut transaction, id, &request.item_ids)
+        .await
+        .inspect_err(log_entity_error(
+            "Failed to replace resources",
+            ObjectKind::Planet,
+            id,
+        ))?
+        .into_iter()
+        .map(Item::from)
+        .collect();
+
+    transaction.commit().await?;
+    Ok(ApiResponse::Ok(response))
+}
+
+#[utoipa::path(
+    operation_id = "deletePlanet",
+    tag = "planets",
+    params(
+        ("id" = Uuid, Path, description = "The planet id"),
+    ),
+    responses(
+        (status = 204, description = "The planet was deleted"),
+        (status = 409, description = "The planet still has moons", body = ErrorResponse),
+    )
+)]
+#[delete("/planets/{id}")]
+async fn delete_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<()>> {
+    let mut transaction = data.db.begin().await?;
+    let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+
+    domain::delete(&mut transaction, id).await?;
+    transaction.commit().await?;
+
+    Ok(ApiResponse::NoContent)
+}
+
+#[utoipa::path(
+    operation_id = "searchPlanets",
+    tag = "planets",
+    params(
+        ("solarSystemId" = Uuid, Path, description = "The solar system id"),
+        PageRequestRaw,
+        ("match_mode" = Option<String>, Query, description = "One of `start`, `prefix`, `contains` or `exact`"),
+        ("name" = Option<String>, Query, description = "Name to match"),
+    ),
+    responses(
+        (status = 200, description = "A page of planets", body = PlanetPage),
+        (status = 400, description = "The search parameters were invalid", body = ErrorResponse),
+    )
+)]
+#[get("/solar-systems/{solarSystemId}/planets")]
+async fn search_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    query: web::Query<SearchRequestRaw>,
+    data: web::Data<AppState>,
+) -> Result<Page<Planet>> {
+    let mut transaction = data.db.begin().await?;
+    let solar_system_id = path.into_inner();
+    solar_system::check_owner(&mut transaction, owner, solar_system_id).await?;
+    let mut search_raw = query.into_inner();
+    search_raw.page_request.paging = data.paging;
+    let search_params = SearchRequest::try_from(search_raw)?;
+
+    let response = domain::search(&mut transaction, solar_system_id, &search_params)
+        .await
+        .map(Page::convert)
+        .inspect_err(log_error(
+            "Failed to search for planets",
+            ObjectKind::Planet,
+        ))?;
+    transaction.commit().await?;
+    Ok(response)
+}
+
+#[utoipa::path(
+    operation_id = "updatePlanet",
+    tag = "planets",
+    params(
+        ("id" = Uuid, Path, description = "The planet id"),
+        ("If-Match" = String, Header, description = "The ETag of the current version"),
+    ),
+    request_body = UpdatePlanetRequest,
+    responses(
+        (status = 200, description = "The updated planet", body = Planet),
+        (status = 400, description = "The request was invalid, or the parent planet is in another solar system or would create a cycle", body = ErrorResponse),
+        (status = 404, description = "The planet does not exist", body = ErrorResponse),
+        (status = 409, description = "The planet was concurrently modified or conflicts with an existing planet", body = ErrorResponse),
+        (status = 412, description = "The planet has been modified since it was fetched", body = ErrorResponse),
+        (status = 428, description = "The If-Match header is missing", body = ErrorResponse),
+    )
+)]
+#[patch("/planets/{id}")]
+async fn update_handler(
+    owner: Owner,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    request: web::Json<UpdatePlanetRequest>,
+    data: web::Data<AppState>,
+) -> Result<Planet> {
+    request.validate()?;
+
+    let mut transaction = begin_update(&data.db, data.pessimistic_locking).await?;
+    let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+
+    let mut planet = with_current_version(
+        domain::lookup_for_update(&mut transaction, id).await,
+        &data.db,
+        (
+            domain::PlanetColumns::Table,
+            domain::PlanetColumns::Id,
+            domain::PlanetColumns::Version,
+        ),
+        id,
+        Some(request.version),
+    )
+    .await?;
+    check_if_match(
+        &req,
+        ObjectKind::Planet,
+        id,
+        planet.version,
+        request.version,
+    )?;
+    planet.version = request.version;
+
+    if let Some(name) = &request.name {
+        planet.name = normalize_name(name);
+    }
+
+    if let Some(planet_type_id) = request.planet_type_id {
+        planet.planet_type_id = planet_type_id;
+    }
+
+    if let Some(parent_planet_id) = request.parent_planet_id {
+        if let Some(parent_planet_id) = parent_planet_id {
+            domain::check_owner(&mut transaction, owner, parent_planet_id).await?;
+        }
+        planet.parent_planet_id = parent_planet_id;
+    }
+
+    if let Some(orbital_resonance) = request.orbital_resonance {
+        planet.orbital_resonance = orbital_resonance;
+    }
+
+    if let Some(rotation_direction) = request.rotation_direction {
+        planet.rotation_direction = rotation_direction;
+    }
+
+    if let Some(solar_energy_percent) = &request.solar_energy_percent {
+        planet.solar_energy_percent = solar_energy_percent.value();
+    }
+
+    let response = with_current_version(
+        domain::update(&mut transaction, &planet).await,
+        &data.db,
+        (
+            domain::PlanetColumns::Table,
+            domain::PlanetColumns::Id,
+            domain::PlanetColumns::Version,
+        ),
+        id,
+        Some(planet.version),
+    )
+    .await
+    .inspect_err(log_entity_error(
+        "Failed to update planet",
+        ObjectKind::Planet,
+        id,
+    ))?;
+
+    transaction.commit().await?;
+    Ok(response.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{
+        app_state, create_planet_type, create_save, create_solar_system, db_pool, post_planet,
+        send, test_app,
+    };
+    use actix_web::{http::StatusCode, test};
+    use serde_json::json;
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn parent_planet_must_be_in_the_same_solar_system() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let save_id = save["id"].as_str().unwrap();
+        let sol = create_solar_system(&app, save_id, "Sol").await;
+        let sol_id = sol["id"].as_str().unwrap();
+        let prime = create_solar_system(&app, save_id, "Solar Prime").await;
+        let prime_id = prime["id"].as_str().unwrap();
+        let planet_type = create_planet_type(&app).await;
+        let planet_type_id = &planet_type["id"];
+
+        let (status, earth) =
+            post_planet(&app, sol_id, json!({ "planet_type_id": planet_type_id })).await;
+        assert_eq!(status, StatusCode::CREATED, "{earth}");
+
+        let (status, body) = post_planet(
+            &app,
+            prime_id,
+            json!({ "planet_type_id": planet_type_id, "parent_planet_id": earth["id"] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error_code"], "InvalidFieldValue");
+        assert_eq!(body["field"]["name"], "parent_planet_id");
+
+        let (status, planet) =
+            post_planet(&app, prime_id, json!({ "planet_type_id": planet_type_id })).await;
+        assert_eq!(status, StatusCode::CREATED, "{planet}");
+        let (status, moon) =
+            post_planet(&app, prime_id, json!({ "planet_type_id": planet_type_id })).await;
+        assert_eq!(status, StatusCode::CREATED, "{moon}");
+
+        let reparent = |parent: &serde_json::Value| {
+            test::TestRequest::patch()
+                .uri(&format!("/api/1/planets/{0}", moon["id"].as_str().unwrap()))
+                .insert_header(("If-Match", "\"0\""))
+                .set_json(json!({ "version": 0, "parent_planet_id": parent["id"] }))
+                .to_request()
+        };
+
+        let (status, body) = send(&app, reparent(&earth)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"]["name"], "parent_planet_id");
+        assert_eq!(body["allowed_values"]["values"], json!([planet["id"]]));
+
+        let (status, body) = send(&app, reparent(&planet)).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["parent_planet_id"], planet["id"]);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn solar_energy_percent_is_at_most_100() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let solar_system = create_solar_system(&app, save["id"].as_str().unwrap(), "Sol").await;
+        let solar_system_id = solar_system["id"].as_str().unwrap();
+        let planet_type = create_planet_type(&app).await;
+        let planet_type_id = &planet_type["id"];
+
+        let (status, planet) = post_planet(
+            &app,
+            solar_system_id,
+            json!({ "planet_type_id": planet_type_id, "solar_energy_percent": 100 }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED, "{planet}");
+        assert_eq!(planet["solar_energy_percent"], 100);
+
+        let (status, body) = post_planet(
+            &app,
+            solar_system_id,
+            json!({ "planet_type_id": planet_type_id, "solar_energy_percent": 101 }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        assert_eq!(body["error_code"], "InvalidFieldValue");
+        assert_eq!(body["field"]["name"], "solar_energy_percent");
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn solar_energy_percent_accepts_whole_numbers_in_any_json_form() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let solar_system = create_solar_system(&app, save["id"].as_str().unwrap(), "Sol").await;
+        let solar_system_id = solar_system["id"].as_str().unwrap();
+        let planet_type = create_planet_type(&app).await;
+        let planet_type_id = &planet_type["id"];
+
+        for solar_energy_percent in [json!(50), json!(50.0), json!("50")] {
+            let (status, planet) = post_planet(
+                &app,
+                solar_system_id,
+                json!({
+                    "planet_type_id": planet_type_id,
+                    "solar_energy_percent": solar_energy_percent,
+                }),
+            )
+            .await;
+            assert_eq!(
+                status,
+                StatusCode::CREATED,
+                "{solar_energy_percent}: {planet}"
+            );
+            assert_eq!(planet["solar_energy_percent"], 50);
+        }
+
+        let (status, body) = post_planet(
+            &app,
+            solar_system_id,
+            json!({ "planet_type_id": planet_type_id, "solar_energy_percent": 50.5 }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        assert_eq!(body["error_code"], "InvalidFieldValue");
+        assert_eq!(body["field"]["name"], "solar_energy_percent");
+    }
+}
@@ -0,0 +1,45 @@
+mod data;
+mod handler;
+
+use actix_web::web;
+pub use data::*;
+use utoipa::OpenApi;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(handler::create_handler)
+        .service(handler::lookup_handler)
+        .service(handler::search_handler)
+        .service(handler::moons_handler)
+        .service(handler::ancestry_handler)
+        .service(handler::resources_handler)
+        .service(handler::allowed_resources_handler)
+        .service(handler::set_resources_handler)
+        .service(handler::delete_handler)
+        .service(handler::update_handler);
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        handler::create_handler,
+        handler::lookup_handler,
+        handler::search_handler,
+        handler::moons_handler,
+        handler::ancestry_handler,
+        handler::resources_handler,
+        handler::allowed_resources_handler,
+        handler::set_resources_handler,
+        handler::update_handler,
+        handler::delete_handler,
+    ),
+    components(schemas(
+        Planet,
+        CreatePlanetRequest,
+        UpdatePlanetRequest,
+        SetPlanetResourcesRequest,
+        crate::planet::OceanType,
+        crate::planet::RotationDirection,
+        crate::data::PlanetPage,
+    ))
+)]
+pub struct ApiDoc;
@@ -0,0 +1,628 @@
+use super::{Planet, PlanetColumns, PlanetResourceColumns};
+use crate::{
+    auth::Owner,
+    data::{add_sorts, count_rows, is_serialization_failure, paginate, Page, Sort},
+    error::{DependentCount, ObjectKind, Result, TrackerError},
+    field::{AllowedValues, Bound, FieldValue},
+    game_save::{self, GameSaveColumns},
+    item::{Item, ItemColumns},
+    planet::api::{PlanetFields, SearchRequest, MAX_PERCENT},
+    planet_type::{PlanetTypeAllowedResourceColumns, PlanetTypeColumns},
+    solar_system::{self, SolarSystemColumns},
+    star::domain::StarColumns,
+    utils::MAX_NAME_LENGTH,
+};
+use sea_query::{
+    Alias, Asterisk, CommonTableExpression, Expr, Func, Iden, LockType, OnConflict, Order,
+    PostgresQueryBuilder, Query, SelectStatement, UnionType, WithClause,
+};
+use sea_query_binder::SqlxBinder;
+use sqlx::{error::ErrorKind, Postgres, Transaction};
+use uuid::Uuid;
+
+const MAX_ANCESTRY_DEPTH: i32 = 64;
+
+pub async fn create<'a>(tx: &mut Transaction<'a, Postgres>, planet: &Planet) -> Result<Planet> {
+    check_parent(tx, planet).await?;
+
+    let (sql, values) = Query::insert()
+        .into_table(PlanetColumns::Table)
+        .columns([
+            PlanetColumns::Id,
+            PlanetColumns::CreatedAt,
+            PlanetColumns::Version,
+            PlanetColumns::SolarSystemId,
+            PlanetColumns::ParentPlanetId,
+            PlanetColumns::PlanetTypeId,
+            PlanetColumns::Name,
+            PlanetColumns::OrbitalResonance,
+            PlanetColumns::RotationDirection,
+            PlanetColumns::SolarEnergyPercent,
+        ])
+        .values_panic([
+            planet.id.into(),
+            Expr::cust("clock_timestamp()"),
+            planet.version.into(),
+            planet.solar_system_id.into(),
+            planet.parent_planet_id.into(),
+            planet.planet_type_id.into(),
+            (&planet.name).into(),
+            planet.orbital_resonance.into(),
+            Expr::val(planet.rotation_direction.as_ref()).as_enum(Alias::new("rotation_direction")),
+            planet.solar_energy_percent.into(),
+        ])
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| map_constraint_errors(err, planet))?;
+
+    lookup(tx, planet.id)
+        .await
+        .map_err(TrackerError::not_found_unexpected)
+}
+
+pub async fn update<'a>(tx: &mut Transaction<'a, Postgres>, planet: &Planet) -> Result<Planet> {
+    check_parent(tx, planet).await?;
+
+    let (sql, values) = Query::update()
+        .table(PlanetColumns::Table)
+        .values([
+            (PlanetColumns::UpdatedAt, Expr::current_timestamp().into()),
+            (
+                PlanetColumns::Version,
+                Expr::col(PlanetColumns::Version).add(1),
+            ),
+            (
+                PlanetColumns::ParentPlanetId,
+                planet.parent_planet_id.into(),
+            ),
+            (PlanetColumns::PlanetTypeId, planet.planet_type_id.into()),
+            (PlanetColumns::Name, planet.name.clone().into()),
+            (
+                PlanetColumns::OrbitalResonance,
+                planet.orbital_resonance.into(),
+            ),
+            (
+                PlanetColumns::RotationDirection,
+                Expr::val(planet.rotation_direction.as_ref())
+                    .as_enum(Alias::new("rotation_direction")),
+            ),
+            (
+                PlanetColumns::SolarEnergyPercent,
+                planet.solar_energy_percent.into(),
+            ),
+        ])
+        .and_where(Expr::col(PlanetColumns::Id).eq(planet.id))
+        .and_where(Expr::col(PlanetColumns::Version).eq(planet.version))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows_updated = sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| map_constraint_errors(err, planet))?
+        .rows_affected();
+
+    if rows_updated == 0 {
+        Err(TrackerError::concurrent_update(
+            ObjectKind::Planet,
+            FieldValue::new(PlanetColumns::Id, planet.id),
+        ))
+    } else {
+        lookup(tx, planet.id).await
+    }
+}
+
+pub async fn check_owner<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    owner: Owner,
+    id: Uuid,
+) -> Result<()> {
+    let select_stmt = Query::select()
+        .expr(Expr::val(1))
+        .from(PlanetColumns::Table)
+        .inner_join(
+            SolarSystemColumns::Table,
+            Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Id))
+                .equals((PlanetColumns::Table, PlanetColumns::SolarSystemId)),
+        )
+        .inner_join(
+            GameSaveColumns::Table,
+            Expr::col((GameSaveColumns::Table, GameSaveColumns::Id))
+                .equals((SolarSystemColumns::Table, SolarSystemColumns::SaveId)),
+        )
+        .and_where(Expr::col((PlanetColumns::Table, PlanetColumns::Id)).eq(id))
+        .to_owned();
+
+    game_save::ensure_owned(tx, owner, select_stmt, || {
+        TrackerError::not_found(ObjectKind::Planet, FieldValue::new(PlanetColumns::Id, id))
+    })
+    .await
+}
+
+pub async fn lookup_optional<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    id: Uuid,
+) -> Result<Option<Planet>> {
+    let (sql, values) = Query::select()
+        .column((Alias::new("planet"), Asterisk))
+        .from_as(PlanetColumns::Table, Alias::new("planet"))
+        .and_where(Expr::col(PlanetColumns::Id).eq(id))
+        .limit(1)
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_as_with::<_, Planet, _>(&sql, values.clone())
+        .fetch_optional(&mut **tx)
+        .await?)
+}
+
+pub async fn lookup<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<Planet> {
+    lookup_optional(tx, id)
+        .await
+        .transpose()
+        .unwrap_or_else(|| {
+            Err(TrackerError::not_found(
+                ObjectKind::Planet,
+                FieldValue::new(PlanetColumns::Id, id),
+            ))
+        })
+}
+
+pub async fn lookup_for_update<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<Planet> {
+    let (sql, values) = Query::select()
+        .column(Asterisk)
+        .from(PlanetColumns::Table)
+        .and_where(Expr::col(PlanetColumns::Id).eq(id))
+        .lock(LockType::Update)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_as_with::<_, Planet, _>(&sql, values.clone())
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|err| {
+            if is_serialization_failure(&err) {
+                TrackerError::concurrent_update(
+                    ObjectKind::Planet,
+                    FieldValue::new(PlanetColumns::Id, id),
+                )
+            } else {
+                TrackerError::from(err)
+            }
+        })?
+        .ok_or_else(|| {
+            TrackerError::not_found(ObjectKind::Planet, FieldValue::new(PlanetColumns::Id, id))
+        })
+}
+
+pub async fn moons<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<Vec<Planet>> {
+    lookup(tx, id).await?;
+
+    let (sql, values) = Query::select()
+        .column(Asterisk)
+        .from(PlanetColumns::Table)
+        .and_where(Expr::col(PlanetColumns::ParentPlanetId).eq(id))
+        .order_by(PlanetColumns::CreatedAt, Order::Asc)
+        .order_by(PlanetColumns::Id, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_as_with::<_, Planet, _>(&sql, values.clone())
+        .fetch_all(&mut **tx)
+        .await?)
+}
+
+pub async fn ancestry<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<Vec<Planet>> {
+    lookup(tx, id).await?;
+
+    let ancestry = Alias::new("ancestry");
+    let depth = Alias::new("depth");
+
+    let mut cte_query = Query::select()
+        .columns([PlanetColumns::Id, PlanetColumns::ParentPlanetId])
+        .expr(Expr::cust("0"))
+        .from(PlanetColumns::Table)
+        .and_where(Expr::col(PlanetColumns::Id).eq(id))
+        .to_owned();
+    cte_query.union(
+        UnionType::All,
+        Query::select()
+            .columns([
+                (PlanetColumns::Table, PlanetColumns::Id),
+                (PlanetColumns::Table, PlanetColumns::ParentPlanetId),
+            ])
+            .expr(Expr::cust("ancestry.depth + 1"))
+            .from(PlanetColumns::Table)
+            .inner_join(
+                ancestry.clone(),
+                Expr::col((ancestry.clone(), PlanetColumns::ParentPlanetId))
+                    .equals((PlanetColumns::Table, PlanetColumns::Id)),
+            )
+            .and_where(Expr::col((ancestry.clone(), depth.clone())).lt(MAX_ANCESTRY_DEPTH))
+            .to_owned(),
+    );
+
+    let with_clause = WithClause::new()
+        .recursive(true)
+        .cte(
+            CommonTableExpression::new()
+                .query(cte_query)
+                .column(PlanetColumns::Id)
+                .column(PlanetColumns::ParentPlanetId)
+                .column(depth.clone())
+                .table_name(ancestry.clone())
+                .to_owned(),
+        )
+        .to_owned();
+
+    let (sql, values) = Query::select()
+        .column((PlanetColumns::Table, Asterisk))
+        .from(PlanetColumns::Table)
+        .inner_join(
+            ancestry.clone(),
+            Expr::col((ancestry.clone(), PlanetColumns::Id))
+                .equals((PlanetColumns::Table, PlanetColumns::Id)),
+        )
+        .and_where(Expr::col((ancestry.clone(), depth.clone())).gt(0))
+        .order_by((ancestry, depth), Order::Asc)
+        .to_owned()
+        .with(with_clause)
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_as_with::<_, Planet, _>(&sql, values.clone())
+        .fetch_all(&mut **tx)
+        .await?)
+}
+
+pub async fn search<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    solar_system_id: Uuid,
+    search_params: &SearchRequest,
+) -> Result<Page<Planet>> {
+    let page_req = &search_params.page_request;
+    let mut joins_tracker = Vec::new();
+
+    let mut select_count_stmt = Query::select()
+        .expr(Func::count(Expr::col(Asterisk)))
+        .from(PlanetColumns::Table)
+        .to_owned();
+    add_where_clause(&mut select_count_stmt, solar_system_id, search_params);
+
+    let mut select_stmt = Query::select()
+        .column((PlanetColumns::Table, Asterisk))
+        .from(PlanetColumns::Table)
+        .to_owned();
+    add_where_clause(&mut select_stmt, solar_system_id, search_params);
+    add_sort_joins(&mut select_stmt, &page_req.sorts, &mut joins_tracker);
+    add_sorts(
+        &mut select_stmt,
+        &page_req.sorts,
+        (PlanetColumns::Table, PlanetColumns::Id),
+    );
+
+    paginate(tx, select_count_stmt, select_stmt, page_req).await
+}
+
+pub async fn delete<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<()> {
+    let moons = count_rows(
+        tx,
+        Query::select()
+            .expr(Func::count(Expr::col(Asterisk)))
+            .from(PlanetColumns::Table)
+            .and_where(Expr::col(PlanetColumns::ParentPlanetId).eq(id))
+            .to_owned(),
+    )
+    .await?;
+
+    let (sql, values) = Query::delete()
+        .from_table(PlanetColumns::Table)
+        .and_where(Expr::col(PlanetColumns::Id).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| match &err {
+            sqlx::Error::Database(db_err) if db_err.kind() == ErrorKind::ForeignKeyViolation => {
+                TrackerError::delete_restricted(
+                    ObjectKind::Planet,
+                    FieldValue::new(PlanetColumns::Id, id),
+                    vec![DependentCount {
+                        object: ObjectKind::Planet,
+                        count: moons,
+                    }],
+                )
+            }
+            _ => TrackerError::from(err),
+        })?;
+    Ok(())
+}
+
+/// The items that can be mined on the planet, ordered by name.
+pub async fn resources<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<Vec<Item>> {
+    lookup(tx, id).await?;
+
+    let (sql, values) = Query::select()
+        .column((ItemColumns::Table, Asterisk))
+        .from(ItemColumns::Table)
+        .inner_join(
+            PlanetResourceColumns::Table,
+            Expr::col((PlanetResourceColumns::Table, PlanetResourceColumns::ItemId))
+                .equals((ItemColumns::Table, ItemColumns::Id)),
+        )
+        .and_where(
+            Expr::col((
+                PlanetResourceColumns::Table,
+                PlanetResourceColumns::PlanetId,
+            ))
+            .eq(id),
+        )
+        .order_by((ItemColumns::Table, ItemColumns::Name), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_as_with::<_, Item, _>(&sql, values.clone())
+        .fetch_all(&mut **tx)
+        .await?)
+}
+
+/// The items the planet's type allows around the spectral class of its
+/// solar system's star, ordered by name. Without a star only the resources
+/// allowed for every class apply.
+pub async fn allowed_resources<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    id: Uuid,
+) -> Result<Vec<Item>> {
+    lookup(tx, id).await?;
+
+    let (sql, values) = Query::select()
+        .distinct()
+        .column((ItemColumns::Table, Asterisk))
+        .from(ItemColumns::Table)
+        .inner_join(
+            PlanetTypeAllowedResourceColumns::Table,
+            Expr::col((
+                PlanetTypeAllowedResourceColumns::Table,
+                PlanetTypeAllowedResourceColumns::ItemId,
+            ))
+            .equals((ItemColumns::Table, ItemColumns::Id)),
+        )
+        .inner_join(
+            PlanetColumns::Table,
+            Expr::col((PlanetColumns::Table, PlanetColumns::PlanetTypeId)).equals((
+                PlanetTypeAllowedResourceColumns::Table,
+                PlanetTypeAllowedResourceColumns::PlanetTypeId,
+            )),
+        )
+        .left_join(
+            StarColumns::Table,
+            Expr::col((StarColumns::Table, StarColumns::SolarSystemId))
+                .equals((PlanetColumns::Table, PlanetColumns::SolarSystemId)),
+        )
+        .and_where(Expr::col((PlanetColumns::Table, PlanetColumns::Id)).eq(id))
+        .and_where(
+            Expr::col((
+                PlanetTypeAllowedResourceColumns::Table,
+                PlanetTypeAllowedResourceColumns::StarSpectralClass,
+            ))
+            .is_null()
+            .or(Expr::col((
+                PlanetTypeAllowedResourceColumns::Table,
+                PlanetTypeAllowedResourceColumns::StarSpectralClass,
+            ))
+            .equals((StarColumns::Table, StarColumns::SpectralClass))),
+        )
+        .order_by((ItemColumns::Table, ItemColumns::Name), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_as_with::<_, Item, _>(&sql, values.clone())
+        .fetch_all(&mut **tx)
+        .await?)
+}
+
+/// Replaces the planet's available resources with `item_ids`; repeated ids are
+/// stored once.
+pub async fn replace_resources<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    id: Uuid,
+    item_ids: &[Uuid],
+) -> Result<Vec<Item>> {
+    lookup(tx, id).await?;
+
+    let (sql, values) = Query::delete()
+        .from_table(PlanetResourceColumns::Table)
+        .and_where(Expr::col(PlanetResourceColumns::PlanetId).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await?;
+
+    // One row per statement so a foreign key violation identifies the missing item.
+    for item_id in item_ids {
+        let (sql, values) = Query::insert()
+            .into_table(PlanetResourceColumns::Table)
+            .columns([
+                PlanetResourceColumns::PlanetId,
+                PlanetResourceColumns::ItemId,
+            ])
+            .values_panic([id.into(), (*item_id).into()])
+            .on_conflict(
+                OnConflict::columns([
+                    PlanetResourceColumns::PlanetId,
+                    PlanetResourceColumns::ItemId,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .build_sqlx(PostgresQueryBuilder);
+
+        sqlx::query_with(&sql, values.clone())
+            .execute(&mut **tx)
+            .await
+            .map_err(|err| map_resource_constraint_errors(err, id, *item_id))?;
+    }
+
+    resources(tx, id).await
+}
+
+/// The parent has to be another planet of the same solar system and must not
+/// be one of the planet's own moons.
+async fn check_parent<'a>(tx: &mut Transaction<'a, Postgres>, planet: &Planet) -> Result<()> {
+    let Some(parent_planet_id) = planet.parent_planet_id else {
+        return Ok(());
+    };
+
+    let parent = lookup(tx, parent_planet_id).await?;
+    if parent.solar_system_id != planet.solar_system_id {
+        let (sql, values) = Query::select()
+            .column(PlanetColumns::Id)
+            .from(PlanetColumns::Table)
+            .and_where(Expr::col(PlanetColumns::SolarSystemId).eq(planet.solar_system_id))
+            .and_where(Expr::col(PlanetColumns::Id).ne(planet.id))
+            .order_by(PlanetColumns::CreatedAt, Order::Asc)
+            .order_by(PlanetColumns::Id, Order::Asc)
+            .build_sqlx(PostgresQueryBuilder);
+
+        let siblings: Vec<(Uuid,)> = sqlx::query_as_with(&sql, values.clone())
+            .fetch_all(&mut **tx)
+            .await?;
+
+        return Err(TrackerError::invalid_field(
+            FieldValue::new(PlanetColumns::ParentPlanetId, parent_planet_id),
+            AllowedValues::choice(siblings.into_iter().map(|(id,)| id)),
+        ));
+    }
+
+    let is_cycle = parent_planet_id == planet.id
+        || ancestry(tx, parent_planet_id)
+            .await?
+            .iter()
+            .any(|ancestor| ancestor.id == planet.id);
+
+    if is_cycle {
+        Err(TrackerError::cyclic_reference(
+            ObjectKind::Planet,
+            FieldValue::new(PlanetColumns::Id, planet.id),
+            FieldValue::new(PlanetColumns::ParentPlanetId, parent_planet_id),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn add_where_clause(select_stmt: &mut SelectStatement, solar_system_id: Uuid, req: &SearchRequest) {
+    select_stmt.and_where(
+        Expr::col((PlanetColumns::Table, PlanetColumns::SolarSystemId)).eq(solar_system_id),
+    );
+
+    if let Some(name) = &req.name {
+        select_stmt.and_where(
+            req.match_mode
+                .predicate((PlanetColumns::Table, PlanetColumns::Name), name),
+        );
+    }
+}
+
+fn add_sort_joins(
+    select_stmt: &mut SelectStatement,
+    sorts: &[Sort<PlanetFields>],
+    joins_tracker: &mut Vec<String>,
+) {
+    for sort in sorts {
+        add_join_for_field(select_stmt, sort.field, joins_tracker);
+    }
+}
+
+pub fn add_join_for_field(
+    select_stmt: &mut SelectStatement,
+    field: PlanetFields,
+    joins_tracker: &mut Vec<String>,
+) {
+    if let PlanetFields::SolarSystem(solar_system_field) = field {
+        let solar_system_table = SolarSystemColumns::Table.to_string();
+        if !joins_tracker.contains(&solar_system_table) {
+            joins_tracker.push(solar_system_table);
+            select_stmt.left_join(
+                SolarSystemColumns::Table,
+                Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Id))
+                    .equals((PlanetColumns::Table, PlanetColumns::SolarSystemId)),
+            );
+        }
+        solar_system::add_join_for_field(select_stmt, solar_system_field, joins_tracker);
+    }
+}
+
+fn map_resource_constraint_errors(err: sqlx::Error, id: Uuid, item_id: Uuid) -> TrackerError {
+    match &err {
+        _ if is_serialization_failure(&err) => TrackerError::concurrent_update(
+            ObjectKind::Planet,
+            FieldValue::new(PlanetColumns::Id, id),
+        ),
+        sqlx::Error::Database(db_err) => match (db_err.kind(), db_err.constraint()) {
+            (ErrorKind::ForeignKeyViolation, Some("planet_available_resources_item_id_fkey")) => {
+                TrackerError::not_found(ObjectKind::Item, FieldValue::new(ItemColumns::Id, item_id))
+            }
+            _ => TrackerError::from(err),
+        },
+        _ => TrackerError::from(err),
+    }
+}
+
+fn map_constraint_errors(err: sqlx::Error, planet: &Planet) -> TrackerError {
+    match &err {
+        _ if is_serialization_failure(&err) => TrackerError::concurrent_update(
+            ObjectKind::Planet,
+            FieldValue::new(PlanetColumns::Id, planet.id),
+        ),
+        sqlx::Error::Database(db_err) => match (db_err.kind(), db_err.constraint()) {
+            (ErrorKind::UniqueViolation, Some("planets_solar_system_id_name_key")) => {
+                TrackerError::duplicate(
+                    ObjectKind::Planet,
+                    [
+                        FieldValue::new(PlanetColumns::SolarSystemId, planet.solar_system_id),
+                        FieldValue::new(PlanetColumns::Name, &planet.name),
+                    ],
+                )
+            }
+            (ErrorKind::ForeignKeyViolation, Some("planets_solar_system_id_fkey")) => {
+                TrackerError::not_found(
+                    ObjectKind::SolarSystem,
+                    FieldValue::new(SolarSystemColumns::Id, planet.solar_system_id),
+                )
+            }
+            (ErrorKind::ForeignKeyViolation, Some("planets_planet_type_id_fkey")) => {
+                TrackerError::not_found(
+                    ObjectKind::PlanetType,
+                    FieldValue::new(PlanetTypeColumns::Id, planet.planet_type_id),
+                )
+            }
+            (ErrorKind::CheckViolation, Some("planets_name_length")) => {
+                TrackerError::invalid_field(
+                    FieldValue::new(PlanetColumns::Name, &planet.name),
+                    AllowedValues::string_len_between(1, MAX_NAME_LENGTH),
+                )
+            }
+            (ErrorKind::CheckViolation, Some("solar_energy_percent_range")) => {
+                TrackerError::invalid_field(
+                    FieldValue::new(
+                        PlanetColumns::SolarEnergyPercent,
+                        planet.solar_energy_percent,
+                    ),
+                    AllowedValues::integer_between(
+                        Bound::inclusive(0),
+                        Bound::inclusive(MAX_PERCENT),
+                    ),
+                )
+            }
+            (ErrorKind::ForeignKeyViolation, Some("planets_parent_planet_id_fkey")) => {
+                match planet.parent_planet_id {
+                    Some(parent_planet_id) => TrackerError::not_found(
+                        ObjectKind::Planet,
+                        FieldValue::new(PlanetColumns::Id, parent_planet_id),
+                    ),
+                    None => TrackerError::from(err),
+                }
+            }
+            _ => TrackerError::from(err),
+        },
+        _ => TrackerError::from(err),
+    }
+}
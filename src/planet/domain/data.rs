@@ -0,0 +1,102 @@
+use crate::utils::normalize_name;
+use chrono::{DateTime, Utc};
+use sea_query::Iden;
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumIter};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Copy, Clone, sqlx::Type, AsRefStr, EnumIter, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "ocean_type", rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum OceanType {
+    Water,
+    Lava,
+    Ice,
+    SulfuricAcid,
+}
+
+#[derive(Debug, Copy, Clone, sqlx::Type, AsRefStr, EnumIter, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "rotation_direction", rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RotationDirection {
+    Normal,
+    Reverse,
+    Horizontal,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Planet {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub version: i32,
+    pub solar_system_id: Uuid,
+    pub parent_planet_id: Option<Uuid>,
+    pub planet_type_id: Uuid,
+    pub name: String,
+    pub orbital_resonance: Option<f32>,
+    pub rotation_direction: RotationDirection,
+    #[sqlx(try_from = "i16")]
+    pub solar_energy_percent: u16,
+}
+
+#[derive(Debug, Copy, Clone, Iden)]
+#[allow(dead_code)]
+pub enum PlanetColumns {
+    #[iden(rename = "planets")]
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Version,
+    SolarSystemId,
+    ParentPlanetId,
+    PlanetTypeId,
+    Name,
+    OrbitalResonance,
+    RotationDirection,
+    SolarEnergyPercent,
+}
+
+#[derive(Debug, Copy, Clone, Iden)]
+pub enum PlanetResourceColumns {
+    #[iden(rename = "planet_available_resources")]
+    Table,
+    PlanetId,
+    ItemId,
+}
+
+impl Planet {
+    pub fn new(
+        solar_system_id: Uuid,
+        parent_planet_id: Option<Uuid>,
+        planet_type_id: Uuid,
+        name: String,
+        orbital_resonance: Option<f32>,
+        rotation_direction: RotationDirection,
+        solar_energy_percent: u16,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: None,
+            version: 0,
+            solar_system_id,
+            parent_planet_id,
+            planet_type_id,
+            name: normalize_name(&name),
+            orbital_resonance,
+            rotation_direction,
+            solar_energy_percent,
+        }
+    }
+}
+
+impl From<PlanetColumns> for String {
+    fn from(value: PlanetColumns) -> Self {
+        value.to_string()
+    }
+}
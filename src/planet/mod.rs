@@ -1 +1,5 @@
-pub mod domain;
+pub mod api;
+mod domain;
+
+pub use api::config;
+pub use domain::*;
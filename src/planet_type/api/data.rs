@@ -0,0 +1,154 @@
+use crate::{
+    data::{MatchMode, PageRequest, PageRequestRaw},
+    error::{TrackerError, Validator},
+    field::Field,
+    field_names,
+    planet::{api::validate_percent, OceanType},
+    planet_type::domain,
+    utils::{
+        double_option, entity_tag, last_modified, validate_search_term, WholeNumber,
+        MAX_NAME_SEARCH_LENGTH,
+    },
+};
+use actix_web::{
+    body::BoxBody,
+    http::header::{ETag, LastModified},
+    HttpResponse, Responder,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PlanetType {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub version: i32,
+    pub name: String,
+    pub ocean_type: Option<OceanType>,
+    pub wind_energy_percent: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CreatePlanetTypeRequest {
+    pub name: String,
+    pub ocean_type: Option<OceanType>,
+    /// Also accepts integer-valued floats and whole-number strings
+    #[schema(value_type = u16)]
+    pub wind_energy_percent: WholeNumber<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdatePlanetTypeRequest {
+    /// The version the changes are based on; it must be the version `If-Match` names
+    pub version: i32,
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub ocean_type: Option<Option<OceanType>>,
+    #[schema(value_type = Option<u16>)]
+    pub wind_energy_percent: Option<WholeNumber<u16>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchRequestRaw {
+    #[serde(flatten)]
+    pub page_request: PageRequestRaw,
+    pub name: Option<String>,
+    pub match_mode: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchRequest {
+    pub page_request: PageRequest<PlanetTypeFields>,
+    pub name: Option<String>,
+    pub match_mode: MatchMode,
+}
+
+impl CreatePlanetTypeRequest {
+    pub fn validate(&self) -> Result<(), TrackerError> {
+        let mut validator = Validator::new();
+        validator.check(
+            self.wind_energy_percent
+                .check("wind_energy_percent")
+                .and_then(|percent| validate_percent("wind_energy_percent", percent)),
+        );
+        validator.finish()
+    }
+}
+
+impl UpdatePlanetTypeRequest {
+    pub fn validate(&self) -> Result<(), TrackerError> {
+        let mut validator = Validator::new();
+        if let Some(wind_energy_percent) = &self.wind_energy_percent {
+            validator.check(
+                wind_energy_percent
+                    .check("wind_energy_percent")
+                    .and_then(|percent| validate_percent("wind_energy_percent", percent)),
+            );
+        }
+        validator.finish()
+    }
+}
+
+impl From<domain::PlanetType> for PlanetType {
+    fn from(value: domain::PlanetType) -> Self {
+        Self {
+            id: value.id,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            version: value.version,
+            name: value.name,
+            ocean_type: value.ocean_type,
+            wind_energy_percent: value.wind_energy_percent,
+        }
+    }
+}
+
+impl Responder for PlanetType {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
+        HttpResponse::Ok()
+            .insert_header(ETag(entity_tag(self.version)))
+            .insert_header(LastModified(last_modified(
+                self.created_at,
+                self.updated_at,
+            )))
+            .json(self)
+    }
+}
+
+impl TryFrom<SearchRequestRaw> for SearchRequest {
+    type Error = TrackerError;
+
+    fn try_from(value: SearchRequestRaw) -> Result<Self, Self::Error> {
+        value.page_request.reject_cursor()?;
+
+        if let Some(name) = &value.name {
+            validate_search_term("name", name, MAX_NAME_SEARCH_LENGTH)?;
+        }
+
+        Ok(Self {
+            page_request: PageRequest::try_from(value.page_request)?,
+            name: value.name,
+            match_mode: MatchMode::try_from(value.match_mode)?,
+        })
+    }
+}
+
+field_names!(
+    PlanetTypeFields<domain::PlanetTypeColumns> {
+        Id => { value: "id", column: Id, kind: Uuid },
+        CreatedAt => { value: "created_at", column: CreatedAt, kind: DateTime },
+        #[default]
+        Name => { value: "name", column: Name },
+        OceanType => { value: "ocean_type", column: OceanType, kind: Enum("ocean_type", OceanType) },
+        WindEnergyPercent => { value: "wind_energy_percent", column: WindEnergyPercent, kind: Integer }
+    }
+);
@@ -0,0 +1,296 @@
+use super::{CreatePlanetTypeRequest, PlanetType, UpdatePlanetTypeRequest};
+use crate::planet_type::api::{SearchRequest, SearchRequestRaw};
+use crate::planet_type::domain;
+use crate::{
+    data::{begin_repeatable_read, with_current_version, Page, PageRequestRaw},
+    error::{log_entity_error, log_error, log_lookup_error, ObjectKind, Result},
+    idempotency::Idempotency,
+    utils::{check_if_match, last_modified, not_modified, ApiResponse},
+    AppState,
+};
+use actix_web::{delete, get, patch, post, web, HttpRequest, HttpResponse, Responder};
+use uuid::Uuid;
+
+#[utoipa::path(
+    operation_id = "createPlanetType",
+    tag = "planet-types",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "A client-chosen key; retrying with the same key returns the originally created resource"),
+    ),
+    request_body = CreatePlanetTypeRequest,
+    responses(
+        (status = 201, description = "The created planet type", body = PlanetType),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 409, description = "A conflicting planet type already exists", body = ErrorResponse),
+    )
+)]
+#[post("/planet-types")]
+async fn create_handler(
+    req: HttpRequest,
+    request: web::Json<CreatePlanetTypeRequest>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<PlanetType>> {
+    request.validate()?;
+
+    let idempotency =
+        Idempotency::from_request(&req, ObjectKind::PlanetType, data.idempotency_key_ttl)?;
+    let mut transaction = data.db.begin().await?;
+    let response = match idempotency.lookup(&mut transaction).await? {
+        Some(id) => domain::lookup(&mut transaction, id).await?,
+        None => {
+            let planet_type = domain::PlanetType::new(
+                request.name.clone(),
+                request.ocean_type,
+                request.wind_energy_percent.value(),
+            );
+
+            let response = domain::create(&mut transaction, &planet_type)
+                .await
+                .inspect_err(log_entity_error(
+                    "Failed to create planet type",
+                    ObjectKind::PlanetType,
+                    planet_type.id,
+                ))?;
+            idempotency.record(&mut transaction, response.id).await?;
+            response
+        }
+    };
+    transaction.commit().await?;
+
+    Ok(ApiResponse::created_versioned(
+        format!("/planet-types/{0}", response.id),
+        response.version,
+        PlanetType::from(response),
+    ))
+}
+
+#[utoipa::path(
+    operation_id = "lookupPlanetType",
+    tag = "planet-types",
+    params(
+        ("id" = Uuid, Path, description = "The planet type id"),
+        ("If-None-Match" = Option<String>, Header, description = "An ETag from a previous response"),
+        ("If-Modified-Since" = Option<String>, Header, description = "The Last-Modified date from a previous response; ignored when If-None-Match is present"),
+    ),
+    responses(
+        (status = 200, description = "The planet type", body = PlanetType),
+        (status = 304, description = "The supplied If-None-Match ETag matches the current version, or the resource is unmodified since If-Modified-Since"),
+        (status = 400, description = "The id is not a valid UUID", body = ErrorResponse),
+        (status = 404, description = "The planet type does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/planet-types/{id}")]
+async fn lookup_handler(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut transaction = data.db.begin().await?;
+
+    let id = path.into_inner();
+    let response = domain::lookup(&mut transaction, id)
+        .await
+        .inspect_err(log_lookup_error(ObjectKind::PlanetType, id))
+        .map(PlanetType::from)?;
+
+    transaction.commit().await?;
+    Ok(not_modified(
+        &req,
+        response.version,
+        last_modified(response.created_at, response.updated_at),
+    )
+    .unwrap_or_else(|| response.respond_to(&req)))
+}
+
+#[utoipa::path(
+    operation_id = "deletePlanetType",
+    tag = "planet-types",
+    params(
+        ("id" = Uuid, Path, description = "The planet type id"),
+    ),
+    responses(
+        (status = 204, description = "The planet type was deleted"),
+        (status = 409, description = "The planet type is still used by planets", body = ErrorResponse),
+    )
+)]
+#[delete("/planet-types/{id}")]
+async fn delete_handler(
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<()>> {
+    let mut transaction = data.db.begin().await?;
+    let id = path.into_inner();
+
+    domain::delete(&mut transaction, id).await?;
+    transaction.commit().await?;
+
+    Ok(ApiResponse::NoContent)
+}
+
+#[utoipa::path(
+    operation_id = "searchPlanetTypes",
+    tag = "planet-types",
+    params(
+        PageRequestRaw,
+        ("match_mode" = Option<String>, Query, description = "One of `start`, `prefix`, `contains` or `exact`"),
+        ("name" = Option<String>, Query, description = "Name to match"),
+    ),
+    responses(
+        (status = 200, description = "A page of planet types", body = PlanetTypePage),
+        (status = 400, description = "The search parameters were invalid", body = ErrorResponse),
+    )
+)]
+#[get("/planet-types")]
+async fn search_handler(
+    query: web::Query<SearchRequestRaw>,
+    data: web::Data<AppState>,
+) -> Result<Page<PlanetType>> {
+    let mut transaction = data.db.begin().await?;
+    let mut search_raw = query.into_inner();
+    search_raw.page_request.paging = data.paging;
+    let search_params = SearchRequest::try_from(search_raw)?;
+
+    let response = domain::search(&mut transaction, &search_params)
+        .await
+        .map(Page::convert)
+        .inspect_err(log_error(
+            "Failed to search for planet types",
+            ObjectKind::PlanetType,
+        ))?;
+    transaction.commit().await?;
+    Ok(response)
+}
+
+#[utoipa::path(
+    operation_id = "updatePlanetType",
+    tag = "planet-types",
+    params(
+        ("id" = Uuid, Path, description = "The planet type id"),
+        ("If-Match" = String, Header, description = "The ETag of the current version"),
+    ),
+    request_body = UpdatePlanetTypeRequest,
+    responses(
+        (status = 200, description = "The updated planet type", body = PlanetType),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 404, description = "The planet type does not exist", body = ErrorResponse),
+        (status = 409, description = "The planet type was concurrently modified or conflicts with an existing planet type", body = ErrorResponse),
+        (status = 412, description = "The planet type has been modified since it was fetched", body = ErrorResponse),
+        (status = 428, description = "The If-Match header is missing", body = ErrorResponse),
+    )
+)]
+#[patch("/planet-types/{id}")]
+async fn update_handler(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    request: web::Json<UpdatePlanetTypeRequest>,
+    data: web::Data<AppState>,
+) -> Result<PlanetType> {
+    request.validate()?;
+
+    let mut transaction = begin_repeatable_read(&data.db).await?;
+    let id = path.into_inner();
+
+    let mut planet_type = domain::lookup(&mut transaction, id).await?;
+    check_if_match(
+        &req,
+        ObjectKind::PlanetType,
+        id,
+        planet_type.version,
+        request.version,
+    )?;
+    planet_type.version = request.version;
+
+    if let Some(name) = &request.name {
+        planet_type.name = name.clone();
+    }
+
+    if let Some(ocean_type) = request.ocean_type {
+        planet_type.ocean_type = ocean_type;
+    }
+
+    if let Some(wind_energy_percent) = &request.wind_energy_percent {
+        planet_type.wind_energy_percent = wind_energy_percent.value();
+    }
+
+    let response = with_current_version(
+        domain::update(&mut transaction, &planet_type).await,
+        &data.db,
+        (
+            domain::PlanetTypeColumns::Table,
+            domain::PlanetTypeColumns::Id,
+            domain::PlanetTypeColumns::Version,
+        ),
+        id,
+        Some(planet_type.version),
+    )
+    .await
+    .inspect_err(log_entity_error(
+        "Failed to update planet type",
+        ObjectKind::PlanetType,
+        id,
+    ))?;
+
+    transaction.commit().await?;
+    Ok(response.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{app_state, db_pool, send, test_app, unique_name};
+    use actix_web::{http::StatusCode, test};
+    use serde_json::json;
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn wind_energy_percent_is_at_most_100() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let create = |wind_energy_percent: u32| {
+            test::TestRequest::post()
+                .uri("/api/1/planet-types")
+                .set_json(json!({
+                    "name": unique_name("Planet Type"),
+                    "wind_energy_percent": wind_energy_percent,
+                }))
+                .to_request()
+        };
+
+        let (status, planet_type) = send(&app, create(100)).await;
+        assert_eq!(status, StatusCode::CREATED, "{planet_type}");
+        assert_eq!(planet_type["wind_energy_percent"], 100);
+
+        let (status, body) = send(&app, create(101)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        assert_eq!(body["error_code"], "InvalidFieldValue");
+        assert_eq!(body["field"]["name"], "wind_energy_percent");
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn wind_energy_percent_accepts_whole_numbers_in_any_json_form() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let create = |wind_energy_percent| {
+            test::TestRequest::post()
+                .uri("/api/1/planet-types")
+                .set_json(json!({
+                    "name": unique_name("Planet Type"),
+                    "wind_energy_percent": wind_energy_percent,
+                }))
+                .to_request()
+        };
+
+        for wind_energy_percent in [json!(50), json!(50.0), json!("50")] {
+            let (status, planet_type) = send(&app, create(wind_energy_percent.clone())).await;
+            assert_eq!(
+                status,
+                StatusCode::CREATED,
+                "{wind_energy_percent}: {planet_type}"
+            );
+            assert_eq!(planet_type["wind_energy_percent"], 50);
+        }
+
+        let (status, body) = send(&app, create(json!(50.5))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        assert_eq!(body["error_code"], "InvalidFieldValue");
+        assert_eq!(body["field"]["name"], "wind_energy_percent");
+    }
+}
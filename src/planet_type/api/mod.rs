@@ -0,0 +1,32 @@
+mod data;
+mod handler;
+
+use actix_web::web;
+pub use data::*;
+use utoipa::OpenApi;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(handler::create_handler)
+        .service(handler::lookup_handler)
+        .service(handler::search_handler)
+        .service(handler::delete_handler)
+        .service(handler::update_handler);
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        handler::create_handler,
+        handler::lookup_handler,
+        handler::search_handler,
+        handler::update_handler,
+        handler::delete_handler,
+    ),
+    components(schemas(
+        PlanetType,
+        CreatePlanetTypeRequest,
+        UpdatePlanetTypeRequest,
+        crate::data::PlanetTypePage,
+    ))
+)]
+pub struct ApiDoc;
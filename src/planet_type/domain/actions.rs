@@ -0,0 +1,225 @@
+use super::{PlanetType, PlanetTypeColumns};
+use crate::{
+    data::{add_sorts, count_rows, is_serialization_failure, paginate, Page},
+    error::{DependentCount, ObjectKind, Result, TrackerError},
+    field::{AllowedValues, Bound, FieldValue},
+    planet::{api::MAX_PERCENT, PlanetColumns},
+    planet_type::api::SearchRequest,
+};
+use sea_query::{
+    Alias, Asterisk, Expr, Func, PostgresQueryBuilder, Query, SelectStatement, SimpleExpr,
+};
+use sea_query_binder::SqlxBinder;
+use sqlx::{error::ErrorKind, Postgres, Transaction};
+use uuid::Uuid;
+
+pub async fn create<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    planet_type: &PlanetType,
+) -> Result<PlanetType> {
+    let (sql, values) = Query::insert()
+        .into_table(PlanetTypeColumns::Table)
+        .columns([
+            PlanetTypeColumns::Id,
+            PlanetTypeColumns::CreatedAt,
+            PlanetTypeColumns::Version,
+            PlanetTypeColumns::Name,
+            PlanetTypeColumns::OceanType,
+            PlanetTypeColumns::WindEnergyPercent,
+        ])
+        .values_panic([
+            planet_type.id.into(),
+            Expr::current_timestamp().into(),
+            planet_type.version.into(),
+            (&planet_type.name).into(),
+            ocean_type_expr(planet_type),
+            planet_type.wind_energy_percent.into(),
+        ])
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| map_constraint_errors(err, planet_type))?;
+
+    lookup(tx, planet_type.id)
+        .await
+        .map_err(TrackerError::not_found_unexpected)
+}
+
+pub async fn update<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    planet_type: &PlanetType,
+) -> Result<PlanetType> {
+    let (sql, values) = Query::update()
+        .table(PlanetTypeColumns::Table)
+        .values([
+            (
+                PlanetTypeColumns::UpdatedAt,
+                Expr::current_timestamp().into(),
+            ),
+            (
+                PlanetTypeColumns::Version,
+                Expr::col(PlanetTypeColumns::Version).add(1),
+            ),
+            (PlanetTypeColumns::Name, planet_type.name.clone().into()),
+            (PlanetTypeColumns::OceanType, ocean_type_expr(planet_type)),
+            (
+                PlanetTypeColumns::WindEnergyPercent,
+                planet_type.wind_energy_percent.into(),
+            ),
+        ])
+        .and_where(Expr::col(PlanetTypeColumns::Id).eq(planet_type.id))
+        .and_where(Expr::col(PlanetTypeColumns::Version).eq(planet_type.version))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows_updated = sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| map_constraint_errors(err, planet_type))?
+        .rows_affected();
+
+    if rows_updated == 0 {
+        Err(TrackerError::concurrent_update(
+            ObjectKind::PlanetType,
+            FieldValue::new(PlanetTypeColumns::Id, planet_type.id),
+        ))
+    } else {
+        lookup(tx, planet_type.id).await
+    }
+}
+
+pub async fn lookup_optional<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    id: Uuid,
+) -> Result<Option<PlanetType>> {
+    let (sql, values) = Query::select()
+        .column((Alias::new("planet_type"), Asterisk))
+        .from_as(PlanetTypeColumns::Table, Alias::new("planet_type"))
+        .and_where(Expr::col(PlanetTypeColumns::Id).eq(id))
+        .limit(1)
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(
+        sqlx::query_as_with::<_, PlanetType, _>(&sql, values.clone())
+            .fetch_optional(&mut **tx)
+            .await?,
+    )
+}
+
+pub async fn lookup<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<PlanetType> {
+    lookup_optional(tx, id)
+        .await
+        .transpose()
+        .unwrap_or_else(|| {
+            Err(TrackerError::not_found(
+                ObjectKind::PlanetType,
+                FieldValue::new(PlanetTypeColumns::Id, id),
+            ))
+        })
+}
+
+pub async fn search<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    search_params: &SearchRequest,
+) -> Result<Page<PlanetType>> {
+    let page_req = &search_params.page_request;
+
+    let mut select_count_stmt = Query::select()
+        .expr(Func::count(Expr::col(Asterisk)))
+        .from(PlanetTypeColumns::Table)
+        .to_owned();
+    add_where_clause(&mut select_count_stmt, search_params);
+
+    let mut select_stmt = Query::select()
+        .expr(Expr::col(Asterisk))
+        .from(PlanetTypeColumns::Table)
+        .to_owned();
+    add_where_clause(&mut select_stmt, search_params);
+    add_sorts(
+        &mut select_stmt,
+        &page_req.sorts,
+        (PlanetTypeColumns::Table, PlanetTypeColumns::Id),
+    );
+
+    paginate(tx, select_count_stmt, select_stmt, page_req).await
+}
+
+pub async fn delete<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<()> {
+    let planets = count_rows(
+        tx,
+        Query::select()
+            .expr(Func::count(Expr::col(Asterisk)))
+            .from(PlanetColumns::Table)
+            .and_where(Expr::col(PlanetColumns::PlanetTypeId).eq(id))
+            .to_owned(),
+    )
+    .await?;
+
+    let (sql, values) = Query::delete()
+        .from_table(PlanetTypeColumns::Table)
+        .and_where(Expr::col(PlanetTypeColumns::Id).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| match &err {
+            sqlx::Error::Database(db_err) if db_err.kind() == ErrorKind::ForeignKeyViolation => {
+                TrackerError::delete_restricted(
+                    ObjectKind::PlanetType,
+                    FieldValue::new(PlanetTypeColumns::Id, id),
+                    vec![DependentCount {
+                        object: ObjectKind::Planet,
+                        count: planets,
+                    }],
+                )
+            }
+            _ => TrackerError::from(err),
+        })?;
+    Ok(())
+}
+
+fn ocean_type_expr(planet_type: &PlanetType) -> SimpleExpr {
+    Expr::val(planet_type.ocean_type.as_ref().map(AsRef::<str>::as_ref))
+        .as_enum(Alias::new("ocean_type"))
+}
+
+fn add_where_clause(select_stmt: &mut SelectStatement, req: &SearchRequest) {
+    if let Some(name) = &req.name {
+        select_stmt.and_where(req.match_mode.predicate(PlanetTypeColumns::Name, name));
+    }
+}
+
+fn map_constraint_errors(err: sqlx::Error, planet_type: &PlanetType) -> TrackerError {
+    match &err {
+        _ if is_serialization_failure(&err) => TrackerError::concurrent_update(
+            ObjectKind::PlanetType,
+            FieldValue::new(PlanetTypeColumns::Id, planet_type.id),
+        ),
+        sqlx::Error::Database(db_err) => match (db_err.kind(), db_err.constraint()) {
+            (ErrorKind::UniqueViolation, Some("planet_types_name_key")) => TrackerError::duplicate(
+                ObjectKind::PlanetType,
+                FieldValue::new(PlanetTypeColumns::Name, &planet_type.name),
+            ),
+            (ErrorKind::UniqueViolation, Some("planet_types_pkey")) => TrackerError::duplicate(
+                ObjectKind::PlanetType,
+                FieldValue::new(PlanetTypeColumns::Id, planet_type.id),
+            ),
+            (ErrorKind::CheckViolation, Some("wind_energy_percent_range")) => {
+                TrackerError::invalid_field(
+                    FieldValue::new(
+                        PlanetTypeColumns::WindEnergyPercent,
+                        planet_type.wind_energy_percent,
+                    ),
+                    AllowedValues::integer_between(
+                        Bound::inclusive(0),
+                        Bound::inclusive(MAX_PERCENT),
+                    ),
+                )
+            }
+            _ => TrackerError::from(err),
+        },
+        _ => TrackerError::from(err),
+    }
+}
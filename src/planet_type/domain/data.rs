@@ -0,0 +1,59 @@
+use crate::planet::OceanType;
+use chrono::{DateTime, Utc};
+use sea_query::Iden;
+use uuid::Uuid;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct PlanetType {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub version: i32,
+    pub name: String,
+    pub ocean_type: Option<OceanType>,
+    #[sqlx(try_from = "i16")]
+    pub wind_energy_percent: u16,
+}
+
+#[derive(Debug, Copy, Clone, Iden)]
+#[allow(dead_code)]
+pub enum PlanetTypeColumns {
+    #[iden(rename = "planet_types")]
+    Table,
+    Id,
+    CreatedAt,
+    UpdatedAt,
+    Version,
+    Name,
+    OceanType,
+    WindEnergyPercent,
+}
+
+#[derive(Debug, Copy, Clone, Iden)]
+pub enum PlanetTypeAllowedResourceColumns {
+    #[iden(rename = "planet_type_allowed_resources")]
+    Table,
+    PlanetTypeId,
+    ItemId,
+    StarSpectralClass,
+}
+
+impl PlanetType {
+    pub fn new(name: String, ocean_type: Option<OceanType>, wind_energy_percent: u16) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: None,
+            version: 0,
+            name,
+            ocean_type,
+            wind_energy_percent,
+        }
+    }
+}
+
+impl From<PlanetTypeColumns> for String {
+    fn from(value: PlanetTypeColumns) -> Self {
+        value.to_string()
+    }
+}
@@ -0,0 +1,5 @@
+mod actions;
+mod data;
+
+pub use actions::*;
+pub use data::*;
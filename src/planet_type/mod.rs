@@ -0,0 +1,5 @@
+pub mod api;
+mod domain;
+
+pub use api::config;
+pub use domain::*;
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReferenceValue {
+    pub value: String,
+    pub label: String,
+}
+
+pub fn reference_values<E: IntoEnumIterator + AsRef<str>>() -> Vec<ReferenceValue> {
+    E::iter()
+        .map(|e| ReferenceValue {
+            value: e.as_ref().to_owned(),
+            label: to_label(e.as_ref()),
+        })
+        .collect()
+}
+
+fn to_label(value: &str) -> String {
+    value
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
@@ -0,0 +1,15 @@
+use super::reference_values;
+use crate::star::SpectralClass;
+use actix_web::{get, HttpResponse};
+
+#[utoipa::path(
+    operation_id = "referenceSpectralClasses",
+    tag = "reference",
+    responses(
+        (status = 200, description = "All spectral classes", body = [ReferenceValue]),
+    )
+)]
+#[get("/reference/spectral-classes")]
+async fn spectral_classes_handler() -> HttpResponse {
+    HttpResponse::Ok().json(reference_values::<SpectralClass>())
+}
@@ -0,0 +1,17 @@
+mod data;
+mod handler;
+
+use actix_web::web;
+pub use data::*;
+use utoipa::OpenApi;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(handler::spectral_classes_handler);
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(handler::spectral_classes_handler,),
+    components(schemas(ReferenceValue,))
+)]
+pub struct ApiDoc;
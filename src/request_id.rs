@@ -0,0 +1,69 @@
+use crate::error::TrackerError;
+use actix_web::{
+    body::EitherBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    HttpMessage,
+};
+use std::fmt;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    fn from_request(req: &ServiceRequest) -> Self {
+        req.headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| is_valid(value))
+            .map(|value| Self(value.to_owned()))
+            .unwrap_or_else(|| Self(Uuid::new_v4().to_string()))
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn is_valid(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+pub fn assign(req: &ServiceRequest) -> RequestId {
+    let request_id = RequestId::from_request(req);
+    req.extensions_mut().insert(request_id.clone());
+    request_id
+}
+
+pub fn attach<B>(
+    res: ServiceResponse<B>,
+    request_id: &RequestId,
+) -> ServiceResponse<EitherBody<B>> {
+    let tracker_error = res
+        .response()
+        .error()
+        .and_then(|err| err.as_error::<TrackerError>());
+
+    let mut res = match tracker_error {
+        Some(err) => {
+            let response = err.error_response_with_request_id(Some(request_id));
+            res.into_response(response).map_into_right_body()
+        }
+        None => res.map_into_left_body(),
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&request_id.0) {
+        res.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    res
+}
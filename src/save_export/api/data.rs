@@ -0,0 +1,310 @@
+use crate::{
+    error::{ObjectKind, TrackerError, Validator},
+    field::{AllowedValues, FieldValue},
+    game_save::{self, api::GameSave},
+    planet::{self, api::Planet},
+    save_export::domain,
+    solar_system::{self, api::SolarSystem, SolarSystemColumns},
+    star::{self, api::Star, domain::StarColumns, SpectralClass},
+    utils::{validate_finite_positive, validate_name},
+};
+use actix_web::{
+    body::BoxBody,
+    error::JsonPayloadError,
+    http::header::{ContentType, HeaderName},
+    web::Bytes,
+    HttpRequest, HttpResponse, Responder, ResponseError,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub const NDJSON: &str = "application/x-ndjson";
+
+pub const CONTENT_SHA256_HEADER: HeaderName = HeaderName::from_static("x-content-sha256");
+
+/// Collections are ordered by `(created_at, id)` so exporting unchanged data
+/// yields byte-identical output.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SaveExport {
+    pub save: GameSave,
+    pub solar_systems: Vec<SolarSystemExport>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SolarSystemExport {
+    pub solar_system: SolarSystem,
+    pub star: Option<Star>,
+    pub planets: Vec<Planet>,
+}
+
+/// A single line of the NDJSON export format.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExportRecord {
+    Save(GameSave),
+    SolarSystem(SolarSystem),
+    Star(Star),
+    Planet(Planet),
+}
+
+impl ExportRecord {
+    pub fn to_ndjson(chunk: domain::ExportChunk) -> Result<Bytes, TrackerError> {
+        let records: Vec<ExportRecord> = match chunk {
+            domain::ExportChunk::Save(save) => vec![Self::Save(save.into())],
+            domain::ExportChunk::SolarSystems(solar_systems) => solar_systems
+                .into_iter()
+                .map(|s| Self::SolarSystem(s.into()))
+                .collect(),
+            domain::ExportChunk::Stars(stars) => {
+                stars.into_iter().map(|s| Self::Star(s.into())).collect()
+            }
+            domain::ExportChunk::Planets(planets) => planets
+                .into_iter()
+                .map(|p| Self::Planet(p.into()))
+                .collect(),
+        };
+
+        let mut body = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut body, &record).map_err(JsonPayloadError::Serialize)?;
+            body.push(b'\n');
+        }
+        Ok(body.into())
+    }
+}
+
+impl From<domain::SaveTree> for SaveExport {
+    fn from(value: domain::SaveTree) -> Self {
+        Self {
+            save: value.save.into(),
+            solar_systems: value
+                .solar_systems
+                .into_iter()
+                .map(SolarSystemExport::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<domain::SolarSystemTree> for SolarSystemExport {
+    fn from(value: domain::SolarSystemTree) -> Self {
+        Self {
+            solar_system: value.solar_system.into(),
+            star: value.star.map(Star::from),
+            planets: value.planets.into_iter().map(Planet::from).collect(),
+        }
+    }
+}
+
+impl SaveExport {
+    pub fn validate(
+        &self,
+        max_mining_speed: u32,
+        allowed_spectral_classes: Option<&[SpectralClass]>,
+    ) -> Result<(), TrackerError> {
+        let mut validator = Validator::new();
+        validator
+            .check(validate_name("save.name", &self.save.name))
+            .check(game_save::api::validate_mining_speed(
+                self.save.mining_speed,
+                max_mining_speed,
+            ));
+        for solar_system in &self.solar_systems {
+            validator.check(validate_name(
+                "solar_system.name",
+                &solar_system.solar_system.name,
+            ));
+            for planet in &solar_system.planets {
+                validator.check(validate_name("planet.name", &planet.name));
+                if let Some(orbital_resonance) = planet.orbital_resonance {
+                    validator.check(validate_finite_positive(
+                        "orbital_resonance",
+                        orbital_resonance,
+                    ));
+                }
+            }
+            if let Some(star) = &solar_system.star {
+                validator
+                    .check(star::api::validate_spectral_class(
+                        "spectral_class",
+                        star.spectral_class,
+                        allowed_spectral_classes,
+                    ))
+                    .check(validate_finite_positive("luminosity", star.luminosity))
+                    .check(validate_finite_positive("radius", star.radius));
+            }
+        }
+        validator.finish()
+    }
+
+    /// Assembles an export from NDJSON records; stars and planets are attached
+    /// to their solar system by `solar_system_id` regardless of line order.
+    pub fn from_ndjson(body: &[u8]) -> Result<Self, TrackerError> {
+        let mut save = None;
+        let mut solar_systems: Vec<SolarSystemExport> = Vec::new();
+        let mut solar_system_indexes: HashMap<Uuid, usize> = HashMap::new();
+        let mut stars = Vec::new();
+        let mut planets = Vec::new();
+
+        let lines = body
+            .split(|b| *b == b'\n')
+            .enumerate()
+            .filter(|(_, line)| !line.trim_ascii().is_empty());
+        for (index, line) in lines {
+            let record = serde_json::from_slice(line).map_err(|e| {
+                TrackerError::batch_item(index, JsonPayloadError::Deserialize(e).into())
+            })?;
+            match record {
+                ExportRecord::Save(s) => {
+                    if save.replace(s).is_some() {
+                        return Err(TrackerError::batch_item(
+                            index,
+                            TrackerError::invalid_field(
+                                FieldValue::new("type", "save"),
+                                AllowedValues::choice(["solar_system", "star", "planet"]),
+                            ),
+                        ));
+                    }
+                }
+                ExportRecord::SolarSystem(s) => {
+                    solar_system_indexes.insert(s.id, solar_systems.len());
+                    solar_systems.push(SolarSystemExport {
+                        solar_system: s,
+                        star: None,
+                        planets: Vec::new(),
+                    });
+                }
+                ExportRecord::Star(s) => stars.push(s),
+                ExportRecord::Planet(p) => planets.push(p),
+            }
+        }
+
+        let save = save
+            .ok_or_else(|| TrackerError::missing_field("type", AllowedValues::choice(["save"])))?;
+
+        let solar_system_index = |solar_system_id: Uuid| {
+            solar_system_indexes
+                .get(&solar_system_id)
+                .copied()
+                .ok_or_else(|| {
+                    TrackerError::not_found(
+                        ObjectKind::SolarSystem,
+                        FieldValue::new(SolarSystemColumns::Id, solar_system_id),
+                    )
+                })
+        };
+        for star in stars {
+            let solar_system = &mut solar_systems[solar_system_index(star.solar_system_id)?];
+            if solar_system.star.is_some() {
+                return Err(TrackerError::duplicate(
+                    ObjectKind::Star,
+                    FieldValue::new(StarColumns::SolarSystemId, star.solar_system_id),
+                ));
+            }
+            solar_system.star = Some(star);
+        }
+        for planet in planets {
+            solar_systems[solar_system_index(planet.solar_system_id)?]
+                .planets
+                .push(planet);
+        }
+
+        Ok(Self {
+            save,
+            solar_systems,
+        })
+    }
+
+    pub fn to_domain(&self, owner_id: Option<Uuid>) -> domain::SaveTree {
+        let save = game_save::GameSave::new(
+            self.save.name.clone(),
+            self.save.notes.clone(),
+            self.save.mining_speed,
+            self.save.allow_duplicate_names,
+            owner_id,
+        );
+
+        let solar_systems = self
+            .solar_systems
+            .iter()
+            .map(|s| s.to_domain(save.id))
+            .collect();
+
+        domain::SaveTree {
+            save,
+            solar_systems,
+        }
+    }
+}
+
+impl SolarSystemExport {
+    fn to_domain(&self, save_id: Uuid) -> domain::SolarSystemTree {
+        let solar_system = solar_system::SolarSystem::new(
+            save_id,
+            self.solar_system.name.clone(),
+            self.solar_system.notes.clone(),
+        );
+
+        let star = self.star.as_ref().map(|star| {
+            star::domain::Star::new(
+                solar_system.id,
+                star.spectral_class,
+                star.luminosity,
+                star.radius,
+            )
+        });
+
+        let mut planets: Vec<planet::Planet> = Vec::with_capacity(self.planets.len());
+        let mut planet_ids: HashMap<Uuid, Uuid> = HashMap::with_capacity(self.planets.len());
+        for p in &self.planets {
+            let new_planet = planet::Planet::new(
+                solar_system.id,
+                p.parent_planet_id,
+                p.planet_type_id,
+                p.name.clone(),
+                p.orbital_resonance,
+                p.rotation_direction,
+                p.solar_energy_percent,
+            );
+            planet_ids.insert(p.id, new_planet.id);
+            planets.push(new_planet);
+        }
+
+        for p in &mut planets {
+            if let Some(parent_planet_id) = p.parent_planet_id {
+                p.parent_planet_id = planet_ids
+                    .get(&parent_planet_id)
+                    .copied()
+                    .or(Some(parent_planet_id));
+            }
+        }
+
+        domain::SolarSystemTree {
+            solar_system,
+            star,
+            planets,
+        }
+    }
+}
+
+impl Responder for SaveExport {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse<Self::Body> {
+        let body = match serde_json::to_vec(&self) {
+            Ok(body) => body,
+            Err(e) => return TrackerError::from(JsonPayloadError::Serialize(e)).error_response(),
+        };
+        let content_hash = format!("{:x}", Sha256::digest(&body));
+
+        HttpResponse::Ok()
+            .content_type(ContentType::json())
+            .insert_header((CONTENT_SHA256_HEADER, content_hash))
+            .body(body)
+    }
+}
@@ -0,0 +1,267 @@
+use super::{ExportRecord, SaveExport, NDJSON};
+use crate::{
+    auth::Owner,
+    data::begin_repeatable_read,
+    error::{log_entity_error, log_lookup_error, ObjectKind, Result},
+    utils::ApiResponse,
+    AppState,
+};
+use crate::{game_save, save_export::domain};
+use actix_web::{
+    get,
+    guard::GuardContext,
+    http::header::{self, Accept},
+    post, web, HttpResponse,
+};
+use futures_util::StreamExt;
+use uuid::Uuid;
+
+#[utoipa::path(
+    operation_id = "exportSave",
+    tag = "saves",
+    params(
+        ("id" = Uuid, Path, description = "The save id"),
+    ),
+    responses(
+        (status = 200, description = "The save and all of its contents, ordered by creation time; X-Content-SHA256 holds the hex SHA-256 of the body. With `Accept: application/x-ndjson` the export is streamed as one `type`-tagged record per line: the save, then its solar systems, stars and planets", content(
+            ("application/json" = SaveExport),
+            ("application/x-ndjson" = String),
+        )),
+        (status = 404, description = "The save does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/saves/{id}/export")]
+async fn export_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<SaveExport> {
+    let mut transaction = begin_repeatable_read(&data.db).await?;
+    let id = path.into_inner();
+    game_save::check_owner(&mut transaction, owner, id).await?;
+
+    let response = domain::export(&mut transaction, id)
+        .await
+        .map(SaveExport::from)
+        .inspect_err(log_entity_error(
+            "Failed to export save",
+            ObjectKind::Save,
+            id,
+        ))?;
+
+    transaction.commit().await?;
+    Ok(response)
+}
+
+fn accepts_ndjson(ctx: &GuardContext) -> bool {
+    ctx.header::<Accept>()
+        .is_some_and(|accept| accept.iter().any(|q| q.item.essence_str() == NDJSON))
+}
+
+fn is_ndjson(ctx: &GuardContext) -> bool {
+    ctx.head()
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|essence| essence.trim().eq_ignore_ascii_case(NDJSON))
+}
+
+#[get("/saves/{id}/export", guard = "accepts_ndjson")]
+async fn export_ndjson_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut transaction = begin_repeatable_read(&data.db).await?;
+    let id = path.into_inner();
+    game_save::check_owner(&mut transaction, owner, id).await?;
+
+    let save = game_save::lookup(&mut transaction, id)
+        .await
+        .inspect_err(log_lookup_error(ObjectKind::Save, id))?;
+
+    let body = domain::export_stream(transaction, save).map(move |chunk| {
+        chunk
+            .inspect_err(log_entity_error(
+                "Failed to export save",
+                ObjectKind::Save,
+                id,
+            ))
+            .and_then(ExportRecord::to_ndjson)
+    });
+    Ok(HttpResponse::Ok().content_type(NDJSON).streaming(body))
+}
+
+#[utoipa::path(
+    operation_id = "importSave",
+    tag = "saves",
+    request_body(
+        description = "A JSON export, or with `Content-Type: application/x-ndjson` the streamed export format",
+        content = SaveExport,
+    ),
+    responses(
+        (status = 201, description = "The imported save with newly assigned ids", body = SaveExport),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 409, description = "A conflicting save already exists", body = ErrorResponse),
+    )
+)]
+#[post("/saves/import")]
+async fn import_handler(
+    owner: Owner,
+    request: web::Json<SaveExport>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<SaveExport>> {
+    import(owner, &request, &data).await
+}
+
+#[post("/saves/import", guard = "is_ndjson")]
+async fn import_ndjson_handler(
+    owner: Owner,
+    body: web::Bytes,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<SaveExport>> {
+    let request = SaveExport::from_ndjson(&body)?;
+    import(owner, &request, &data).await
+}
+
+async fn import(
+    owner: Owner,
+    request: &SaveExport,
+    data: &AppState,
+) -> Result<ApiResponse<SaveExport>> {
+    request.validate(
+        data.max_mining_speed,
+        data.allowed_spectral_classes.as_deref(),
+    )?;
+
+    let mut transaction = data.db.begin().await?;
+    let tree = request.to_domain(owner.id());
+
+    let response = domain::import(&mut transaction, &tree)
+        .await
+        .map(SaveExport::from)
+        .inspect_err(log_entity_error(
+            "Failed to import save",
+            ObjectKind::Save,
+            tree.save.id,
+        ))?;
+
+    transaction.commit().await?;
+    Ok(ApiResponse::created(
+        format!("/saves/{0}", response.save.id),
+        response,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        save_export::api::CONTENT_SHA256_HEADER,
+        test_support::{
+            app_state, create_planet_type, create_save, create_solar_system, db_pool, post_planet,
+            send, test_app,
+        },
+    };
+    use actix_web::{http::StatusCode, test};
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn exporting_unchanged_data_is_byte_identical() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let save_id = save["id"].as_str().unwrap();
+        for name in ["Sol", "Solar Prime", "Alpha Centauri"] {
+            create_solar_system(&app, save_id, name).await;
+        }
+
+        let mut exports = Vec::new();
+        for _ in 0..2 {
+            let res = test::call_service(
+                &app,
+                test::TestRequest::get()
+                    .uri(&format!("/api/1/saves/{save_id}/export"))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(res.status(), StatusCode::OK);
+            let hash = res
+                .headers()
+                .get(CONTENT_SHA256_HEADER)
+                .expect("the export should carry its hash")
+                .to_str()
+                .unwrap()
+                .to_owned();
+            let body = test::read_body(res).await;
+            assert_eq!(hash, format!("{:x}", Sha256::digest(&body)));
+            exports.push((body, hash));
+        }
+
+        assert_eq!(exports[0], exports[1]);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn export_nests_stars_and_planets_under_their_solar_system() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let save_id = save["id"].as_str().unwrap();
+        let planet_type = create_planet_type(&app).await;
+        let sol = create_solar_system(&app, save_id, "Sol").await;
+        let sol_id = sol["id"].as_str().unwrap();
+        let prime = create_solar_system(&app, save_id, "Solar Prime").await;
+        let prime_id = prime["id"].as_str().unwrap();
+
+        let (status, star) = send(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/api/1/solar-systems/{prime_id}/star"))
+                .set_json(json!({ "spectral_class": "class_g", "luminosity": 1.0, "radius": 1.0 }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED, "{star}");
+        let mut planet_ids = Vec::new();
+        for solar_system_id in [sol_id, prime_id, sol_id] {
+            let (status, planet) = post_planet(
+                &app,
+                solar_system_id,
+                json!({ "planet_type_id": planet_type["id"] }),
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED, "{planet}");
+            planet_ids.push(planet["id"].clone());
+        }
+
+        let (status, export) = send(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!("/api/1/saves/{save_id}/export"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{export}");
+        let solar_systems = export["solar_systems"].as_array().unwrap();
+        assert_eq!(solar_systems.len(), 2);
+
+        let planet_ids_of = |i: usize| -> Vec<serde_json::Value> {
+            solar_systems[i]["planets"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|planet| planet["id"].clone())
+                .collect()
+        };
+        assert_eq!(solar_systems[0]["solar_system"]["id"], sol["id"]);
+        assert_eq!(solar_systems[0]["star"], serde_json::Value::Null);
+        assert_eq!(
+            planet_ids_of(0),
+            [planet_ids[0].clone(), planet_ids[2].clone()]
+        );
+        assert_eq!(solar_systems[1]["solar_system"]["id"], prime["id"]);
+        assert_eq!(solar_systems[1]["star"]["id"], star["id"]);
+        assert_eq!(planet_ids_of(1), [planet_ids[1].clone()]);
+    }
+}
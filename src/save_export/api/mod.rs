@@ -0,0 +1,20 @@
+mod data;
+mod handler;
+
+use actix_web::web;
+pub use data::*;
+use utoipa::OpenApi;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(handler::export_ndjson_handler)
+        .service(handler::export_handler)
+        .service(handler::import_ndjson_handler)
+        .service(handler::import_handler);
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(handler::export_handler, handler::import_handler,),
+    components(schemas(SaveExport, SolarSystemExport,))
+)]
+pub struct ApiDoc;
@@ -0,0 +1,289 @@
+use super::{ExportChunk, SaveTree, SolarSystemTree};
+use crate::{
+    data::{add_sorts, paginate, Page, PageRequest, Sort, FIRST_PAGE, MAX_PAGE_SIZE},
+    error::{ObjectKind, Result, TrackerError},
+    field::FieldValue,
+    game_save::{self, GameSave},
+    planet::{self, api::PlanetFields, Planet, PlanetColumns},
+    solar_system::{self, api::SolarSystemFields, SolarSystem, SolarSystemColumns},
+    star::{
+        self,
+        api::StarFields,
+        domain::{Star, StarColumns},
+    },
+};
+use futures_util::{stream, Stream};
+use sea_query::{Asterisk, Expr, Func, Order, PostgresQueryBuilder, Query, SelectStatement};
+use sea_query_binder::SqlxBinder;
+use sqlx::{Postgres, Transaction};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Reads the whole save with one query per table, so the export is only a
+/// consistent snapshot when `tx` is at least `REPEATABLE READ`.
+pub async fn export<'a>(tx: &mut Transaction<'a, Postgres>, save_id: Uuid) -> Result<SaveTree> {
+    let save = game_save::lookup(tx, save_id).await?;
+    let solar_systems = export_solar_systems(tx, save_id).await?;
+    let mut stars = export_stars(tx, save_id).await?;
+    let mut planets = export_planets(tx, save_id).await?;
+
+    let solar_system_trees = solar_systems
+        .into_iter()
+        .map(|solar_system| SolarSystemTree {
+            star: stars.remove(&solar_system.id),
+            planets: planets.remove(&solar_system.id).unwrap_or_default(),
+            solar_system,
+        })
+        .collect();
+
+    Ok(SaveTree {
+        save,
+        solar_systems: solar_system_trees,
+    })
+}
+
+pub async fn import<'a>(tx: &mut Transaction<'a, Postgres>, tree: &SaveTree) -> Result<SaveTree> {
+    game_save::create(tx, &tree.save).await?;
+
+    for solar_system_tree in &tree.solar_systems {
+        solar_system::create(tx, &solar_system_tree.solar_system).await?;
+
+        if let Some(star) = &solar_system_tree.star {
+            star::domain::create(tx, star).await?;
+        }
+
+        import_planets(tx, &solar_system_tree.planets).await?;
+    }
+
+    export(tx, tree.save.id).await
+}
+
+async fn import_planets<'a>(tx: &mut Transaction<'a, Postgres>, planets: &[Planet]) -> Result<()> {
+    let mut created: HashSet<Uuid> = HashSet::with_capacity(planets.len());
+    let mut remaining: Vec<&Planet> = planets.iter().collect();
+
+    while !remaining.is_empty() {
+        let (ready, pending): (Vec<&Planet>, Vec<&Planet>) = remaining.into_iter().partition(|p| {
+            p.parent_planet_id
+                .is_none_or(|parent_id| created.contains(&parent_id))
+        });
+
+        if ready.is_empty() {
+            let parent_planet_id = pending[0].parent_planet_id.unwrap_or_default();
+            return Err(TrackerError::not_found(
+                ObjectKind::Planet,
+                FieldValue::new(PlanetColumns::Id, parent_planet_id),
+            ));
+        }
+
+        for planet in ready {
+            planet::create(tx, planet).await?;
+            created.insert(planet.id);
+        }
+        remaining = pending;
+    }
+    Ok(())
+}
+
+/// Streams a save page by page so memory stays bounded by `MAX_PAGE_SIZE`.
+/// The transaction is committed once the last page has been produced.
+pub fn export_stream(
+    tx: Transaction<'static, Postgres>,
+    save: GameSave,
+) -> impl Stream<Item = Result<ExportChunk>> {
+    let save_id = save.id;
+    stream::try_unfold(
+        (tx, Some(ExportPhase::Save(save))),
+        move |(mut tx, phase)| async move {
+            let Some(phase) = phase else {
+                tx.commit().await?;
+                return Ok(None);
+            };
+
+            let (chunk, next_phase) = match phase {
+                ExportPhase::Save(save) => (
+                    ExportChunk::Save(save),
+                    Some(ExportPhase::SolarSystems(FIRST_PAGE)),
+                ),
+                ExportPhase::SolarSystems(page) => {
+                    let page = solar_system::search(&mut tx, save_id, &solar_systems_request(page))
+                        .await?;
+                    let next_phase = match page.metadata.next_page {
+                        Some(next_page) => ExportPhase::SolarSystems(next_page),
+                        None => ExportPhase::Stars(FIRST_PAGE),
+                    };
+                    (ExportChunk::SolarSystems(page.data), Some(next_phase))
+                }
+                ExportPhase::Stars(page) => {
+                    let page = star::domain::search(&mut tx, save_id, &stars_request(page)).await?;
+                    let next_phase = match page.metadata.next_page {
+                        Some(next_page) => ExportPhase::Stars(next_page),
+                        None => ExportPhase::Planets(FIRST_PAGE),
+                    };
+                    (ExportChunk::Stars(page.data), Some(next_phase))
+                }
+                ExportPhase::Planets(page) => {
+                    let page = search_save_planets(&mut tx, save_id, page).await?;
+                    let next_phase = page.metadata.next_page.map(ExportPhase::Planets);
+                    (ExportChunk::Planets(page.data), next_phase)
+                }
+            };
+            Ok(Some((chunk, (tx, next_phase))))
+        },
+    )
+}
+
+enum ExportPhase {
+    Save(GameSave),
+    SolarSystems(u64),
+    Stars(u64),
+    Planets(u64),
+}
+
+fn solar_systems_request(page: u64) -> solar_system::api::SearchRequest {
+    solar_system::api::SearchRequest {
+        page_request: PageRequest::new(
+            page,
+            MAX_PAGE_SIZE,
+            vec![
+                Sort::asc(SolarSystemFields::CreatedAt),
+                Sort::asc(SolarSystemFields::Id),
+            ],
+        ),
+        names: Vec::new(),
+        match_mode: Default::default(),
+        notes: None,
+        updated_after: None,
+        without_star: false,
+        includes: Default::default(),
+        fields: Default::default(),
+    }
+}
+
+fn stars_request(page: u64) -> star::api::SearchRequest {
+    star::api::SearchRequest {
+        page_request: PageRequest::new(
+            page,
+            MAX_PAGE_SIZE,
+            vec![Sort::asc(StarFields::CreatedAt), Sort::asc(StarFields::Id)],
+        ),
+        spectral_class: None,
+        luminosity_min: None,
+        luminosity_max: None,
+        radius_min: None,
+        radius_max: None,
+    }
+}
+
+fn planets_request(page: u64) -> planet::api::SearchRequest {
+    planet::api::SearchRequest {
+        page_request: PageRequest::new(
+            page,
+            MAX_PAGE_SIZE,
+            vec![
+                Sort::asc(PlanetFields::CreatedAt),
+                Sort::asc(PlanetFields::Id),
+            ],
+        ),
+        name: None,
+        match_mode: Default::default(),
+    }
+}
+
+async fn search_save_planets<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+    page: u64,
+) -> Result<Page<Planet>> {
+    let page_req = planets_request(page).page_request;
+    let in_save =
+        Expr::col(PlanetColumns::SolarSystemId).in_subquery(save_solar_system_ids(save_id));
+
+    let select_count_stmt = Query::select()
+        .expr(Func::count(Expr::col(Asterisk)))
+        .from(PlanetColumns::Table)
+        .and_where(in_save.clone())
+        .to_owned();
+
+    let mut select_stmt = Query::select()
+        .column(Asterisk)
+        .from(PlanetColumns::Table)
+        .and_where(in_save)
+        .to_owned();
+    add_sorts(&mut select_stmt, &page_req.sorts, PlanetColumns::Id);
+
+    paginate(tx, select_count_stmt, select_stmt, &page_req).await
+}
+
+fn save_solar_system_ids(save_id: Uuid) -> SelectStatement {
+    Query::select()
+        .column(SolarSystemColumns::Id)
+        .from(SolarSystemColumns::Table)
+        .and_where(Expr::col(SolarSystemColumns::SaveId).eq(save_id))
+        .to_owned()
+}
+
+async fn export_solar_systems<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+) -> Result<Vec<SolarSystem>> {
+    let (sql, values) = Query::select()
+        .column(Asterisk)
+        .from(SolarSystemColumns::Table)
+        .and_where(Expr::col(SolarSystemColumns::SaveId).eq(save_id))
+        .order_by(SolarSystemColumns::CreatedAt, Order::Asc)
+        .order_by(SolarSystemColumns::Id, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_as_with::<_, SolarSystem, _>(&sql, values)
+        .fetch_all(&mut **tx)
+        .await?)
+}
+
+async fn export_stars<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+) -> Result<HashMap<Uuid, Star>> {
+    let (sql, values) = Query::select()
+        .column(Asterisk)
+        .from(StarColumns::Table)
+        .and_where(
+            Expr::col(StarColumns::SolarSystemId).in_subquery(save_solar_system_ids(save_id)),
+        )
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_as_with::<_, Star, _>(&sql, values)
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .map(|star| (star.solar_system_id, star))
+        .collect())
+}
+
+/// The save's planets grouped by solar system, each group in creation order.
+async fn export_planets<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+) -> Result<HashMap<Uuid, Vec<Planet>>> {
+    let (sql, values) = Query::select()
+        .column(Asterisk)
+        .from(PlanetColumns::Table)
+        .and_where(
+            Expr::col(PlanetColumns::SolarSystemId).in_subquery(save_solar_system_ids(save_id)),
+        )
+        .order_by(PlanetColumns::CreatedAt, Order::Asc)
+        .order_by(PlanetColumns::Id, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let mut planets: HashMap<Uuid, Vec<Planet>> = HashMap::new();
+    for planet in sqlx::query_as_with::<_, Planet, _>(&sql, values)
+        .fetch_all(&mut **tx)
+        .await?
+    {
+        planets
+            .entry(planet.solar_system_id)
+            .or_default()
+            .push(planet);
+    }
+    Ok(planets)
+}
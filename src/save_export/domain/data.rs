@@ -0,0 +1,24 @@
+use crate::{game_save::GameSave, planet::Planet, solar_system::SolarSystem, star::domain::Star};
+
+#[derive(Debug)]
+pub struct SaveTree {
+    pub save: GameSave,
+    pub solar_systems: Vec<SolarSystemTree>,
+}
+
+#[derive(Debug)]
+pub struct SolarSystemTree {
+    pub solar_system: SolarSystem,
+    pub star: Option<Star>,
+    pub planets: Vec<Planet>,
+}
+
+/// One step of a streamed export: the save, then pages of its solar systems,
+/// stars and planets.
+#[derive(Debug)]
+pub enum ExportChunk {
+    Save(GameSave),
+    SolarSystems(Vec<SolarSystem>),
+    Stars(Vec<Star>),
+    Planets(Vec<Planet>),
+}
@@ -1,50 +1,269 @@
 use crate::{
-    data::{PageRequest, PageRequestRaw},
+    data::{
+        FieldsRequestRaw, IncludeRequestRaw, Includes, MatchMode, PageRequest, PageRequestRaw,
+        SparseFields,
+    },
     error::TrackerError,
-    field::Field,
+    field::{AllowedValues, Field, FieldValue},
     field_names,
     game_save::api::SaveFields,
     solar_system::domain,
-    utils::double_option,
+    star::api::Star,
+    utils::{
+        comma_separated, double_option, entity_tag, last_modified, parse_datetime, validate_name,
+        validate_search_term, MAX_NAME_SEARCH_LENGTH, MAX_NOTES_SEARCH_LENGTH,
+    },
+};
+use actix_web::{
+    body::BoxBody,
+    http::header::{ETag, LastModified},
+    HttpResponse, Responder,
 };
-use actix_web::{body::BoxBody, HttpResponse, Responder};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumIter, EnumString};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SolarSystem {
     pub id: Uuid,
     pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub version: i32,
     pub save_id: Uuid,
     pub name: String,
     pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub planet_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Star>)]
+    pub star: Option<Option<Star>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, AsRefStr, EnumIter, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum SolarSystemInclude {
+    PlanetCount,
+    Star,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
 pub struct CreateSolarSystemRequest {
     pub name: String,
     pub notes: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
 pub struct UpdateSolarSystemRequest {
+    /// The version the changes are based on; it must be the version `If-Match` names
+    pub version: i32,
     pub name: Option<String>,
     #[serde(default, deserialize_with = "double_option")]
     pub notes: Option<Option<String>>,
 }
 
+impl CreateSolarSystemRequest {
+    pub fn validate(&self) -> Result<(), TrackerError> {
+        validate_name("name", &self.name)
+    }
+}
+
+impl UpdateSolarSystemRequest {
+    pub fn validate(&self) -> Result<(), TrackerError> {
+        if let Some(name) = &self.name {
+            validate_name("name", name)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BatchCreateSolarSystemsRequest {
+    pub solar_systems: Vec<CreateSolarSystemRequest>,
+}
+
+impl BatchCreateSolarSystemsRequest {
+    pub fn validate(&self) -> Result<(), TrackerError> {
+        for (index, solar_system) in self.solar_systems.iter().enumerate() {
+            solar_system
+                .validate()
+                .map_err(|err| TrackerError::batch_item(index, err))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Created,
+    Duplicate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub status: BatchItemStatus,
+    pub id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BatchCreateSolarSystemsResponse {
+    pub results: Vec<BatchItemResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BulkDeleteSolarSystemsRequestRaw {
+    /// Comma separated list of solar system ids to delete.
+    #[serde(default, deserialize_with = "comma_separated")]
+    #[param(value_type = String)]
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BulkDeleteSolarSystemsRequest {
+    pub ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkDeleteSolarSystemsResponse {
+    pub deleted: Vec<Uuid>,
+    pub not_found: Vec<Uuid>,
+}
+
+impl TryFrom<BulkDeleteSolarSystemsRequestRaw> for BulkDeleteSolarSystemsRequest {
+    type Error = TrackerError;
+
+    fn try_from(value: BulkDeleteSolarSystemsRequestRaw) -> Result<Self, Self::Error> {
+        if value.ids.is_empty() {
+            return Err(TrackerError::missing_field("ids", AllowedValues::uuid()));
+        }
+
+        let mut ids: Vec<Uuid> = Vec::with_capacity(value.ids.len());
+        for raw in value.ids {
+            let id = raw.trim().parse::<Uuid>().map_err(|_| {
+                TrackerError::invalid_field(
+                    FieldValue::new("ids", raw.as_str()),
+                    AllowedValues::uuid(),
+                )
+            })?;
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        Ok(Self { ids })
+    }
+}
+
+impl BulkDeleteSolarSystemsResponse {
+    pub fn new(requested: &[Uuid], deleted: &[Uuid]) -> Self {
+        let (deleted, not_found) = requested.iter().partition(|id| deleted.contains(id));
+        Self { deleted, not_found }
+    }
+}
+
+impl From<Vec<domain::BatchCreateOutcome>> for BatchCreateSolarSystemsResponse {
+    fn from(value: Vec<domain::BatchCreateOutcome>) -> Self {
+        let results = value
+            .into_iter()
+            .enumerate()
+            .map(|(index, outcome)| match outcome {
+                domain::BatchCreateOutcome::Created(solar_system) => BatchItemResult {
+                    index,
+                    status: BatchItemStatus::Created,
+                    id: solar_system.id,
+                },
+                domain::BatchCreateOutcome::Duplicate(id) => BatchItemResult {
+                    index,
+                    status: BatchItemStatus::Duplicate,
+                    id,
+                },
+            })
+            .collect();
+        Self { results }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MoveSolarSystemRequest {
+    /// The version the changes are based on; it must be the version `If-Match` names
+    pub version: i32,
+    pub save_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NameAvailableRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NameAvailableResponse {
+    pub available: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchRequestRaw {
     #[serde(flatten)]
     pub page_request: PageRequestRaw,
     pub name: Option<String>,
+    #[serde(skip)]
+    pub names: Vec<String>,
+    pub match_mode: Option<String>,
+    pub notes: Option<String>,
+    pub updated_after: Option<String>,
+    #[serde(flatten)]
+    pub include_request: IncludeRequestRaw,
+    #[serde(flatten)]
+    pub fields_request: FieldsRequestRaw,
 }
 
 #[derive(Debug, Clone)]
 pub struct SearchRequest {
     pub page_request: PageRequest<SolarSystemFields>,
-    pub name: Option<String>,
+    pub names: Vec<String>,
+    pub match_mode: MatchMode,
+    pub notes: Option<String>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub without_star: bool,
+    pub includes: Includes<SolarSystemInclude>,
+    pub fields: SparseFields<SolarSystemFields>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextSearchRequestRaw {
+    #[serde(flatten)]
+    pub page_request: PageRequestRaw,
+    pub q: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TextSearchRequest {
+    pub page_request: PageRequest<SolarSystemFields>,
+    pub query: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SolarSystemSearchResult {
+    #[serde(flatten)]
+    pub solar_system: SolarSystem,
+    pub star: Option<Star>,
+}
+
+impl From<domain::SolarSystemMatch> for SolarSystemSearchResult {
+    fn from(value: domain::SolarSystemMatch) -> Self {
+        Self {
+            solar_system: value.solar_system.into(),
+            star: value.star.map(Star::from),
+        }
+    }
 }
 
 impl From<domain::SolarSystem> for SolarSystem {
@@ -52,9 +271,13 @@ impl From<domain::SolarSystem> for SolarSystem {
         Self {
             id: value.id,
             created_at: value.created_at,
+            updated_at: value.updated_at,
+            version: value.version,
             save_id: value.save_id,
             name: value.name,
             notes: value.notes,
+            planet_count: value.planet_count.map(|c| c as u64),
+            star: None,
         }
     }
 }
@@ -63,7 +286,13 @@ impl Responder for SolarSystem {
     type Body = BoxBody;
 
     fn respond_to(self, _req: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
-        HttpResponse::Ok().json(self)
+        HttpResponse::Ok()
+            .insert_header(ETag(entity_tag(self.version)))
+            .insert_header(LastModified(last_modified(
+                self.created_at,
+                self.updated_at,
+            )))
+            .json(self)
     }
 }
 
@@ -71,20 +300,107 @@ impl TryFrom<SearchRequestRaw> for SearchRequest {
     type Error = TrackerError;
 
     fn try_from(value: SearchRequestRaw) -> Result<Self, Self::Error> {
+        value.page_request.reject_cursor()?;
+
+        let mut names = value.names;
+        if let Some(name) = value.name {
+            names.insert(0, name);
+        }
+
+        for name in &names {
+            validate_search_term("name", name, MAX_NAME_SEARCH_LENGTH)?;
+        }
+
+        if let Some(notes) = &value.notes {
+            validate_search_term("notes", notes, MAX_NOTES_SEARCH_LENGTH)?;
+        }
+
         Ok(Self {
             page_request: PageRequest::try_from(value.page_request)?,
-            name: value.name,
+            names,
+            match_mode: MatchMode::try_from(value.match_mode)?,
+            notes: value.notes,
+            updated_after: parse_datetime("updated_after", value.updated_after)?,
+            without_star: false,
+            includes: Includes::try_from(value.include_request)?,
+            fields: SparseFields::try_from(value.fields_request)?,
+        })
+    }
+}
+
+impl TryFrom<TextSearchRequestRaw> for TextSearchRequest {
+    type Error = TrackerError;
+
+    fn try_from(value: TextSearchRequestRaw) -> Result<Self, Self::Error> {
+        value.page_request.reject_cursor()?;
+
+        let query = value
+            .q
+            .map(|q| q.trim().to_owned())
+            .filter(|q| !q.is_empty())
+            .ok_or_else(|| {
+                TrackerError::missing_field(
+                    "q",
+                    AllowedValues::string_len_between(1, MAX_NAME_SEARCH_LENGTH),
+                )
+            })?;
+
+        if query.chars().count() > MAX_NAME_SEARCH_LENGTH {
+            return Err(TrackerError::invalid_field(
+                FieldValue::new("q", query),
+                AllowedValues::string_len_between(1, MAX_NAME_SEARCH_LENGTH),
+            ));
+        }
+
+        Ok(Self {
+            page_request: PageRequest::try_from(value.page_request)?,
+            query,
         })
     }
 }
 
 field_names!(
     SolarSystemFields<domain::SolarSystemColumns> {
-        Id => { value: "id", column: Id },
+        Id => { value: "id", column: Id, kind: Uuid },
         Save(SaveFields) => { prefix: "save" },
         #[default]
-        CreatedAt => { value: "created_at", column: CreatedAt },
+        CreatedAt => { value: "created_at", column: CreatedAt, kind: DateTime },
+        UpdatedAt => { value: "updated_at", column: UpdatedAt, kind: DateTime },
         Name => { value: "name", column: Name },
         Notes => { value: "notes", column: Notes }
     }
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::web;
+
+    fn search_request(query: &str) -> Result<SearchRequest, TrackerError> {
+        let raw = web::Query::<SearchRequestRaw>::from_query(query)
+            .expect("query should deserialize")
+            .into_inner();
+        SearchRequest::try_from(raw)
+    }
+
+    #[test]
+    fn oversized_name_search_term_is_rejected() {
+        let query = format!("name={0}", "a".repeat(MAX_NAME_SEARCH_LENGTH + 1));
+        match search_request(&query) {
+            Err(TrackerError::InvalidFieldValue(field, _)) => assert_eq!(field.name, "name"),
+            other => panic!("expected an invalid name, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn notes_search_term_has_its_own_limit() {
+        let query = format!("notes={0}", "a".repeat(MAX_NAME_SEARCH_LENGTH + 1));
+        assert!(search_request(&query).is_ok());
+
+        let query = format!("notes={0}", "a".repeat(MAX_NOTES_SEARCH_LENGTH + 1));
+        match search_request(&query) {
+            Err(TrackerError::InvalidFieldValue(field, _)) => assert_eq!(field.name, "notes"),
+            other => panic!("expected invalid notes, got {other:?}"),
+        }
+    }
+}
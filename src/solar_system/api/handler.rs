@@ -1,96 +1,844 @@
-use super::{CreateSolarSystemRequest, SolarSystem, UpdateSolarSystemRequest};
-use crate::solar_system::api::{SearchRequest, SearchRequestRaw};
-use crate::solar_system::domain;
-use crate::{data::Page, error::Result, AppState};
-use actix_web::{delete, get, patch, post, web, HttpResponse};
+use super::{
+    BatchCreateSolarSystemsRequest, BatchCreateSolarSystemsResponse, BulkDeleteSolarSystemsRequest,
+    BulkDeleteSolarSystemsRequestRaw, BulkDeleteSolarSystemsResponse, CreateSolarSystemRequest,
+    MoveSolarSystemRequest, NameAvailableRequest, NameAvailableResponse, SolarSystem,
+    SolarSystemSearchResult, UpdateSolarSystemRequest,
+};
+use crate::solar_system::api::{
+    SearchRequest, SearchRequestRaw, SolarSystemInclude, TextSearchRequest, TextSearchRequestRaw,
+};
+use crate::star::{self, api::Star};
+use crate::{
+    auth::Owner,
+    data::{
+        begin_update, query_values, with_current_version, FieldsRequestRaw, IncludeRequestRaw,
+        Includes, Page, PageRequestRaw,
+    },
+    error::{log_entity_error, log_error, log_lookup_error, ObjectKind, Result},
+    idempotency::Idempotency,
+    utils::{check_if_match, last_modified, normalize_name, not_modified, ApiResponse},
+    AppState,
+};
+use crate::{game_save, solar_system::domain};
+use actix_web::{delete, get, patch, post, web, HttpRequest, HttpResponse, Responder};
 use log::error;
+use std::collections::HashMap;
 use uuid::Uuid;
 
+#[utoipa::path(
+    operation_id = "createSolarSystem",
+    tag = "solar-systems",
+    params(
+        ("saveId" = Uuid, Path, description = "The save id"),
+        ("Idempotency-Key" = Option<String>, Header, description = "A client-chosen key; retrying with the same key returns the originally created resource"),
+    ),
+    request_body = CreateSolarSystemRequest,
+    responses(
+        (status = 201, description = "The created solar system", body = SolarSystem),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 404, description = "The save does not exist", body = ErrorResponse),
+        (status = 409, description = "A conflicting solar system already exists", body = ErrorResponse),
+    )
+)]
 #[post("/saves/{saveId}/solar-systems")]
 async fn create_handler(
+    owner: Owner,
+    req: HttpRequest,
     path: web::Path<Uuid>,
     request: web::Json<CreateSolarSystemRequest>,
     data: web::Data<AppState>,
-) -> Result<SolarSystem> {
+) -> Result<ApiResponse<SolarSystem>> {
+    request.validate()?;
+
+    let idempotency =
+        Idempotency::from_request(&req, ObjectKind::SolarSystem, data.idempotency_key_ttl)?;
     let mut transaction = data.db.begin().await?;
-    let save_id = path.into_inner();
+    game_save::check_owner(&mut transaction, owner, *path).await?;
+    let response = match idempotency.lookup(&mut transaction).await? {
+        Some(id) => domain::lookup(&mut transaction, id).await?,
+        None => {
+            let save_id = path.into_inner();
+
+            let solar_system =
+                domain::SolarSystem::new(save_id, request.name.clone(), request.notes.clone());
+
+            let response = domain::create(&mut transaction, &solar_system)
+                .await
+                .inspect_err(log_entity_error(
+                    "Failed to create solar system",
+                    ObjectKind::SolarSystem,
+                    solar_system.id,
+                ))?;
+            idempotency.record(&mut transaction, response.id).await?;
+            response
+        }
+    };
+    transaction.commit().await?;
+
+    Ok(ApiResponse::created_versioned(
+        format!("/solar-systems/{0}", response.id),
+        response.version,
+        SolarSystem::from(response),
+    ))
+}
 
-    let solar_system =
-        domain::SolarSystem::new(save_id, request.name.clone(), request.notes.clone());
+#[utoipa::path(
+    operation_id = "batchCreateSolarSystems",
+    tag = "solar-systems",
+    params(
+        ("saveId" = Uuid, Path, description = "The save id"),
+    ),
+    request_body = BatchCreateSolarSystemsRequest,
+    responses(
+        (status = 200, description = "The outcome of each solar system, in request order", body = BatchCreateSolarSystemsResponse),
+        (status = 400, description = "A solar system was invalid; `index` identifies it", body = ErrorResponse),
+        (status = 404, description = "The save does not exist", body = ErrorResponse),
+        (status = 409, description = "A solar system conflicted; `index` identifies it", body = ErrorResponse),
+    )
+)]
+#[post("/saves/{saveId}/solar-systems/batch")]
+async fn batch_create_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    request: web::Json<BatchCreateSolarSystemsRequest>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<BatchCreateSolarSystemsResponse>> {
+    request.validate()?;
 
-    let response = domain::create(&mut transaction, &solar_system)
+    let save_id = path.into_inner();
+    let solar_systems: Vec<domain::SolarSystem> = request
+        .solar_systems
+        .iter()
+        .map(|s| domain::SolarSystem::new(save_id, s.name.clone(), s.notes.clone()))
+        .collect();
+
+    let mut transaction = data.db.begin().await?;
+    game_save::check_owner(&mut transaction, owner, save_id).await?;
+    let outcomes = domain::create_batch(&mut transaction, save_id, &solar_systems)
         .await
-        .inspect_err(|err| error!("Failed to create solar system {}: {}", request.name, err))?;
+        .inspect_err(log_error(
+            "Failed to batch create solar systems",
+            ObjectKind::SolarSystem,
+        ))?;
     transaction.commit().await?;
 
-    Ok(response.into())
+    Ok(ApiResponse::Ok(BatchCreateSolarSystemsResponse::from(
+        outcomes,
+    )))
 }
 
+#[utoipa::path(
+    operation_id = "lookupSolarSystem",
+    tag = "solar-systems",
+    params(
+        ("id" = Uuid, Path, description = "The solar system id"),
+        IncludeRequestRaw,
+        ("If-None-Match" = Option<String>, Header, description = "An ETag from a previous response"),
+        ("If-Modified-Since" = Option<String>, Header, description = "The Last-Modified date from a previous response; ignored when If-None-Match is present"),
+    ),
+    responses(
+        (status = 200, description = "The solar system", body = SolarSystem),
+        (status = 304, description = "The supplied If-None-Match ETag matches the current version, or the resource is unmodified since If-Modified-Since"),
+        (status = 400, description = "The id is not a valid UUID or an include is unknown", body = ErrorResponse),
+        (status = 404, description = "The solar system does not exist", body = ErrorResponse),
+    )
+)]
 #[get("/solar-systems/{id}")]
-async fn lookup_handler(path: web::Path<Uuid>, data: web::Data<AppState>) -> Result<SolarSystem> {
+async fn lookup_handler(
+    owner: Owner,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    query: web::Query<IncludeRequestRaw>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let includes = Includes::try_from(query.into_inner())?;
     let mut transaction = data.db.begin().await?;
 
     let id = path.into_inner();
-    let response = domain::lookup(&mut transaction, id)
+    domain::check_owner(&mut transaction, owner, id).await?;
+    let response = domain::lookup_with_includes(&mut transaction, id, &includes)
         .await
-        .inspect_err(|err| error!("Failed to lookup solar system with id `{}`: {}", id, err))
-        .map(SolarSystem::from)?;
+        .inspect_err(log_lookup_error(ObjectKind::SolarSystem, id))
+        .map(|m| {
+            let mut solar_system = SolarSystem::from(m.solar_system);
+            if includes.contains(SolarSystemInclude::Star) {
+                solar_system.star = Some(m.star.map(Star::from));
+            }
+            solar_system
+        })?;
 
     transaction.commit().await?;
-    Ok(response)
+    Ok(not_modified(
+        &req,
+        response.version,
+        last_modified(response.created_at, response.updated_at),
+    )
+    .unwrap_or_else(|| response.respond_to(&req)))
 }
 
+#[utoipa::path(
+    operation_id = "deleteSolarSystem",
+    tag = "solar-systems",
+    params(
+        ("id" = Uuid, Path, description = "The solar system id"),
+    ),
+    responses(
+        (status = 204, description = "The solar system was deleted"),
+        (status = 409, description = "The solar system still has stars or planets", body = ErrorResponse),
+    )
+)]
 #[delete("/solar-systems/{id}")]
-async fn delete_handler(path: web::Path<Uuid>, data: web::Data<AppState>) -> Result<HttpResponse> {
+async fn delete_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<()>> {
     let mut transaction = data.db.begin().await?;
     let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
 
     domain::delete(&mut transaction, id).await?;
     transaction.commit().await?;
 
-    Ok(HttpResponse::NoContent().finish())
+    Ok(ApiResponse::NoContent)
+}
+
+#[utoipa::path(
+    operation_id = "bulkDeleteSolarSystems",
+    tag = "solar-systems",
+    params(
+        ("saveId" = Uuid, Path, description = "The save id"),
+        BulkDeleteSolarSystemsRequestRaw,
+    ),
+    responses(
+        (status = 200, description = "The ids that were deleted and those not found in the save", body = BulkDeleteSolarSystemsResponse),
+        (status = 400, description = "An id was missing or not a valid UUID", body = ErrorResponse),
+        (status = 404, description = "The save does not exist", body = ErrorResponse),
+        (status = 409, description = "A solar system still has stars or planets", body = ErrorResponse),
+    )
+)]
+#[delete("/saves/{saveId}/solar-systems")]
+async fn bulk_delete_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    query: web::Query<BulkDeleteSolarSystemsRequestRaw>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<BulkDeleteSolarSystemsResponse>> {
+    let save_id = path.into_inner();
+    let request = BulkDeleteSolarSystemsRequest::try_from(query.into_inner())?;
+
+    let mut transaction = data.db.begin().await?;
+    game_save::check_owner(&mut transaction, owner, save_id).await?;
+    let deleted = domain::delete_many(&mut transaction, save_id, &request.ids)
+        .await
+        .inspect_err(log_entity_error(
+            "Failed to delete solar systems of save",
+            ObjectKind::Save,
+            save_id,
+        ))?;
+    transaction.commit().await?;
+
+    Ok(ApiResponse::Ok(BulkDeleteSolarSystemsResponse::new(
+        &request.ids,
+        &deleted,
+    )))
 }
 
+#[utoipa::path(
+    operation_id = "searchSolarSystems",
+    tag = "solar-systems",
+    params(
+        ("saveId" = Uuid, Path, description = "The save id"),
+        PageRequestRaw,
+        ("match_mode" = Option<String>, Query, description = "One of `start`, `prefix`, `contains` or `exact`"),
+        ("name" = Option<String>, Query, description = "Name to match"),
+        ("names" = Option<Vec<String>>, Query, description = "Additional names to match"),
+        ("notes" = Option<String>, Query, description = "Case-insensitive substring of the notes"),
+        ("updated_after" = Option<DateTime<Utc>>, Query, description = "Only include solar systems created or updated after this time, ordered by that time"),
+        ("filter" = Option<Vec<String>>, Query, description = "Filters in the form `field:operator:value`"),
+        IncludeRequestRaw,
+        FieldsRequestRaw,
+    ),
+    responses(
+        (status = 200, description = "A page of solar systems", body = SolarSystemPage),
+        (status = 400, description = "The search parameters were invalid", body = ErrorResponse),
+    )
+)]
 #[get("/saves/{saveId}/solar-systems")]
 async fn search_handler(
+    owner: Owner,
+    req: HttpRequest,
     path: web::Path<Uuid>,
     query: web::Query<SearchRequestRaw>,
     data: web::Data<AppState>,
-) -> Result<Page<SolarSystem>> {
+) -> Result<Page<serde_json::Value>> {
     let mut transaction = data.db.begin().await?;
     let save_id = path.into_inner();
-    let search_params = SearchRequest::try_from(query.into_inner())?;
+    game_save::check_owner(&mut transaction, owner, save_id).await?;
+    let mut search_raw = query.into_inner();
+    search_raw.names = query_values(req.query_string(), "names")?;
+    search_raw.page_request.filters = query_values(req.query_string(), "filter")?;
+    search_raw.page_request.paging = data.paging;
+    let search_params = SearchRequest::try_from(search_raw)?;
 
-    let response = domain::search(&mut transaction, save_id, &search_params)
+    let mut response = domain::search(&mut transaction, save_id, &search_params)
         .await
         .map(|r| r.map(|s| SolarSystem::from(s)))
-        .inspect_err(|err| error!("Failed to search for solar systems: {}", err))?;
+        .inspect_err(log_error(
+            "Failed to search for solar systems",
+            ObjectKind::SolarSystem,
+        ))?;
+
+    if search_params.includes.contains(SolarSystemInclude::Star) {
+        let solar_system_ids: Vec<Uuid> = response.data.iter().map(|s| s.id).collect();
+        let mut stars: HashMap<Uuid, Star> =
+            star::domain::lookup_by_solar_systems(&mut transaction, &solar_system_ids)
+                .await?
+                .into_iter()
+                .map(|s| (s.solar_system_id, Star::from(s)))
+                .collect();
+        for solar_system in &mut response.data {
+            solar_system.star = Some(stars.remove(&solar_system.id));
+        }
+    }
+
+    transaction.commit().await?;
+    response.try_map(|s| {
+        search_params
+            .fields
+            .project(s, search_params.includes.names())
+    })
+}
+
+#[utoipa::path(
+    operation_id = "solarSystemsWithoutStar",
+    tag = "solar-systems",
+    params(
+        ("saveId" = Uuid, Path, description = "The save id"),
+        PageRequestRaw,
+        ("match_mode" = Option<String>, Query, description = "One of `start`, `prefix`, `contains` or `exact`"),
+        ("name" = Option<String>, Query, description = "Name to match"),
+        ("names" = Option<Vec<String>>, Query, description = "Additional names to match"),
+        ("notes" = Option<String>, Query, description = "Case-insensitive substring of the notes"),
+        ("updated_after" = Option<DateTime<Utc>>, Query, description = "Only include solar systems created or updated after this time, ordered by that time"),
+        ("filter" = Option<Vec<String>>, Query, description = "Filters in the form `field:operator:value`"),
+        IncludeRequestRaw,
+        FieldsRequestRaw,
+    ),
+    responses(
+        (status = 200, description = "A page of solar systems that have no star", body = SolarSystemPage),
+        (status = 400, description = "The search parameters were invalid", body = ErrorResponse),
+    )
+)]
+#[get("/saves/{saveId}/solar-systems/without-star")]
+async fn without_star_handler(
+    owner: Owner,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    query: web::Query<SearchRequestRaw>,
+    data: web::Data<AppState>,
+) -> Result<Page<serde_json::Value>> {
+    let mut transaction = data.db.begin().await?;
+    let save_id = path.into_inner();
+    game_save::check_owner(&mut transaction, owner, save_id).await?;
+    let mut search_raw = query.into_inner();
+    search_raw.names = query_values(req.query_string(), "names")?;
+    search_raw.page_request.filters = query_values(req.query_string(), "filter")?;
+    search_raw.page_request.paging = data.paging;
+    let mut search_params = SearchRequest::try_from(search_raw)?;
+    search_params.without_star = true;
+
+    let response = domain::search(&mut transaction, save_id, &search_params)
+        .await
+        .map(Page::convert::<SolarSystem>)
+        .inspect_err(log_error(
+            "Failed to search for solar systems without a star",
+            ObjectKind::SolarSystem,
+        ))?;
+    transaction.commit().await?;
+    response.try_map(|s| {
+        search_params
+            .fields
+            .project(s, search_params.includes.names())
+    })
+}
+
+#[utoipa::path(
+    operation_id = "solarSystemNameAvailable",
+    tag = "solar-systems",
+    params(
+        ("saveId" = Uuid, Path, description = "The save id"),
+        ("name" = String, Query, description = "The name to check. Whitespace is trimmed and collapsed as on create, and the comparison is case sensitive like the unique constraint"),
+    ),
+    responses(
+        (status = 200, description = "Whether the name is available in the save", body = NameAvailableResponse),
+        (status = 400, description = "The name is missing", body = ErrorResponse),
+        (status = 404, description = "The save does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/saves/{saveId}/solar-systems/name-available")]
+async fn name_available_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    query: web::Query<NameAvailableRequest>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut transaction = data.db.begin().await?;
+    let save_id = path.into_inner();
+    game_save::check_owner(&mut transaction, owner, save_id).await?;
+
+    let exists = domain::name_exists(&mut transaction, save_id, &query.name)
+        .await
+        .inspect_err(log_entity_error(
+            "Failed to check solar system name availability",
+            ObjectKind::Save,
+            save_id,
+        ))?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(NameAvailableResponse { available: !exists }))
+}
+
+#[utoipa::path(
+    operation_id = "searchSave",
+    tag = "solar-systems",
+    params(
+        ("saveId" = Uuid, Path, description = "The save id"),
+        ("q" = String, Query, description = "The search text"),
+        PageRequestRaw,
+        ("filter" = Option<Vec<String>>, Query, description = "Filters in the form `field:operator:value`"),
+    ),
+    responses(
+        (status = 200, description = "A page of matching solar systems ranked by relevance", body = SolarSystemSearchResultPage),
+        (status = 400, description = "The search parameters were invalid", body = ErrorResponse),
+    )
+)]
+#[get("/saves/{saveId}/search")]
+async fn text_search_handler(
+    owner: Owner,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    query: web::Query<TextSearchRequestRaw>,
+    data: web::Data<AppState>,
+) -> Result<Page<SolarSystemSearchResult>> {
+    let mut transaction = data.db.begin().await?;
+    let save_id = path.into_inner();
+    game_save::check_owner(&mut transaction, owner, save_id).await?;
+    let mut search_raw = query.into_inner();
+    search_raw.page_request.filters = query_values(req.query_string(), "filter")?;
+    search_raw.page_request.paging = data.paging;
+    let search_params = TextSearchRequest::try_from(search_raw)?;
+
+    let response = domain::text_search(&mut transaction, save_id, &search_params)
+        .await
+        .map(Page::convert)
+        .inspect_err(log_entity_error(
+            "Failed to search save",
+            ObjectKind::Save,
+            save_id,
+        ))?;
     transaction.commit().await?;
     Ok(response)
 }
 
+#[utoipa::path(
+    operation_id = "updateSolarSystem",
+    tag = "solar-systems",
+    params(
+        ("id" = Uuid, Path, description = "The solar system id"),
+        ("If-Match" = String, Header, description = "The ETag of the current version"),
+    ),
+    request_body = UpdateSolarSystemRequest,
+    responses(
+        (status = 200, description = "The updated solar system", body = SolarSystem),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 404, description = "The solar system does not exist", body = ErrorResponse),
+        (status = 409, description = "The solar system was concurrently modified or conflicts with an existing solar system", body = ErrorResponse),
+        (status = 412, description = "The solar system has been modified since it was fetched", body = ErrorResponse),
+        (status = 428, description = "The If-Match header is missing", body = ErrorResponse),
+    )
+)]
 #[patch("/solar-systems/{id}")]
 async fn update_handler(
+    owner: Owner,
+    req: HttpRequest,
     path: web::Path<Uuid>,
     request: web::Json<UpdateSolarSystemRequest>,
     data: web::Data<AppState>,
 ) -> Result<SolarSystem> {
-    let mut transaction = data.db.begin().await?;
+    request.validate()?;
+
+    let mut transaction = begin_update(&data.db, data.pessimistic_locking).await?;
     let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+
+    let mut solar_system = with_current_version(
+        domain::lookup_for_update(&mut transaction, id).await,
+        &data.db,
+        (
+            domain::SolarSystemColumns::Table,
+            domain::SolarSystemColumns::Id,
+            domain::SolarSystemColumns::Version,
+        ),
+        id,
+        Some(request.version),
+    )
+    .await?;
+    check_if_match(
+        &req,
+        ObjectKind::SolarSystem,
+        id,
+        solar_system.version,
+        request.version,
+    )?;
+    solar_system.version = request.version;
 
-    let mut solar_system = domain::lookup(&mut transaction, id).await?;
     if let Some(name) = &request.name {
-        solar_system.name = name.clone();
+        solar_system.name = normalize_name(name);
     }
 
     if let Some(notes) = &request.notes {
         solar_system.notes = notes.clone();
     }
 
-    let response = domain::update(&mut transaction, &solar_system)
-        .await
-        .inspect_err(|err| error!("Failed to update save with id `{}`: {}", id, err))?;
+    let response = with_current_version(
+        domain::update(&mut transaction, &solar_system).await,
+        &data.db,
+        (
+            domain::SolarSystemColumns::Table,
+            domain::SolarSystemColumns::Id,
+            domain::SolarSystemColumns::Version,
+        ),
+        id,
+        Some(solar_system.version),
+    )
+    .await
+    .inspect_err(log_entity_error(
+        "Failed to update solar system",
+        ObjectKind::SolarSystem,
+        id,
+    ))?;
 
     transaction.commit().await?;
     Ok(response.into())
 }
+
+#[utoipa::path(
+    operation_id = "moveSolarSystem",
+    tag = "solar-systems",
+    params(
+        ("id" = Uuid, Path, description = "The solar system id"),
+        ("If-Match" = String, Header, description = "The ETag of the current version"),
+    ),
+    request_body = MoveSolarSystemRequest,
+    responses(
+        (status = 200, description = "The moved solar system", body = SolarSystem),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 404, description = "The solar system or the target save does not exist", body = ErrorResponse),
+        (status = 409, description = "The solar system was concurrently modified or the target save already has a solar system with the same name", body = ErrorResponse),
+        (status = 412, description = "The solar system has been modified since it was fetched", body = ErrorResponse),
+        (status = 428, description = "The If-Match header is missing", body = ErrorResponse),
+    )
+)]
+#[patch("/solar-systems/{id}/move")]
+async fn move_handler(
+    owner: Owner,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    request: web::Json<MoveSolarSystemRequest>,
+    data: web::Data<AppState>,
+) -> Result<SolarSystem> {
+    let mut transaction = begin_update(&data.db, data.pessimistic_locking).await?;
+    let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+    game_save::check_owner(&mut transaction, owner, request.save_id).await?;
+
+    let mut solar_system = with_current_version(
+        domain::lookup_for_update(&mut transaction, id).await,
+        &data.db,
+        (
+            domain::SolarSystemColumns::Table,
+            domain::SolarSystemColumns::Id,
+            domain::SolarSystemColumns::Version,
+        ),
+        id,
+        Some(request.version),
+    )
+    .await?;
+    check_if_match(
+        &req,
+        ObjectKind::SolarSystem,
+        id,
+        solar_system.version,
+        request.version,
+    )?;
+    solar_system.version = request.version;
+    solar_system.save_id = request.save_id;
+
+    let response = with_current_version(
+        domain::update(&mut transaction, &solar_system).await,
+        &data.db,
+        (
+            domain::SolarSystemColumns::Table,
+            domain::SolarSystemColumns::Id,
+            domain::SolarSystemColumns::Version,
+        ),
+        id,
+        Some(solar_system.version),
+    )
+    .await
+    .inspect_err(|err| {
+        error!(
+            entity:% = ObjectKind::SolarSystem, id:% = id, save_id:% = request.save_id,
+            error_code:% = err.error_code();
+            "Failed to move solar system: {}", err
+        )
+    })?;
+
+    transaction.commit().await?;
+    Ok(response.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{
+        app_state, assert_one_wins, create_save, create_solar_system, db_pool, send, test_app,
+    };
+    use actix_http::Request;
+    use actix_web::{
+        body::MessageBody,
+        dev::{Service, ServiceResponse},
+        http::StatusCode,
+        test,
+    };
+    use futures_util::future::join;
+    use serde_json::json;
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn concurrent_moves_of_the_same_version_conflict() {
+        let db = db_pool().await;
+
+        for pessimistic_locking in [false, true] {
+            let mut state = app_state(db.clone());
+            state.pessimistic_locking = pessimistic_locking;
+            let app = test::init_service(test_app(state)).await;
+
+            let source = create_save(&app).await;
+            let solar_system =
+                create_solar_system(&app, source["id"].as_str().unwrap(), "Sol").await;
+            let uri = format!(
+                "/api/1/solar-systems/{0}/move",
+                solar_system["id"].as_str().unwrap()
+            );
+            let first_target = create_save(&app).await;
+            let second_target = create_save(&app).await;
+
+            let move_to = |target: &serde_json::Value| {
+                test::TestRequest::patch()
+                    .uri(&uri)
+                    .insert_header(("If-Match", "\"0\""))
+                    .set_json(json!({ "version": 0, "save_id": target["id"] }))
+                    .to_request()
+            };
+            let ((first, _), (second, _)) = join(
+                send(&app, move_to(&first_target)),
+                send(&app, move_to(&second_target)),
+            )
+            .await;
+
+            assert_one_wins(first, second, pessimistic_locking);
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn names_differing_in_whitespace_collide() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let save_id = save["id"].as_str().unwrap();
+        create_solar_system(&app, save_id, "Solar Prime").await;
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!(
+                    "/api/1/saves/{save_id}/solar-systems/name-available?name=%20Solar%20%20Prime%20"
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "available": false }));
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/api/1/saves/{save_id}/solar-systems"))
+                .set_json(json!({ "name": "Solar Prime  " }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error_code"], "Duplicate");
+
+        let other = create_solar_system(&app, save_id, "Sol").await;
+        let (status, body) = send(
+            &app,
+            test::TestRequest::patch()
+                .uri(&format!(
+                    "/api/1/solar-systems/{0}",
+                    other["id"].as_str().unwrap()
+                ))
+                .insert_header(("If-Match", "\"0\""))
+                .set_json(json!({ "version": 0, "name": " Solar Prime" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error_code"], "Duplicate");
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn estimated_counts_find_the_next_page_from_the_rows() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let save_id = save["id"].as_str().unwrap();
+        for name in ["Alpha", "Beta", "Gamma"] {
+            create_solar_system(&app, save_id, name).await;
+        }
+
+        let page = |page: u64| {
+            test::TestRequest::get()
+                .uri(&format!(
+                    "/api/1/saves/{save_id}/solar-systems?count=estimate&size=2&page={page}"
+                ))
+                .to_request()
+        };
+
+        let (status, body) = send(&app, page(1)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"].as_array().unwrap().len(), 2);
+        assert_eq!(body["metadata"]["count_estimated"], true);
+        assert!(body["metadata"].get("total_pages").is_none());
+        assert_eq!(body["metadata"]["next_page"], 2);
+
+        let (status, body) = send(&app, page(2)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+        assert_eq!(body["metadata"]["next_page"], serde_json::Value::Null);
+        assert_eq!(body["metadata"]["prev_page"], 1);
+    }
+
+    /// Searches the save's solar systems and returns the matching names, sorted.
+    async fn search_names<S, B>(app: &S, save_id: &str, name: &str, match_mode: &str) -> Vec<String>
+    where
+        S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody,
+    {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        query
+            .append_pair("name", name)
+            .append_pair("match_mode", match_mode);
+        let (status, body) = send(
+            app,
+            test::TestRequest::get()
+                .uri(&format!(
+                    "/api/1/saves/{save_id}/solar-systems?{0}",
+                    query.finish()
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let mut names: Vec<String> = body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|solar_system| solar_system["name"].as_str().unwrap().to_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn prefix_search_matches_word_starts_only() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let save_id = save["id"].as_str().unwrap();
+        for name in ["Solar Prime", "Absolute", "New Solaris"] {
+            create_solar_system(&app, save_id, name).await;
+        }
+
+        assert_eq!(
+            search_names(&app, save_id, "Sol", "prefix").await,
+            ["New Solaris", "Solar Prime"]
+        );
+        assert_eq!(
+            search_names(&app, save_id, "sol", "start").await,
+            ["Solar Prime"]
+        );
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn contains_search_matches_the_regex_for_simple_terms() {
+        let db = db_pool().await;
+        let app = test::init_service(test_app(app_state(db.clone()))).await;
+        let save = create_save(&app).await;
+        let save_id = save["id"].as_str().unwrap();
+        for name in ["Solar Prime", "Absolute", "Consolidated", "Beta Hydri"] {
+            create_solar_system(&app, save_id, name).await;
+        }
+
+        for term in ["sol", "PRIME", "a h", "zzz"] {
+            let mut regex_names: Vec<String> = sqlx::query_scalar(
+                "SELECT name FROM solar_systems WHERE save_id = $1::uuid AND name ~* $2",
+            )
+            .bind(save_id)
+            .bind(regex::escape(term))
+            .fetch_all(&db)
+            .await
+            .unwrap();
+            regex_names.sort();
+            assert_eq!(
+                search_names(&app, save_id, term, "contains").await,
+                regex_names,
+                "{term}"
+            );
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn offset_paged_searches_reject_a_cursor() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let save_id = save["id"].as_str().unwrap();
+
+        let (status, page) = send(
+            &app,
+            test::TestRequest::get()
+                .uri("/api/1/saves?size=1")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let cursor = page["metadata"]["next_cursor"].as_str().unwrap();
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!(
+                    "/api/1/saves/{save_id}/solar-systems?size=1&cursor={cursor}"
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        assert_eq!(body["error_code"], "InvalidFieldValue");
+        assert_eq!(body["field"]["name"], "cursor");
+    }
+}
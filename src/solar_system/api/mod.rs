@@ -3,11 +3,51 @@ mod handler;
 
 use actix_web::web;
 pub use data::*;
+use utoipa::OpenApi;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(handler::create_handler)
+        .service(handler::batch_create_handler)
         .service(handler::lookup_handler)
         .service(handler::search_handler)
+        .service(handler::without_star_handler)
+        .service(handler::name_available_handler)
+        .service(handler::text_search_handler)
         .service(handler::delete_handler)
-        .service(handler::update_handler);
+        .service(handler::bulk_delete_handler)
+        .service(handler::update_handler)
+        .service(handler::move_handler);
 }
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        handler::create_handler,
+        handler::batch_create_handler,
+        handler::lookup_handler,
+        handler::search_handler,
+        handler::without_star_handler,
+        handler::name_available_handler,
+        handler::text_search_handler,
+        handler::update_handler,
+        handler::move_handler,
+        handler::delete_handler,
+        handler::bulk_delete_handler,
+    ),
+    components(schemas(
+        SolarSystem,
+        CreateSolarSystemRequest,
+        UpdateSolarSystemRequest,
+        MoveSolarSystemRequest,
+        BatchCreateSolarSystemsRequest,
+        BatchCreateSolarSystemsResponse,
+        BatchItemResult,
+        BatchItemStatus,
+        BulkDeleteSolarSystemsResponse,
+        NameAvailableResponse,
+        SolarSystemSearchResult,
+        crate::data::SolarSystemPage,
+        crate::data::SolarSystemSearchResultPage,
+    ))
+)]
+pub struct ApiDoc;
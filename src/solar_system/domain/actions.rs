@@ -1,24 +1,106 @@
-use super::{SolarSystem, SolarSystemColumns};
+use super::{BatchCreateOutcome, SolarSystem, SolarSystemColumns, SolarSystemMatch};
 use crate::{
-    data::{Page, PageMetadata, Sort},
-    error::{ObjectKind, Result, TrackerError},
-    field::{Field, FieldValue},
-    game_save::GameSaveColumns,
-    solar_system::api::{SearchRequest, SolarSystemFields},
+    auth::Owner,
+    data::{add_sorts, count_rows, is_serialization_failure, paginate, Includes, Page, Sort},
+    error::{DependentCount, ObjectKind, Result, TrackerError},
+    field::{AllowedValues, FieldValue},
+    game_save::{self, GameSaveColumns},
+    planet::PlanetColumns,
+    solar_system::api::{SearchRequest, SolarSystemFields, SolarSystemInclude, TextSearchRequest},
+    star::{
+        self,
+        domain::{Star, StarColumns},
+    },
+    utils::{escape_like, normalize_name, MAX_NAME_LENGTH},
 };
 use sea_query::{
-    extension::postgres::PgBinOper, Alias, Asterisk, Expr, Func, Iden, PostgresQueryBuilder, Query,
-    SelectStatement,
+    extension::postgres::PgBinOper, Alias, Asterisk, Cond, Expr, Func, Iden, InsertStatement,
+    LockType, OnConflict, Order, PostgresQueryBuilder, Query, SelectStatement, SimpleExpr,
 };
 use sea_query_binder::SqlxBinder;
-use sqlx::{error::ErrorKind, Postgres, Row, Transaction};
+use sqlx::{error::ErrorKind, FromRow, Postgres, Row, Transaction};
+use std::collections::HashMap;
 use uuid::Uuid;
 
+const STAR_COLUMN_PREFIX: &str = "star_";
+const SEARCH_DOCUMENT: &str =
+    "to_tsvector('simple', solar_systems.name || ' ' || coalesce(solar_systems.notes, ''))";
+
 pub async fn create<'a>(
     tx: &mut Transaction<'a, Postgres>,
     solar_system: &SolarSystem,
 ) -> Result<SolarSystem> {
-    let (sql, values) = Query::insert()
+    let (sql, values) = insert_statement(solar_system).build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| map_constraint_errors(err, solar_system))?;
+
+    lookup(tx, solar_system.id)
+        .await
+        .map_err(TrackerError::not_found_unexpected)
+}
+
+pub async fn create_batch<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+    solar_systems: &[SolarSystem],
+) -> Result<Vec<BatchCreateOutcome>> {
+    game_save::lookup(tx, save_id).await?;
+
+    let mut outcomes = Vec::with_capacity(solar_systems.len());
+    for (index, solar_system) in solar_systems.iter().enumerate() {
+        let outcome = create_unless_duplicate(tx, solar_system)
+            .await
+            .map_err(|err| TrackerError::batch_item(index, err))?;
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
+
+async fn create_unless_duplicate<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    solar_system: &SolarSystem,
+) -> Result<BatchCreateOutcome> {
+    let (sql, values) = insert_statement(solar_system)
+        .on_conflict(
+            OnConflict::columns([SolarSystemColumns::SaveId, SolarSystemColumns::Name])
+                .target_and_where(Expr::col(SolarSystemColumns::AllowDuplicateNames).not())
+                .do_nothing()
+                .to_owned(),
+        )
+        .build_sqlx(PostgresQueryBuilder);
+
+    let inserted = sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| map_constraint_errors(err, solar_system))?
+        .rows_affected();
+
+    if inserted == 0 {
+        let (sql, values) = Query::select()
+            .column(SolarSystemColumns::Id)
+            .from(SolarSystemColumns::Table)
+            .and_where(Expr::col(SolarSystemColumns::SaveId).eq(solar_system.save_id))
+            .and_where(Expr::col(SolarSystemColumns::Name).eq(&solar_system.name))
+            .build_sqlx(PostgresQueryBuilder);
+
+        let existing_id: Uuid = sqlx::query_with(&sql, values.clone())
+            .fetch_one(&mut **tx)
+            .await?
+            .get(0);
+        return Ok(BatchCreateOutcome::Duplicate(existing_id));
+    }
+
+    lookup(tx, solar_system.id)
+        .await
+        .map(BatchCreateOutcome::Created)
+        .map_err(TrackerError::not_found_unexpected)
+}
+
+fn insert_statement(solar_system: &SolarSystem) -> InsertStatement {
+    Query::insert()
         .into_table(SolarSystemColumns::Table)
         .columns([
             SolarSystemColumns::Id,
@@ -27,25 +109,67 @@ pub async fn create<'a>(
             SolarSystemColumns::SaveId,
             SolarSystemColumns::Name,
             SolarSystemColumns::Notes,
+            SolarSystemColumns::AllowDuplicateNames,
         ])
         .values_panic([
             solar_system.id.into(),
-            Expr::current_timestamp().into(),
+            Expr::cust("clock_timestamp()"),
             solar_system.version.into(),
             solar_system.save_id.into(),
             (&solar_system.name).into(),
             solar_system.notes.as_deref().into(),
+            save_allows_duplicate_names(solar_system.save_id),
         ])
+        .to_owned()
+}
+
+/// The save's `allow_duplicate_names` flag, copied onto each solar system so the
+/// partial unique index on `(save_id, name)` can honour it. A missing save
+/// yields `false` so the foreign key violation is reported instead.
+fn save_allows_duplicate_names(save_id: Uuid) -> SimpleExpr {
+    let flag = Query::select()
+        .column(GameSaveColumns::AllowDuplicateNames)
+        .from(GameSaveColumns::Table)
+        .and_where(Expr::col(GameSaveColumns::Id).eq(save_id))
+        .to_owned();
+
+    Func::coalesce([
+        SimpleExpr::SubQuery(None, Box::new(flag.into_sub_query_statement())),
+        false.into(),
+    ])
+    .into()
+}
+
+pub async fn set_allow_duplicate_names<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+    allow_duplicate_names: bool,
+) -> Result<()> {
+    let (sql, values) = Query::update()
+        .table(SolarSystemColumns::Table)
+        .value(
+            SolarSystemColumns::AllowDuplicateNames,
+            allow_duplicate_names,
+        )
+        .and_where(Expr::col(SolarSystemColumns::SaveId).eq(save_id))
+        .and_where(Expr::col(SolarSystemColumns::AllowDuplicateNames).ne(allow_duplicate_names))
         .build_sqlx(PostgresQueryBuilder);
 
     sqlx::query_with(&sql, values.clone())
         .execute(&mut **tx)
         .await
-        .map_err(|err| map_constraint_errors(err, solar_system))?;
-
-    lookup(tx, solar_system.id)
-        .await
-        .map_err(TrackerError::not_found_unexpected)
+        .map_err(|err| match &err {
+            sqlx::Error::Database(db_err)
+                if db_err.constraint() == Some("solar_systems_save_id_name_key") =>
+            {
+                TrackerError::duplicate(
+                    ObjectKind::SolarSystem,
+                    FieldValue::new(SolarSystemColumns::SaveId, save_id),
+                )
+            }
+            _ => TrackerError::from(err),
+        })?;
+    Ok(())
 }
 
 pub async fn update<'a>(
@@ -63,6 +187,11 @@ pub async fn update<'a>(
                 SolarSystemColumns::Version,
                 Expr::col(SolarSystemColumns::Version).add(1),
             ),
+            (SolarSystemColumns::SaveId, solar_system.save_id.into()),
+            (
+                SolarSystemColumns::AllowDuplicateNames,
+                save_allows_duplicate_names(solar_system.save_id),
+            ),
             (SolarSystemColumns::Name, solar_system.name.clone().into()),
             (SolarSystemColumns::Notes, solar_system.notes.clone().into()),
         ])
@@ -104,6 +233,31 @@ pub async fn lookup_optional<'a>(
     )
 }
 
+pub async fn check_owner<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    owner: Owner,
+    id: Uuid,
+) -> Result<()> {
+    let select_stmt = Query::select()
+        .expr(Expr::val(1))
+        .from(SolarSystemColumns::Table)
+        .inner_join(
+            GameSaveColumns::Table,
+            Expr::col((GameSaveColumns::Table, GameSaveColumns::Id))
+                .equals((SolarSystemColumns::Table, SolarSystemColumns::SaveId)),
+        )
+        .and_where(Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Id)).eq(id))
+        .to_owned();
+
+    game_save::ensure_owned(tx, owner, select_stmt, || {
+        TrackerError::not_found(
+            ObjectKind::SolarSystem,
+            FieldValue::new(SolarSystemColumns::Id, id),
+        )
+    })
+    .await
+}
+
 pub async fn lookup<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<SolarSystem> {
     lookup_optional(tx, id)
         .await
@@ -116,6 +270,102 @@ pub async fn lookup<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<
         })
 }
 
+pub async fn lookup_for_update<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    id: Uuid,
+) -> Result<SolarSystem> {
+    let (sql, values) = Query::select()
+        .column(Asterisk)
+        .from(SolarSystemColumns::Table)
+        .and_where(Expr::col(SolarSystemColumns::Id).eq(id))
+        .lock(LockType::Update)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_as_with::<_, SolarSystem, _>(&sql, values.clone())
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|err| {
+            if is_serialization_failure(&err) {
+                TrackerError::concurrent_update(
+                    ObjectKind::SolarSystem,
+                    FieldValue::new(SolarSystemColumns::Id, id),
+                )
+            } else {
+                TrackerError::from(err)
+            }
+        })?
+        .ok_or_else(|| {
+            TrackerError::not_found(
+                ObjectKind::SolarSystem,
+                FieldValue::new(SolarSystemColumns::Id, id),
+            )
+        })
+}
+
+pub async fn name_exists<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+    name: &str,
+) -> Result<bool> {
+    game_save::lookup(tx, save_id).await?;
+
+    let (sql, values) = Query::select()
+        .expr(Expr::exists(
+            Query::select()
+                .expr(Expr::val(1))
+                .from(SolarSystemColumns::Table)
+                .and_where(Expr::col(SolarSystemColumns::SaveId).eq(save_id))
+                .and_where(Expr::col(SolarSystemColumns::Name).eq(normalize_name(name)))
+                .to_owned(),
+        ))
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_with(&sql, values.clone())
+        .fetch_one(&mut **tx)
+        .await?
+        .get(0))
+}
+
+pub async fn lookup_with_includes<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    id: Uuid,
+    includes: &Includes<SolarSystemInclude>,
+) -> Result<SolarSystemMatch> {
+    let include_star = includes.contains(SolarSystemInclude::Star);
+    let mut select_stmt = Query::select()
+        .column((SolarSystemColumns::Table, Asterisk))
+        .from(SolarSystemColumns::Table)
+        .and_where(Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Id)).eq(id))
+        .limit(1)
+        .to_owned();
+    add_includes(&mut select_stmt, includes);
+    if include_star {
+        add_star_join(&mut select_stmt, &mut Vec::new());
+        star::domain::select_prefixed_columns(&mut select_stmt, STAR_COLUMN_PREFIX);
+    }
+
+    let (sql, values) = select_stmt.build_sqlx(PostgresQueryBuilder);
+
+    let row = sqlx::query_with(&sql, values.clone())
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| {
+            TrackerError::not_found(
+                ObjectKind::SolarSystem,
+                FieldValue::new(SolarSystemColumns::Id, id),
+            )
+        })?;
+
+    Ok(SolarSystemMatch {
+        solar_system: SolarSystem::from_row(&row)?,
+        star: if include_star {
+            star::domain::from_prefixed_row(&row, STAR_COLUMN_PREFIX)?
+        } else {
+            None
+        },
+    })
+}
+
 pub async fn search<'a>(
     tx: &mut Transaction<'a, Postgres>,
     save_id: Uuid,
@@ -128,40 +378,94 @@ pub async fn search<'a>(
         .expr(Func::count(Expr::col(Asterisk)))
         .from(SolarSystemColumns::Table)
         .to_owned();
-    add_where_clause(&mut select_count_stmt, save_id, search_params);
+    add_where_clause(
+        &mut select_count_stmt,
+        save_id,
+        search_params,
+        &mut Vec::new(),
+    );
 
-    let (count_sql, count_values) = select_count_stmt.build_sqlx(PostgresQueryBuilder);
+    let mut select_stmt = Query::select().from(SolarSystemColumns::Table).to_owned();
+    search_params
+        .fields
+        .select_columns(&mut select_stmt, (SolarSystemColumns::Table, Asterisk));
+    add_where_clause(&mut select_stmt, save_id, search_params, &mut joins_tracker);
+    add_includes(&mut select_stmt, &search_params.includes);
+    if search_params.updated_after.is_some() {
+        select_stmt.order_by_expr(last_modified(), Order::Asc);
+    }
+    add_sort_joins(&mut select_stmt, &page_req.sorts, &mut joins_tracker);
+    add_sorts(
+        &mut select_stmt,
+        &page_req.sorts,
+        (SolarSystemColumns::Table, SolarSystemColumns::Id),
+    );
 
-    let total_results: i64 = sqlx::query_with(&count_sql, count_values.clone())
-        .fetch_one(&mut **tx)
-        .await?
-        .get(0);
+    paginate(tx, select_count_stmt, select_stmt, page_req).await
+}
+
+pub async fn text_search<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+    search_params: &TextSearchRequest,
+) -> Result<Page<SolarSystemMatch>> {
+    let page_req = &search_params.page_request;
+    let mut joins_tracker = Vec::new();
+
+    let mut select_count_stmt = Query::select()
+        .expr(Func::count(Expr::col(Asterisk)))
+        .from(SolarSystemColumns::Table)
+        .to_owned();
+    add_text_search_where_clause(
+        &mut select_count_stmt,
+        save_id,
+        search_params,
+        &mut Vec::new(),
+    );
 
     let mut select_stmt = Query::select()
-        .expr(Expr::col(Asterisk))
+        .column((SolarSystemColumns::Table, Asterisk))
         .from(SolarSystemColumns::Table)
-        .limit(page_req.size)
-        .offset(page_req.offset())
         .to_owned();
-    add_where_clause(&mut select_stmt, save_id, search_params);
-    add_sorts(&mut select_stmt, &page_req.sorts, &mut joins_tracker);
+    add_text_search_where_clause(&mut select_stmt, save_id, search_params, &mut joins_tracker);
+    if has_search_tokens(&search_params.query) {
+        select_stmt.order_by_expr(
+            Expr::cust_with_values(
+                format!(
+                    "ts_rank({0}, plainto_tsquery('simple', $1))",
+                    SEARCH_DOCUMENT
+                ),
+                [search_params.query.as_str()],
+            ),
+            Order::Desc,
+        );
+    }
+    add_sort_joins(&mut select_stmt, &page_req.sorts, &mut joins_tracker);
+    add_sorts(
+        &mut select_stmt,
+        &page_req.sorts,
+        (SolarSystemColumns::Table, SolarSystemColumns::Id),
+    );
 
-    let (sql, values) = select_stmt.build_sqlx(PostgresQueryBuilder);
+    let page: Page<SolarSystem> = paginate(tx, select_count_stmt, select_stmt, page_req).await?;
 
-    Ok(
-        sqlx::query_as_with::<_, SolarSystem, _>(&sql, values.clone())
-            .fetch_all(&mut **tx)
-            .await
-            .map(|result| {
-                Page::new(
-                    result,
-                    PageMetadata::new(page_req.page, page_req.size, total_results as u64),
-                )
-            })?,
-    )
+    let solar_system_ids: Vec<Uuid> = page.data.iter().map(|s| s.id).collect();
+    let mut stars: HashMap<Uuid, Star> =
+        star::domain::lookup_by_solar_systems(tx, &solar_system_ids)
+            .await?
+            .into_iter()
+            .map(|s| (s.solar_system_id, s))
+            .collect();
+
+    Ok(page.map(|solar_system| SolarSystemMatch {
+        star: stars.remove(&solar_system.id),
+        solar_system,
+    }))
 }
 
 pub async fn delete<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<()> {
+    let dependents = count_dependents(tx, &[id]).await?;
+
     let (sql, values) = Query::delete()
         .from_table(SolarSystemColumns::Table)
         .and_where(Expr::col(SolarSystemColumns::Id).eq(id))
@@ -169,29 +473,206 @@ pub async fn delete<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<
 
     sqlx::query_with(&sql, values.clone())
         .execute(&mut **tx)
-        .await?;
+        .await
+        .map_err(|err| match &err {
+            sqlx::Error::Database(db_err) if db_err.kind() == ErrorKind::ForeignKeyViolation => {
+                TrackerError::delete_restricted(
+                    ObjectKind::SolarSystem,
+                    FieldValue::new(SolarSystemColumns::Id, id),
+                    dependents,
+                )
+            }
+            _ => TrackerError::from(err),
+        })?;
     Ok(())
 }
 
-fn add_where_clause(select_stmt: &mut SelectStatement, save_id: Uuid, req: &SearchRequest) {
-    select_stmt.and_where(Expr::col(SolarSystemColumns::SaveId).eq(save_id));
+/// Deletes the given solar systems of the save, returning the ids that were
+/// actually deleted. Ids that don't exist or belong to another save are skipped.
+pub async fn delete_many<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+    ids: &[Uuid],
+) -> Result<Vec<Uuid>> {
+    game_save::lookup(tx, save_id).await?;
+    let dependents = count_dependents(tx, ids).await?;
+
+    let (sql, values) = Query::delete()
+        .from_table(SolarSystemColumns::Table)
+        .and_where(Expr::col(SolarSystemColumns::SaveId).eq(save_id))
+        .and_where(Expr::col(SolarSystemColumns::Id).is_in(ids.iter().copied()))
+        .returning_col(SolarSystemColumns::Id)
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_with(&sql, values.clone())
+        .fetch_all(&mut **tx)
+        .await
+        .map_err(|err| match &err {
+            sqlx::Error::Database(db_err) if db_err.kind() == ErrorKind::ForeignKeyViolation => {
+                TrackerError::delete_restricted(
+                    ObjectKind::SolarSystem,
+                    FieldValue::new(SolarSystemColumns::SaveId, save_id),
+                    dependents,
+                )
+            }
+            _ => TrackerError::from(err),
+        })?
+        .iter()
+        .map(|row| row.get(0))
+        .collect())
+}
 
-    if let Some(name) = &req.name {
-        let pattern = format!("(^|\\s+){0}", regex::escape(name));
-        select_stmt.and_where(
-            Expr::col(SolarSystemColumns::Name).binary(PgBinOper::RegexCaseInsensitive, pattern),
+/// Counts the stars and planets that keep the given solar systems from being deleted.
+async fn count_dependents<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    ids: &[Uuid],
+) -> Result<Vec<DependentCount>> {
+    let stars = count_rows(
+        tx,
+        Query::select()
+            .expr(Func::count(Expr::col(Asterisk)))
+            .from(StarColumns::Table)
+            .and_where(Expr::col(StarColumns::SolarSystemId).is_in(ids.iter().copied()))
+            .to_owned(),
+    )
+    .await?;
+
+    let planets = count_rows(
+        tx,
+        Query::select()
+            .expr(Func::count(Expr::col(Asterisk)))
+            .from(PlanetColumns::Table)
+            .and_where(Expr::col(PlanetColumns::SolarSystemId).is_in(ids.iter().copied()))
+            .to_owned(),
+    )
+    .await?;
+
+    Ok(vec![
+        DependentCount {
+            object: ObjectKind::Star,
+            count: stars,
+        },
+        DependentCount {
+            object: ObjectKind::Planet,
+            count: planets,
+        },
+    ])
+}
+
+fn add_where_clause(
+    select_stmt: &mut SelectStatement,
+    save_id: Uuid,
+    req: &SearchRequest,
+    joins_tracker: &mut Vec<String>,
+) {
+    select_stmt
+        .and_where(Expr::col((SolarSystemColumns::Table, SolarSystemColumns::SaveId)).eq(save_id));
+
+    if !req.names.is_empty() {
+        let mut name_cond = Cond::any();
+        for name in &req.names {
+            name_cond = name_cond.add(
+                req.match_mode
+                    .predicate((SolarSystemColumns::Table, SolarSystemColumns::Name), name),
+            );
+        }
+        select_stmt.cond_where(name_cond);
+    }
+
+    if let Some(notes) = &req.notes {
+        let notes_col = Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Notes));
+        select_stmt
+            .and_where(notes_col.clone().is_not_null())
+            .and_where(notes_col.binary(PgBinOper::ILike, format!("%{0}%", escape_like(notes))));
+    }
+
+    if let Some(updated_after) = req.updated_after {
+        select_stmt.and_where(Expr::expr(last_modified()).gt(updated_after));
+    }
+
+    if req.without_star {
+        add_star_join(select_stmt, joins_tracker);
+        select_stmt.and_where(Expr::col((StarColumns::Table, StarColumns::Id)).is_null());
+    }
+
+    for filter in &req.page_request.filters {
+        add_join_for_field(select_stmt, filter.field, joins_tracker);
+        select_stmt.and_where(filter.predicate());
+    }
+}
+
+fn last_modified() -> SimpleExpr {
+    Func::coalesce([
+        Expr::col((SolarSystemColumns::Table, SolarSystemColumns::UpdatedAt)).into(),
+        Expr::col((SolarSystemColumns::Table, SolarSystemColumns::CreatedAt)).into(),
+    ])
+    .into()
+}
+
+fn add_includes(select_stmt: &mut SelectStatement, includes: &Includes<SolarSystemInclude>) {
+    if includes.contains(SolarSystemInclude::PlanetCount) {
+        let planet_count = Query::select()
+            .expr(Func::count(Expr::col(Asterisk)))
+            .from(PlanetColumns::Table)
+            .and_where(
+                Expr::col((PlanetColumns::Table, PlanetColumns::SolarSystemId))
+                    .equals((SolarSystemColumns::Table, SolarSystemColumns::Id)),
+            )
+            .to_owned();
+        select_stmt.expr_as(
+            SimpleExpr::SubQuery(None, Box::new(planet_count.into_sub_query_statement())),
+            Alias::new("planet_count"),
         );
     }
 }
 
-fn add_sorts(
+fn has_search_tokens(query: &str) -> bool {
+    query.chars().any(char::is_alphanumeric)
+}
+
+fn add_text_search_where_clause(
+    select_stmt: &mut SelectStatement,
+    save_id: Uuid,
+    req: &TextSearchRequest,
+    joins_tracker: &mut Vec<String>,
+) {
+    let query = req.query.as_str();
+    select_stmt
+        .and_where(Expr::col((SolarSystemColumns::Table, SolarSystemColumns::SaveId)).eq(save_id));
+
+    if has_search_tokens(query) {
+        select_stmt.and_where(Expr::cust_with_values(
+            format!("{0} @@ plainto_tsquery('simple', $1)", SEARCH_DOCUMENT),
+            [query],
+        ));
+    } else {
+        let pattern = format!("%{0}%", escape_like(query));
+        select_stmt.cond_where(
+            Cond::any()
+                .add(
+                    Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Name))
+                        .binary(PgBinOper::ILike, pattern.clone()),
+                )
+                .add(
+                    Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Notes))
+                        .binary(PgBinOper::ILike, pattern),
+                ),
+        );
+    }
+
+    for filter in &req.page_request.filters {
+        add_join_for_field(select_stmt, filter.field, joins_tracker);
+        select_stmt.and_where(filter.predicate());
+    }
+}
+
+fn add_sort_joins(
     select_stmt: &mut SelectStatement,
     sorts: &[Sort<SolarSystemFields>],
     joins_tracker: &mut Vec<String>,
 ) {
     for sort in sorts {
         add_join_for_field(select_stmt, sort.field, joins_tracker);
-        select_stmt.order_by(sort.field.column(), sort.direction.into());
     }
 }
 
@@ -216,8 +697,24 @@ pub fn add_join_for_field(
     }
 }
 
+fn add_star_join(select_stmt: &mut SelectStatement, joins_tracker: &mut Vec<String>) {
+    let star_table = StarColumns::Table.to_string();
+    if !joins_tracker.contains(&star_table) {
+        joins_tracker.push(star_table);
+        select_stmt.left_join(
+            StarColumns::Table,
+            Expr::col((StarColumns::Table, StarColumns::SolarSystemId))
+                .equals((SolarSystemColumns::Table, SolarSystemColumns::Id)),
+        );
+    }
+}
+
 fn map_constraint_errors(err: sqlx::Error, solar_system: &SolarSystem) -> TrackerError {
     match &err {
+        _ if is_serialization_failure(&err) => TrackerError::concurrent_update(
+            ObjectKind::SolarSystem,
+            FieldValue::new(SolarSystemColumns::Id, solar_system.id),
+        ),
         sqlx::Error::Database(db_err) => match (db_err.kind(), db_err.constraint()) {
             (ErrorKind::UniqueViolation, Some("solar_systems_save_id_name_key")) => {
                 TrackerError::duplicate(
@@ -234,6 +731,12 @@ fn map_constraint_errors(err: sqlx::Error, solar_system: &SolarSystem) -> Tracke
                     FieldValue::new(GameSaveColumns::Id, solar_system.save_id),
                 )
             }
+            (ErrorKind::CheckViolation, Some("solar_systems_name_length")) => {
+                TrackerError::invalid_field(
+                    FieldValue::new(SolarSystemColumns::Name, &solar_system.name),
+                    AllowedValues::string_len_between(1, MAX_NAME_LENGTH),
+                )
+            }
             _ => TrackerError::from(err),
         },
         _ => TrackerError::from(err),
@@ -1,3 +1,4 @@
+use crate::{star::domain::Star, utils::normalize_name};
 use chrono::{DateTime, Utc};
 use sea_query::Iden;
 use uuid::Uuid;
@@ -5,12 +6,32 @@ use uuid::Uuid;
 #[derive(Debug, sqlx::FromRow)]
 pub struct SolarSystem {
     pub id: Uuid,
+    #[sqlx(default)]
     pub created_at: DateTime<Utc>,
+    #[sqlx(default)]
     pub updated_at: Option<DateTime<Utc>>,
+    #[sqlx(default)]
     pub version: i32,
+    #[sqlx(default)]
     pub save_id: Uuid,
+    #[sqlx(default)]
     pub name: String,
+    #[sqlx(default)]
     pub notes: Option<String>,
+    #[sqlx(default)]
+    pub planet_count: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct SolarSystemMatch {
+    pub solar_system: SolarSystem,
+    pub star: Option<Star>,
+}
+
+#[derive(Debug)]
+pub enum BatchCreateOutcome {
+    Created(SolarSystem),
+    Duplicate(Uuid),
 }
 
 #[derive(Debug, Copy, Clone, Iden)]
@@ -25,6 +46,10 @@ pub enum SolarSystemColumns {
     SaveId,
     Name,
     Notes,
+    CachedPlanetCount,
+    CachedHasStar,
+    CountsRefreshedAt,
+    AllowDuplicateNames,
 }
 
 impl SolarSystem {
@@ -35,8 +60,9 @@ impl SolarSystem {
             updated_at: None,
             version: 0,
             save_id,
-            name,
+            name: normalize_name(&name),
             notes,
+            planet_count: None,
         }
     }
 }
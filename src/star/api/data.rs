@@ -0,0 +1,275 @@
+use crate::{
+    data::{PageRequest, PageRequestRaw},
+    error::{TrackerError, Validator},
+    field::{AllowedValues, Bound, Field, FieldValue},
+    field_names,
+    solar_system::api::SolarSystemFields,
+    star::{domain, SpectralClass},
+    utils::{entity_tag, last_modified, validate_finite_positive},
+};
+use actix_web::{
+    body::BoxBody,
+    http::header::{ETag, LastModified},
+    HttpResponse, Responder,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Star {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub version: i32,
+    pub solar_system_id: Uuid,
+    pub spectral_class: SpectralClass,
+    pub luminosity: f32,
+    pub radius: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CreateStarRequest {
+    pub spectral_class: SpectralClass,
+    pub luminosity: f32,
+    pub radius: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateStarRequest {
+    /// The version the changes are based on; it must be the version `If-Match` names
+    pub version: i32,
+    pub spectral_class: Option<SpectralClass>,
+    pub luminosity: Option<f32>,
+    pub radius: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchRequestRaw {
+    #[serde(flatten)]
+    pub page_request: PageRequestRaw,
+    pub spectral_class: Option<SpectralClass>,
+    pub luminosity_min: Option<f32>,
+    pub luminosity_gt: Option<f32>,
+    pub luminosity_max: Option<f32>,
+    pub luminosity_lt: Option<f32>,
+    pub radius_min: Option<f32>,
+    pub radius_gt: Option<f32>,
+    pub radius_max: Option<f32>,
+    pub radius_lt: Option<f32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchRequest {
+    pub page_request: PageRequest<StarFields>,
+    pub spectral_class: Option<SpectralClass>,
+    pub luminosity_min: Option<Bound>,
+    pub luminosity_max: Option<Bound>,
+    pub radius_min: Option<Bound>,
+    pub radius_max: Option<Bound>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ReassignSpectralClassRequest {
+    pub from: SpectralClass,
+    pub to: SpectralClass,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReassignSpectralClassResponse {
+    pub updated: u64,
+}
+
+impl From<domain::Star> for Star {
+    fn from(value: domain::Star) -> Self {
+        Self {
+            id: value.id,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            version: value.version,
+            solar_system_id: value.solar_system_id,
+            spectral_class: value.spectral_class,
+            luminosity: value.luminosity,
+            radius: value.radius,
+        }
+    }
+}
+
+impl Responder for Star {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
+        HttpResponse::Ok()
+            .insert_header(ETag(entity_tag(self.version)))
+            .insert_header(LastModified(last_modified(
+                self.created_at,
+                self.updated_at,
+            )))
+            .json(self)
+    }
+}
+
+impl CreateStarRequest {
+    pub fn validate(
+        &self,
+        allowed_spectral_classes: Option<&[SpectralClass]>,
+    ) -> Result<(), TrackerError> {
+        let mut validator = Validator::new();
+        validator
+            .check(validate_spectral_class(
+                "spectral_class",
+                self.spectral_class,
+                allowed_spectral_classes,
+            ))
+            .check(validate_finite_positive("luminosity", self.luminosity))
+            .check(validate_finite_positive("radius", self.radius));
+        validator.finish()
+    }
+}
+
+impl UpdateStarRequest {
+    pub fn validate(
+        &self,
+        allowed_spectral_classes: Option<&[SpectralClass]>,
+    ) -> Result<(), TrackerError> {
+        let mut validator = Validator::new();
+        if let Some(spectral_class) = self.spectral_class {
+            validator.check(validate_spectral_class(
+                "spectral_class",
+                spectral_class,
+                allowed_spectral_classes,
+            ));
+        }
+
+        if let Some(luminosity) = self.luminosity {
+            validator.check(validate_finite_positive("luminosity", luminosity));
+        }
+
+        if let Some(radius) = self.radius {
+            validator.check(validate_finite_positive("radius", radius));
+        }
+        validator.finish()
+    }
+}
+
+impl ReassignSpectralClassRequest {
+    pub fn validate(
+        &self,
+        allowed_spectral_classes: Option<&[SpectralClass]>,
+    ) -> Result<(), TrackerError> {
+        validate_spectral_class("to", self.to, allowed_spectral_classes)
+    }
+}
+
+/// Rejects spectral classes outside the configured `ALLOWED_SPECTRAL_CLASSES`;
+/// every class is allowed when it is unset.
+pub fn validate_spectral_class(
+    name: &str,
+    spectral_class: SpectralClass,
+    allowed_spectral_classes: Option<&[SpectralClass]>,
+) -> Result<(), TrackerError> {
+    match allowed_spectral_classes {
+        Some(allowed) if !allowed.contains(&spectral_class) => Err(TrackerError::invalid_field(
+            FieldValue::new(name, spectral_class.as_ref()),
+            AllowedValues::choice(allowed.iter().map(|c| c.as_ref())),
+        )),
+        _ => Ok(()),
+    }
+}
+
+impl TryFrom<SearchRequestRaw> for SearchRequest {
+    type Error = TrackerError;
+
+    fn try_from(value: SearchRequestRaw) -> Result<Self, Self::Error> {
+        value.page_request.reject_cursor()?;
+
+        let (luminosity_min, luminosity_max) = parse_float_range(
+            ("luminosity_min", value.luminosity_min),
+            ("luminosity_gt", value.luminosity_gt),
+            ("luminosity_max", value.luminosity_max),
+            ("luminosity_lt", value.luminosity_lt),
+        )?;
+        let (radius_min, radius_max) = parse_float_range(
+            ("radius_min", value.radius_min),
+            ("radius_gt", value.radius_gt),
+            ("radius_max", value.radius_max),
+            ("radius_lt", value.radius_lt),
+        )?;
+
+        Ok(Self {
+            page_request: PageRequest::try_from(value.page_request)?,
+            spectral_class: value.spectral_class,
+            luminosity_min,
+            luminosity_max,
+            radius_min,
+            radius_max,
+        })
+    }
+}
+
+/// Each end of the range is given inclusively (`_min`/`_max`), exclusively
+/// (`_gt`/`_lt`) or both, in which case the tighter of the two applies.
+fn parse_float_range<'a>(
+    min: (&'a str, Option<f32>),
+    gt: (&'a str, Option<f32>),
+    max: (&'a str, Option<f32>),
+    lt: (&'a str, Option<f32>),
+) -> Result<(Option<Bound>, Option<Bound>), TrackerError> {
+    for (name, value) in [min, gt, max, lt] {
+        if let Some(value) = value.filter(|v| !v.is_finite()) {
+            return Err(TrackerError::invalid_field(
+                FieldValue::new(name, value),
+                AllowedValues::float(),
+            ));
+        }
+    }
+
+    let end = |(name, value): (&'a str, Option<f32>), inclusive| {
+        value.map(|value| (name, value, inclusive))
+    };
+    let lower = match (end(min, true), end(gt, false)) {
+        (Some(min), Some(gt)) => Some(if gt.1 >= min.1 { gt } else { min }),
+        (min, gt) => min.or(gt),
+    };
+    let upper = match (end(max, true), end(lt, false)) {
+        (Some(max), Some(lt)) => Some(if lt.1 <= max.1 { lt } else { max }),
+        (max, lt) => max.or(lt),
+    };
+
+    if let (Some((name, low, low_inclusive)), Some((_, high, high_inclusive))) = (lower, upper) {
+        let inclusive = low_inclusive && high_inclusive;
+        if low > high || (low == high && !inclusive) {
+            return Err(TrackerError::invalid_field(
+                FieldValue::new(name, low),
+                AllowedValues::float_max(Bound {
+                    value: high.into(),
+                    inclusive,
+                }),
+            ));
+        }
+    }
+
+    let bound = |(_, value, inclusive): (&str, f32, bool)| Bound {
+        value: value.into(),
+        inclusive,
+    };
+    Ok((lower.map(bound), upper.map(bound)))
+}
+
+field_names!(
+    StarFields<domain::StarColumns> {
+        Id => { value: "id", column: Id, kind: Uuid },
+        SolarSystem(SolarSystemFields) => { prefix: "solar_system" },
+        #[default]
+        CreatedAt => { value: "created_at", column: CreatedAt, kind: DateTime },
+        SpectralClass => { value: "spectral_class", column: SpectralClass, kind: Enum("spectral_class", SpectralClass) },
+        Luminosity => { value: "luminosity", column: Luminosity, kind: Float },
+        Radius => { value: "radius", column: Radius, kind: Float }
+    }
+);
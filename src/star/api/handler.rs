@@ -0,0 +1,495 @@
+use super::{
+    CreateStarRequest, ReassignSpectralClassRequest, ReassignSpectralClassResponse, Star,
+    UpdateStarRequest,
+};
+use crate::star::api::{SearchRequest, SearchRequestRaw};
+use crate::star::domain;
+use crate::{
+    auth::Owner,
+    data::{begin_update, with_current_version, Page, PageRequestRaw},
+    error::{log_entity_error, log_error, log_lookup_error, ObjectKind, Result},
+    game_save,
+    idempotency::Idempotency,
+    solar_system,
+    utils::{check_if_match, last_modified, not_modified, ApiResponse},
+    AppState,
+};
+use actix_web::{delete, get, patch, post, web, HttpRequest, HttpResponse, Responder};
+use uuid::Uuid;
+
+#[utoipa::path(
+    operation_id = "createStar",
+    tag = "stars",
+    params(
+        ("solarSystemId" = Uuid, Path, description = "The solar system id"),
+        ("Idempotency-Key" = Option<String>, Header, description = "A client-chosen key; retrying with the same key returns the originally created resource"),
+    ),
+    request_body = CreateStarRequest,
+    responses(
+        (status = 201, description = "The created star", body = Star),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 404, description = "The solar system does not exist", body = ErrorResponse),
+        (status = 409, description = "A conflicting star already exists", body = ErrorResponse),
+    )
+)]
+#[post("/solar-systems/{solarSystemId}/star")]
+async fn create_handler(
+    owner: Owner,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    request: web::Json<CreateStarRequest>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<Star>> {
+    request.validate(data.allowed_spectral_classes.as_deref())?;
+
+    let idempotency = Idempotency::from_request(&req, ObjectKind::Star, data.idempotency_key_ttl)?;
+    let mut transaction = data.db.begin().await?;
+    solar_system::check_owner(&mut transaction, owner, *path).await?;
+    let response = match idempotency.lookup(&mut transaction).await? {
+        Some(id) => domain::lookup(&mut transaction, id).await?,
+        None => {
+            let solar_system_id = path.into_inner();
+
+            let star = domain::Star::new(
+                solar_system_id,
+                request.spectral_class,
+                request.luminosity,
+                request.radius,
+            );
+
+            let response =
+                domain::create(&mut transaction, &star)
+                    .await
+                    .inspect_err(log_entity_error(
+                        "Failed to create star",
+                        ObjectKind::Star,
+                        star.id,
+                    ))?;
+            idempotency.record(&mut transaction, response.id).await?;
+            response
+        }
+    };
+    transaction.commit().await?;
+
+    Ok(ApiResponse::created_versioned(
+        format!("/stars/{0}", response.id),
+        response.version,
+        Star::from(response),
+    ))
+}
+
+#[utoipa::path(
+    operation_id = "lookupStar",
+    tag = "stars",
+    params(
+        ("id" = Uuid, Path, description = "The star id"),
+        ("If-None-Match" = Option<String>, Header, description = "An ETag from a previous response"),
+        ("If-Modified-Since" = Option<String>, Header, description = "The Last-Modified date from a previous response; ignored when If-None-Match is present"),
+    ),
+    responses(
+        (status = 200, description = "The star", body = Star),
+        (status = 304, description = "The supplied If-None-Match ETag matches the current version, or the resource is unmodified since If-Modified-Since"),
+        (status = 400, description = "The id is not a valid UUID", body = ErrorResponse),
+        (status = 404, description = "The star does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/stars/{id}")]
+async fn lookup_handler(
+    owner: Owner,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut transaction = data.db.begin().await?;
+
+    let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+    let response = domain::lookup(&mut transaction, id)
+        .await
+        .inspect_err(log_lookup_error(ObjectKind::Star, id))
+        .map(Star::from)?;
+
+    transaction.commit().await?;
+    Ok(not_modified(
+        &req,
+        response.version,
+        last_modified(response.created_at, response.updated_at),
+    )
+    .unwrap_or_else(|| response.respond_to(&req)))
+}
+
+#[utoipa::path(
+    operation_id = "deleteStar",
+    tag = "stars",
+    params(
+        ("id" = Uuid, Path, description = "The star id"),
+    ),
+    responses(
+        (status = 204, description = "The star was deleted"),
+    )
+)]
+#[delete("/stars/{id}")]
+async fn delete_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<()>> {
+    let mut transaction = data.db.begin().await?;
+    let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+
+    domain::delete(&mut transaction, id).await?;
+    transaction.commit().await?;
+
+    Ok(ApiResponse::NoContent)
+}
+
+#[utoipa::path(
+    operation_id = "searchStars",
+    tag = "stars",
+    params(
+        ("saveId" = Uuid, Path, description = "The save id"),
+        PageRequestRaw,
+        ("spectral_class" = Option<SpectralClass>, Query, description = "Only include stars of this spectral class"),
+        ("luminosity_min" = Option<f32>, Query, description = "Only include stars with a luminosity of at least this value"),
+        ("luminosity_gt" = Option<f32>, Query, description = "Only include stars with a luminosity greater than this value"),
+        ("luminosity_max" = Option<f32>, Query, description = "Only include stars with a luminosity of at most this value"),
+        ("luminosity_lt" = Option<f32>, Query, description = "Only include stars with a luminosity less than this value"),
+        ("radius_min" = Option<f32>, Query, description = "Only include stars with a radius of at least this value"),
+        ("radius_gt" = Option<f32>, Query, description = "Only include stars with a radius greater than this value"),
+        ("radius_max" = Option<f32>, Query, description = "Only include stars with a radius of at most this value"),
+        ("radius_lt" = Option<f32>, Query, description = "Only include stars with a radius less than this value"),
+    ),
+    responses(
+        (status = 200, description = "A page of stars", body = StarPage),
+        (status = 400, description = "The search parameters were invalid", body = ErrorResponse),
+    )
+)]
+#[get("/saves/{saveId}/stars")]
+async fn search_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    query: web::Query<SearchRequestRaw>,
+    data: web::Data<AppState>,
+) -> Result<Page<Star>> {
+    let mut transaction = data.db.begin().await?;
+    let save_id = path.into_inner();
+    game_save::check_owner(&mut transaction, owner, save_id).await?;
+    let mut search_raw = query.into_inner();
+    search_raw.page_request.paging = data.paging;
+    let search_params = SearchRequest::try_from(search_raw)?;
+
+    let response = domain::search(&mut transaction, save_id, &search_params)
+        .await
+        .map(Page::convert)
+        .inspect_err(log_error("Failed to search for stars", ObjectKind::Star))?;
+    transaction.commit().await?;
+    Ok(response)
+}
+
+#[utoipa::path(
+    operation_id = "updateStar",
+    tag = "stars",
+    params(
+        ("id" = Uuid, Path, description = "The star id"),
+        ("If-Match" = String, Header, description = "The ETag of the current version"),
+    ),
+    request_body = UpdateStarRequest,
+    responses(
+        (status = 200, description = "The updated star", body = Star),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 404, description = "The star does not exist", body = ErrorResponse),
+        (status = 409, description = "The star was concurrently modified or conflicts with an existing star", body = ErrorResponse),
+        (status = 412, description = "The star has been modified since it was fetched", body = ErrorResponse),
+        (status = 428, description = "The If-Match header is missing", body = ErrorResponse),
+    )
+)]
+#[patch("/stars/{id}")]
+async fn update_handler(
+    owner: Owner,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    request: web::Json<UpdateStarRequest>,
+    data: web::Data<AppState>,
+) -> Result<Star> {
+    request.validate(data.allowed_spectral_classes.as_deref())?;
+
+    let mut transaction = begin_update(&data.db, data.pessimistic_locking).await?;
+    let id = path.into_inner();
+    domain::check_owner(&mut transaction, owner, id).await?;
+
+    let mut star = with_current_version(
+        domain::lookup_for_update(&mut transaction, id).await,
+        &data.db,
+        (
+            domain::StarColumns::Table,
+            domain::StarColumns::Id,
+            domain::StarColumns::Version,
+        ),
+        id,
+        Some(request.version),
+    )
+    .await?;
+    check_if_match(&req, ObjectKind::Star, id, star.version, request.version)?;
+    star.version = request.version;
+
+    if let Some(spectral_class) = request.spectral_class {
+        star.spectral_class = spectral_class;
+    }
+
+    if let Some(luminosity) = request.luminosity {
+        star.luminosity = luminosity;
+    }
+
+    if let Some(radius) = request.radius {
+        star.radius = radius;
+    }
+
+    let response = with_current_version(
+        domain::update(&mut transaction, &star).await,
+        &data.db,
+        (
+            domain::StarColumns::Table,
+            domain::StarColumns::Id,
+            domain::StarColumns::Version,
+        ),
+        id,
+        Some(star.version),
+    )
+    .await
+    .inspect_err(log_entity_error(
+        "Failed to update star",
+        ObjectKind::Star,
+        id,
+    ))?;
+
+    transaction.commit().await?;
+    Ok(response.into())
+}
+
+#[utoipa::path(
+    operation_id = "reassignSpectralClass",
+    tag = "stars",
+    params(
+        ("saveId" = Uuid, Path, description = "The save id"),
+    ),
+    request_body = ReassignSpectralClassRequest,
+    responses(
+        (status = 200, description = "The number of stars that were updated", body = ReassignSpectralClassResponse),
+        (status = 400, description = "The request was invalid", body = ErrorResponse),
+        (status = 404, description = "The save does not exist", body = ErrorResponse),
+    )
+)]
+#[patch("/saves/{saveId}/stars/spectral-class")]
+async fn reassign_spectral_class_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    request: web::Json<ReassignSpectralClassRequest>,
+    data: web::Data<AppState>,
+) -> Result<ApiResponse<ReassignSpectralClassResponse>> {
+    request.validate(data.allowed_spectral_classes.as_deref())?;
+
+    let mut transaction = data.db.begin().await?;
+    let save_id = path.into_inner();
+    game_save::check_owner(&mut transaction, owner, save_id).await?;
+
+    let updated =
+        domain::reassign_spectral_class(&mut transaction, save_id, request.from, request.to)
+            .await
+            .inspect_err(log_entity_error(
+                "Failed to reassign spectral class of stars",
+                ObjectKind::Save,
+                save_id,
+            ))?;
+    transaction.commit().await?;
+
+    Ok(ApiResponse::Ok(ReassignSpectralClassResponse { updated }))
+}
+
+#[utoipa::path(
+    operation_id = "saveSpectralClasses",
+    tag = "stars",
+    params(
+        ("saveId" = Uuid, Path, description = "The save id"),
+    ),
+    responses(
+        (status = 200, description = "The spectral classes of the save's stars, in class order", body = Vec<SpectralClass>),
+        (status = 404, description = "The save does not exist", body = ErrorResponse),
+    )
+)]
+#[get("/saves/{saveId}/stars/spectral-classes")]
+async fn spectral_classes_handler(
+    owner: Owner,
+    path: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mut transaction = data.db.begin().await?;
+    let save_id = path.into_inner();
+    game_save::check_owner(&mut transaction, owner, save_id).await?;
+
+    let spectral_classes = domain::distinct_spectral_classes(&mut transaction, save_id)
+        .await
+        .inspect_err(log_error(
+            "Failed to list spectral classes",
+            ObjectKind::Star,
+        ))?;
+    transaction.commit().await?;
+
+    Ok(HttpResponse::Ok().json(spectral_classes))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{
+        app_state, create_save, create_solar_system, db_pool, send, test_app,
+    };
+    use actix_web::{http::StatusCode, test};
+    use serde_json::json;
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn negative_luminosity_is_rejected() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let solar_system = create_solar_system(&app, save["id"].as_str().unwrap(), "Sol").await;
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!(
+                    "/api/1/solar-systems/{0}/star",
+                    solar_system["id"].as_str().unwrap()
+                ))
+                .set_json(json!({
+                    "spectral_class": "class_g",
+                    "luminosity": -1.0,
+                    "radius": 1.0,
+                }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        assert_eq!(body["error_code"], "InvalidFieldValue");
+        assert_eq!(body["field"]["name"], "luminosity");
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn star_lookups_deserialize_the_stored_star() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let save_id = save["id"].as_str().unwrap();
+        let solar_system = create_solar_system(&app, save_id, "Sol").await;
+        let solar_system_id = solar_system["id"].as_str().unwrap();
+
+        let (status, star) = send(
+            &app,
+            test::TestRequest::post()
+                .uri(&format!("/api/1/solar-systems/{solar_system_id}/star"))
+                .set_json(json!({
+                    "spectral_class": "class_g",
+                    "luminosity": 1.5,
+                    "radius": 2.0,
+                }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED, "{star}");
+
+        let (status, fetched) = send(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!("/api/1/stars/{0}", star["id"].as_str().unwrap()))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{fetched}");
+        assert_eq!(fetched, star);
+        assert_eq!(fetched["solar_system_id"], solar_system_id);
+        assert_eq!(fetched["spectral_class"], "class_g");
+        assert_eq!(fetched["luminosity"], 1.5);
+        assert_eq!(fetched["radius"], 2.0);
+
+        let (status, page) = send(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!(
+                    "/api/1/saves/{save_id}/solar-systems?include=star"
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{page}");
+        assert_eq!(page["data"][0]["star"], star);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn exclusive_bounds_drop_the_boundary_value() {
+        let app = test::init_service(test_app(app_state(db_pool().await))).await;
+        let save = create_save(&app).await;
+        let save_id = save["id"].as_str().unwrap();
+        for (name, luminosity) in [("Dim", 1.0), ("Bright", 2.0)] {
+            let solar_system = create_solar_system(&app, save_id, name).await;
+            let (status, star) = send(
+                &app,
+                test::TestRequest::post()
+                    .uri(&format!(
+                        "/api/1/solar-systems/{0}/star",
+                        solar_system["id"].as_str().unwrap()
+                    ))
+                    .set_json(json!({
+                        "spectral_class": "class_g",
+                        "luminosity": luminosity,
+                        "radius": 1.0,
+                    }))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::CREATED, "{star}");
+        }
+
+        let luminosities = |query: &'static str| {
+            let app = &app;
+            async move {
+                let (status, page) = send(
+                    app,
+                    test::TestRequest::get()
+                        .uri(&format!(
+                            "/api/1/saves/{save_id}/stars?{query}&sort=luminosity"
+                        ))
+                        .to_request(),
+                )
+                .await;
+                assert_eq!(status, StatusCode::OK, "{page}");
+                page["data"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|star| star["luminosity"].as_f64().unwrap())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(luminosities("luminosity_min=1.0").await, [1.0, 2.0]);
+        assert_eq!(luminosities("luminosity_gt=1.0").await, [2.0]);
+        assert_eq!(luminosities("luminosity_max=2.0").await, [1.0, 2.0]);
+        assert_eq!(luminosities("luminosity_lt=2.0").await, [1.0]);
+        assert_eq!(
+            luminosities("luminosity_min=1.0&luminosity_gt=1.0").await,
+            [2.0]
+        );
+
+        let (status, body) = send(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!(
+                    "/api/1/saves/{save_id}/stars?luminosity_gt=1.0&luminosity_max=1.0"
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        assert_eq!(body["field"]["name"], "luminosity_gt");
+    }
+}
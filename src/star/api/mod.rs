@@ -0,0 +1,39 @@
+mod data;
+mod handler;
+
+use actix_web::web;
+pub use data::*;
+use utoipa::OpenApi;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(handler::create_handler)
+        .service(handler::lookup_handler)
+        .service(handler::search_handler)
+        .service(handler::delete_handler)
+        .service(handler::update_handler)
+        .service(handler::reassign_spectral_class_handler)
+        .service(handler::spectral_classes_handler);
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        handler::create_handler,
+        handler::lookup_handler,
+        handler::search_handler,
+        handler::update_handler,
+        handler::delete_handler,
+        handler::reassign_spectral_class_handler,
+        handler::spectral_classes_handler,
+    ),
+    components(schemas(
+        Star,
+        CreateStarRequest,
+        UpdateStarRequest,
+        ReassignSpectralClassRequest,
+        ReassignSpectralClassResponse,
+        crate::star::SpectralClass,
+        crate::data::StarPage,
+    ))
+)]
+pub struct ApiDoc;
@@ -1 +1,445 @@
+use super::{Star, StarColumns};
+use crate::{
+    auth::Owner,
+    data::{add_sorts, is_serialization_failure, paginate, Page, Sort},
+    error::{ObjectKind, Result, TrackerError},
+    field::{AllowedValues, Bound, FieldValue},
+    game_save::{self, GameSaveColumns},
+    solar_system::{self, SolarSystemColumns},
+    star::{
+        api::{SearchRequest, StarFields},
+        SpectralClass,
+    },
+};
+use sea_query::{
+    Alias, Asterisk, Expr, Func, Iden, LockType, Order, PostgresQueryBuilder, Query,
+    SelectStatement, SimpleExpr,
+};
+use sea_query_binder::SqlxBinder;
+use sqlx::{error::ErrorKind, postgres::PgRow, Postgres, Row, Transaction};
+use uuid::Uuid;
 
+const STAR_COLUMNS: [StarColumns; 8] = [
+    StarColumns::Id,
+    StarColumns::CreatedAt,
+    StarColumns::UpdatedAt,
+    StarColumns::Version,
+    StarColumns::SolarSystemId,
+    StarColumns::SpectralClass,
+    StarColumns::Luminosity,
+    StarColumns::Radius,
+];
+
+pub async fn create<'a>(tx: &mut Transaction<'a, Postgres>, star: &Star) -> Result<Star> {
+    let (sql, values) = Query::insert()
+        .into_table(StarColumns::Table)
+        .columns([
+            StarColumns::Id,
+            StarColumns::CreatedAt,
+            StarColumns::Version,
+            StarColumns::SolarSystemId,
+            StarColumns::SpectralClass,
+            StarColumns::Luminosity,
+            StarColumns::Radius,
+        ])
+        .values_panic([
+            star.id.into(),
+            Expr::cust("clock_timestamp()"),
+            star.version.into(),
+            star.solar_system_id.into(),
+            Expr::val(star.spectral_class.as_ref()).as_enum(Alias::new("spectral_class")),
+            star.luminosity.into(),
+            star.radius.into(),
+        ])
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| map_constraint_errors(err, star))?;
+
+    lookup(tx, star.id)
+        .await
+        .map_err(TrackerError::not_found_unexpected)
+}
+
+pub async fn update<'a>(tx: &mut Transaction<'a, Postgres>, star: &Star) -> Result<Star> {
+    let (sql, values) = Query::update()
+        .table(StarColumns::Table)
+        .values([
+            (StarColumns::UpdatedAt, Expr::current_timestamp().into()),
+            (StarColumns::Version, Expr::col(StarColumns::Version).add(1)),
+            (
+                StarColumns::SpectralClass,
+                Expr::val(star.spectral_class.as_ref()).as_enum(Alias::new("spectral_class")),
+            ),
+            (StarColumns::Luminosity, star.luminosity.into()),
+            (StarColumns::Radius, star.radius.into()),
+        ])
+        .and_where(Expr::col(StarColumns::Id).eq(star.id))
+        .and_where(Expr::col(StarColumns::Version).eq(star.version))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows_updated = sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await
+        .map_err(|err| map_constraint_errors(err, star))?
+        .rows_affected();
+
+    if rows_updated == 0 {
+        Err(TrackerError::concurrent_update(
+            ObjectKind::Star,
+            FieldValue::new(StarColumns::Id, star.id),
+        ))
+    } else {
+        lookup(tx, star.id).await
+    }
+}
+
+pub async fn reassign_spectral_class<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+    from: SpectralClass,
+    to: SpectralClass,
+) -> Result<u64> {
+    game_save::lookup(tx, save_id).await?;
+
+    let (sql, values) = Query::update()
+        .table(StarColumns::Table)
+        .values([
+            (StarColumns::UpdatedAt, Expr::current_timestamp().into()),
+            (StarColumns::Version, Expr::col(StarColumns::Version).add(1)),
+            (
+                StarColumns::SpectralClass,
+                Expr::val(to.as_ref()).as_enum(Alias::new("spectral_class")),
+            ),
+        ])
+        .and_where(
+            Expr::col(StarColumns::SpectralClass)
+                .eq(Expr::val(from.as_ref()).as_enum(Alias::new("spectral_class"))),
+        )
+        .and_where(
+            Expr::col(StarColumns::SolarSystemId).in_subquery(
+                Query::select()
+                    .column(SolarSystemColumns::Id)
+                    .from(SolarSystemColumns::Table)
+                    .and_where(Expr::col(SolarSystemColumns::SaveId).eq(save_id))
+                    .to_owned(),
+            ),
+        )
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await?
+        .rows_affected())
+}
+
+pub async fn distinct_spectral_classes<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+) -> Result<Vec<SpectralClass>> {
+    game_save::lookup(tx, save_id).await?;
+
+    let (sql, values) = Query::select()
+        .distinct()
+        .column((StarColumns::Table, StarColumns::SpectralClass))
+        .from(StarColumns::Table)
+        .inner_join(
+            SolarSystemColumns::Table,
+            Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Id))
+                .equals((StarColumns::Table, StarColumns::SolarSystemId)),
+        )
+        .and_where(Expr::col((SolarSystemColumns::Table, SolarSystemColumns::SaveId)).eq(save_id))
+        .order_by((StarColumns::Table, StarColumns::SpectralClass), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_with(&sql, values.clone())
+        .fetch_all(&mut **tx)
+        .await?
+        .iter()
+        .map(|row| row.get(0))
+        .collect())
+}
+
+pub async fn check_owner<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    owner: Owner,
+    id: Uuid,
+) -> Result<()> {
+    let select_stmt = Query::select()
+        .expr(Expr::val(1))
+        .from(StarColumns::Table)
+        .inner_join(
+            SolarSystemColumns::Table,
+            Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Id))
+                .equals((StarColumns::Table, StarColumns::SolarSystemId)),
+        )
+        .inner_join(
+            GameSaveColumns::Table,
+            Expr::col((GameSaveColumns::Table, GameSaveColumns::Id))
+                .equals((SolarSystemColumns::Table, SolarSystemColumns::SaveId)),
+        )
+        .and_where(Expr::col((StarColumns::Table, StarColumns::Id)).eq(id))
+        .to_owned();
+
+    game_save::ensure_owned(tx, owner, select_stmt, || {
+        TrackerError::not_found(ObjectKind::Star, FieldValue::new(StarColumns::Id, id))
+    })
+    .await
+}
+
+pub async fn lookup_optional<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    id: Uuid,
+) -> Result<Option<Star>> {
+    let (sql, values) = Query::select()
+        .column((Alias::new("star"), Asterisk))
+        .from_as(StarColumns::Table, Alias::new("star"))
+        .and_where(Expr::col(StarColumns::Id).eq(id))
+        .limit(1)
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_as_with::<_, Star, _>(&sql, values.clone())
+        .fetch_optional(&mut **tx)
+        .await?)
+}
+
+pub async fn lookup<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<Star> {
+    lookup_optional(tx, id)
+        .await
+        .transpose()
+        .unwrap_or_else(|| {
+            Err(TrackerError::not_found(
+                ObjectKind::Star,
+                FieldValue::new(StarColumns::Id, id),
+            ))
+        })
+}
+
+pub async fn lookup_for_update<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<Star> {
+    let (sql, values) = Query::select()
+        .column(Asterisk)
+        .from(StarColumns::Table)
+        .and_where(Expr::col(StarColumns::Id).eq(id))
+        .lock(LockType::Update)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_as_with::<_, Star, _>(&sql, values.clone())
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|err| {
+            if is_serialization_failure(&err) {
+                TrackerError::concurrent_update(
+                    ObjectKind::Star,
+                    FieldValue::new(StarColumns::Id, id),
+                )
+            } else {
+                TrackerError::from(err)
+            }
+        })?
+        .ok_or_else(|| {
+            TrackerError::not_found(ObjectKind::Star, FieldValue::new(StarColumns::Id, id))
+        })
+}
+
+pub async fn lookup_by_solar_systems<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    solar_system_ids: &[Uuid],
+) -> Result<Vec<Star>> {
+    if solar_system_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (sql, values) = Query::select()
+        .column(Asterisk)
+        .from(StarColumns::Table)
+        .and_where(Expr::col(StarColumns::SolarSystemId).is_in(solar_system_ids.iter().copied()))
+        .build_sqlx(PostgresQueryBuilder);
+
+    Ok(sqlx::query_as_with::<_, Star, _>(&sql, values.clone())
+        .fetch_all(&mut **tx)
+        .await?)
+}
+
+pub fn select_prefixed_columns(select_stmt: &mut SelectStatement, prefix: &str) {
+    for column in STAR_COLUMNS {
+        select_stmt.expr_as(
+            Expr::col((StarColumns::Table, column)),
+            Alias::new(format!("{0}{1}", prefix, column.to_string())),
+        );
+    }
+}
+
+pub fn from_prefixed_row(row: &PgRow, prefix: &str) -> Result<Option<Star>> {
+    let column = |column: StarColumns| format!("{0}{1}", prefix, column.to_string());
+
+    let Some(id) = row.try_get::<Option<Uuid>, _>(column(StarColumns::Id).as_str())? else {
+        return Ok(None);
+    };
+
+    Ok(Some(Star {
+        id,
+        created_at: row.try_get(column(StarColumns::CreatedAt).as_str())?,
+        updated_at: row.try_get(column(StarColumns::UpdatedAt).as_str())?,
+        version: row.try_get(column(StarColumns::Version).as_str())?,
+        solar_system_id: row.try_get(column(StarColumns::SolarSystemId).as_str())?,
+        spectral_class: row.try_get(column(StarColumns::SpectralClass).as_str())?,
+        luminosity: row.try_get(column(StarColumns::Luminosity).as_str())?,
+        radius: row.try_get(column(StarColumns::Radius).as_str())?,
+    }))
+}
+
+pub async fn search<'a>(
+    tx: &mut Transaction<'a, Postgres>,
+    save_id: Uuid,
+    search_params: &SearchRequest,
+) -> Result<Page<Star>> {
+    let page_req = &search_params.page_request;
+    let mut joins_tracker = vec![SolarSystemColumns::Table.to_string()];
+
+    let mut select_count_stmt = Query::select()
+        .expr(Func::count(Expr::col(Asterisk)))
+        .from(StarColumns::Table)
+        .inner_join(
+            SolarSystemColumns::Table,
+            Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Id))
+                .equals((StarColumns::Table, StarColumns::SolarSystemId)),
+        )
+        .to_owned();
+    add_where_clause(&mut select_count_stmt, save_id, search_params);
+
+    let mut select_stmt = Query::select()
+        .column((StarColumns::Table, Asterisk))
+        .from(StarColumns::Table)
+        .inner_join(
+            SolarSystemColumns::Table,
+            Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Id))
+                .equals((StarColumns::Table, StarColumns::SolarSystemId)),
+        )
+        .to_owned();
+    add_where_clause(&mut select_stmt, save_id, search_params);
+    add_sort_joins(&mut select_stmt, &page_req.sorts, &mut joins_tracker);
+    add_sorts(
+        &mut select_stmt,
+        &page_req.sorts,
+        (StarColumns::Table, StarColumns::Id),
+    );
+
+    paginate(tx, select_count_stmt, select_stmt, page_req).await
+}
+
+pub async fn delete<'a>(tx: &mut Transaction<'a, Postgres>, id: Uuid) -> Result<()> {
+    let (sql, values) = Query::delete()
+        .from_table(StarColumns::Table)
+        .and_where(Expr::col(StarColumns::Id).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values.clone())
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+fn add_where_clause(select_stmt: &mut SelectStatement, save_id: Uuid, req: &SearchRequest) {
+    select_stmt
+        .and_where(Expr::col((SolarSystemColumns::Table, SolarSystemColumns::SaveId)).eq(save_id));
+
+    if let Some(spectral_class) = req.spectral_class {
+        select_stmt.and_where(
+            Expr::col((StarColumns::Table, StarColumns::SpectralClass))
+                .eq(Expr::val(spectral_class.as_ref()).as_enum(Alias::new("spectral_class"))),
+        );
+    }
+
+    let luminosity = (StarColumns::Table, StarColumns::Luminosity);
+    let radius = (StarColumns::Table, StarColumns::Radius);
+    for (column, bound, lower) in [
+        (luminosity, &req.luminosity_min, true),
+        (luminosity, &req.luminosity_max, false),
+        (radius, &req.radius_min, true),
+        (radius, &req.radius_max, false),
+    ] {
+        if let Some(bound) = bound {
+            select_stmt.and_where(bound_predicate(column, bound, lower));
+        }
+    }
+}
+
+fn bound_predicate(column: (StarColumns, StarColumns), bound: &Bound, lower: bool) -> SimpleExpr {
+    let col = Expr::col(column);
+    let value = Expr::val(bound.value.clone()).cast_as(Alias::new("real"));
+    match (lower, bound.inclusive) {
+        (true, true) => col.gte(value),
+        (true, false) => col.gt(value),
+        (false, true) => col.lte(value),
+        (false, false) => col.lt(value),
+    }
+}
+
+fn add_sort_joins(
+    select_stmt: &mut SelectStatement,
+    sorts: &[Sort<StarFields>],
+    joins_tracker: &mut Vec<String>,
+) {
+    for sort in sorts {
+        add_join_for_field(select_stmt, sort.field, joins_tracker);
+    }
+}
+
+pub fn add_join_for_field(
+    select_stmt: &mut SelectStatement,
+    field: StarFields,
+    joins_tracker: &mut Vec<String>,
+) {
+    if let StarFields::SolarSystem(solar_system_field) = field {
+        let solar_system_table = SolarSystemColumns::Table.to_string();
+        if !joins_tracker.contains(&solar_system_table) {
+            joins_tracker.push(solar_system_table);
+            select_stmt.left_join(
+                SolarSystemColumns::Table,
+                Expr::col((SolarSystemColumns::Table, SolarSystemColumns::Id))
+                    .equals((StarColumns::Table, StarColumns::SolarSystemId)),
+            );
+        }
+        solar_system::add_join_for_field(select_stmt, solar_system_field, joins_tracker);
+    }
+}
+
+fn map_constraint_errors(err: sqlx::Error, star: &Star) -> TrackerError {
+    match &err {
+        _ if is_serialization_failure(&err) => TrackerError::concurrent_update(
+            ObjectKind::Star,
+            FieldValue::new(StarColumns::Id, star.id),
+        ),
+        sqlx::Error::Database(db_err) => match (db_err.kind(), db_err.constraint()) {
+            (ErrorKind::UniqueViolation, Some("stars_solar_system_id_key")) => {
+                TrackerError::duplicate(
+                    ObjectKind::Star,
+                    FieldValue::new(StarColumns::SolarSystemId, star.solar_system_id),
+                )
+            }
+            (ErrorKind::UniqueViolation, Some("stars_pkey")) => {
+                TrackerError::duplicate(ObjectKind::Star, FieldValue::new(StarColumns::Id, star.id))
+            }
+            (ErrorKind::ForeignKeyViolation, Some("stars_solar_system_id_fkey")) => {
+                TrackerError::not_found(
+                    ObjectKind::SolarSystem,
+                    FieldValue::new(SolarSystemColumns::Id, star.solar_system_id),
+                )
+            }
+            (ErrorKind::CheckViolation, Some("positive_luminosity")) => {
+                TrackerError::invalid_field(
+                    FieldValue::new(StarColumns::Luminosity, star.luminosity),
+                    AllowedValues::float_min(Bound::exclusive(0.0)),
+                )
+            }
+            (ErrorKind::CheckViolation, Some("positive_radius")) => TrackerError::invalid_field(
+                FieldValue::new(StarColumns::Radius, star.radius),
+                AllowedValues::float_min(Bound::exclusive(0.0)),
+            ),
+            _ => TrackerError::from(err),
+        },
+        _ => TrackerError::from(err),
+    }
+}
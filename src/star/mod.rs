@@ -1,9 +1,26 @@
+pub mod api;
 pub mod domain;
 
+pub use api::config;
+
 use serde::{Deserialize, Serialize};
-use strum::{AsRefStr, EnumIter};
+use strum::{AsRefStr, EnumIter, EnumString};
+use utoipa::ToSchema;
 
-#[derive(Debug, Copy, Clone, sqlx::Type, AsRefStr, EnumIter, Serialize, Deserialize)]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    sqlx::Type,
+    AsRefStr,
+    EnumIter,
+    EnumString,
+    Serialize,
+    Deserialize,
+    ToSchema,
+)]
 #[sqlx(type_name = "spectral_class", rename_all = "snake_case")]
 #[strum(serialize_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
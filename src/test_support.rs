@@ -0,0 +1,186 @@
+use crate::{
+    config, data::PagingConfig, error, extractor_config, AppState, DEFAULT_MAX_JSON_BODY_BYTES,
+    DEFAULT_MAX_MINING_SPEED,
+};
+use actix_http::Request;
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Service, ServiceFactory, ServiceRequest, ServiceResponse},
+    http::StatusCode,
+    test, web, App,
+};
+use serde_json::{json, Value};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+
+/// Connects to `DATABASE_URL` and runs the migrations. Tests that need a
+/// database are `#[ignore]`d; run them with `cargo test -- --ignored`.
+pub async fn db_pool() -> PgPool {
+    dotenvy::dotenv().ok();
+    let conn_str = std::env::var("DATABASE_URL").expect("DATABASE_URL is required");
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect(&conn_str)
+        .await
+        .expect("Failed to connect to the database");
+    sqlx::migrate!()
+        .run(&pool)
+        .await
+        .expect("Failed to run sql migrations");
+    pool
+}
+
+pub fn app_state(db: PgPool) -> AppState {
+    AppState {
+        db,
+        base_url: None,
+        max_mining_speed: DEFAULT_MAX_MINING_SPEED,
+        idempotency_key_ttl: chrono::Duration::hours(1),
+        pessimistic_locking: false,
+        admin_token: None,
+        api_tokens: Vec::new(),
+        allowed_spectral_classes: None,
+        paging: PagingConfig::default(),
+    }
+}
+
+pub fn test_app(
+    state: AppState,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<BoxBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    App::new()
+        .app_data(web::Data::new(state))
+        .configure(extractor_config(DEFAULT_MAX_JSON_BODY_BYTES))
+        .configure(config)
+        .default_service(web::to(error::route_not_found_handler))
+}
+
+/// Sends the request and returns the status with the body parsed as JSON,
+/// or `Value::Null` for an empty body.
+pub async fn send<S, B>(app: &S, req: Request) -> (StatusCode, Value)
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let res = test::call_service(app, req).await;
+    let status = res.status();
+    let body = test::read_body(res).await;
+    let json = if body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&body).expect("response body should be JSON")
+    };
+    (status, json)
+}
+
+/// Creates a save with a unique name and returns it.
+pub async fn create_save<S, B>(app: &S) -> Value
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let (status, save) = send(
+        app,
+        test::TestRequest::post()
+            .uri("/api/1/saves")
+            .set_json(json!({ "name": unique_name("Save"), "mining_speed": 200 }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{save}");
+    save
+}
+
+/// Creates a solar system with the given name in the save and returns it.
+pub async fn create_solar_system<S, B>(app: &S, save_id: &str, name: &str) -> Value
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let (status, solar_system) = send(
+        app,
+        test::TestRequest::post()
+            .uri(&format!("/api/1/saves/{save_id}/solar-systems"))
+            .set_json(json!({ "name": name }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{solar_system}");
+    solar_system
+}
+
+/// Creates a planet type with a unique name and returns it.
+pub async fn create_planet_type<S, B>(app: &S) -> Value
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let (status, planet_type) = send(
+        app,
+        test::TestRequest::post()
+            .uri("/api/1/planet-types")
+            .set_json(json!({ "name": unique_name("Planet Type"), "wind_energy_percent": 100 }))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{planet_type}");
+    planet_type
+}
+
+/// Posts a planet with the given fields added to a minimal valid planet.
+pub async fn post_planet<S, B>(app: &S, solar_system_id: &str, fields: Value) -> (StatusCode, Value)
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let mut planet = json!({
+        "name": unique_name("Planet"),
+        "rotation_direction": "normal",
+        "solar_energy_percent": 100,
+    });
+    planet
+        .as_object_mut()
+        .unwrap()
+        .extend(fields.as_object().cloned().unwrap_or_default());
+
+    send(
+        app,
+        test::TestRequest::post()
+            .uri(&format!("/api/1/solar-systems/{solar_system_id}/planets"))
+            .set_json(planet)
+            .to_request(),
+    )
+    .await
+}
+
+/// A name no other test run uses, so tests can share one database.
+pub fn unique_name(prefix: &str) -> String {
+    format!("{prefix} {0}", uuid::Uuid::new_v4().simple())
+}
+
+/// Asserts that exactly one of two concurrent writes of the same version
+/// succeeded. The other either lost the race to write (`409`) or, when it
+/// read the row after the winner committed, failed its `If-Match` (`412`).
+pub fn assert_one_wins(first: StatusCode, second: StatusCode, pessimistic_locking: bool) {
+    let mut statuses = [first, second];
+    statuses.sort();
+    assert_eq!(
+        statuses[0],
+        StatusCode::OK,
+        "pessimistic_locking = {pessimistic_locking}"
+    );
+    assert!(
+        matches!(
+            statuses[1],
+            StatusCode::CONFLICT | StatusCode::PRECONDITION_FAILED
+        ),
+        "pessimistic_locking = {pessimistic_locking}, loser = {0}",
+        statuses[1]
+    );
+}
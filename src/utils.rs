@@ -1,4 +1,24 @@
-use serde::{Deserialize, Deserializer};
+use crate::{
+    error::{ObjectKind, TrackerError},
+    field::{AllowedValues, Bound, FieldValue},
+    API_PREFIX,
+};
+use actix_web::{
+    body::BoxBody,
+    http::header::{
+        self, ETag, EntityTag, Header, HttpDate, IfMatch, IfModifiedSince, IfNoneMatch,
+        LastModified,
+    },
+    HttpRequest, HttpResponse, Responder,
+};
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+pub const MAX_NAME_LENGTH: usize = 120;
+pub const MAX_NAME_SEARCH_LENGTH: usize = 100;
+pub const MAX_NOTES_SEARCH_LENGTH: usize = 500;
 
 pub fn double_option<'de, T, D>(de: D) -> Result<Option<Option<T>>, D::Error>
 where
@@ -7,3 +27,385 @@ where
 {
     Deserialize::deserialize(de).map(Some)
 }
+
+pub fn comma_separated<'de, D>(de: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(de)?;
+    Ok(value
+        .split(',')
+        .filter(|v| !v.is_empty())
+        .map(str::to_owned)
+        .collect())
+}
+
+/// An unsigned integer that also accepts integer-valued floats (`2.0`) and
+/// whole-number strings (`"2"`), since JSON clients don't always keep the
+/// distinction. Anything else is kept as sent so `check` can reject it as an
+/// invalid value of the named field instead of failing the whole body.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum WholeNumber<T> {
+    Valid(T),
+    Invalid(serde_json::Value),
+}
+
+impl<T: Copy + Default> WholeNumber<T> {
+    pub fn check(&self, name: &str) -> Result<T, TrackerError> {
+        match self {
+            WholeNumber::Valid(value) => Ok(*value),
+            WholeNumber::Invalid(raw) => {
+                let field = match raw {
+                    serde_json::Value::Null => FieldValue::null_value(name),
+                    serde_json::Value::Number(n) => match n.as_i64() {
+                        Some(n) => FieldValue::new(name, n),
+                        None => FieldValue::new(name, n.as_f64().unwrap_or_default()),
+                    },
+                    serde_json::Value::String(s) => FieldValue::new(name, s),
+                    other => FieldValue::new(name, other.to_string()),
+                };
+                Err(TrackerError::invalid_field(field, AllowedValues::integer()))
+            }
+        }
+    }
+
+    /// The coerced value, only meaningful once `check` has passed.
+    pub fn value(&self) -> T {
+        match self {
+            WholeNumber::Valid(value) => *value,
+            WholeNumber::Invalid(_) => T::default(),
+        }
+    }
+}
+
+impl<'de, T: TryFrom<u64>> Deserialize<'de> for WholeNumber<T> {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = serde_json::Value::deserialize(de)?;
+        let whole = match &raw {
+            serde_json::Value::Number(number) => number.as_u64().or_else(|| {
+                number
+                    .as_f64()
+                    .filter(|n| n.fract() == 0.0 && *n >= 0.0 && *n <= u64::MAX as f64)
+                    .map(|n| n as u64)
+            }),
+            serde_json::Value::String(value) => value.trim().parse::<u64>().ok(),
+            _ => None,
+        };
+
+        Ok(match whole.and_then(|n| T::try_from(n).ok()) {
+            Some(value) => WholeNumber::Valid(value),
+            None => WholeNumber::Invalid(raw),
+        })
+    }
+}
+
+pub fn parse_datetime(
+    name: &str,
+    value: Option<String>,
+) -> Result<Option<DateTime<Utc>>, TrackerError> {
+    value
+        .map(|raw| {
+            raw.parse::<DateTime<Utc>>().map_err(|_| {
+                TrackerError::invalid_field(
+                    FieldValue::new(name, raw.as_str()),
+                    AllowedValues::datetime_iso(),
+                )
+            })
+        })
+        .transpose()
+}
+
+pub fn entity_tag(version: i32) -> EntityTag {
+    EntityTag::new_strong(version.to_string())
+}
+
+/// The outcome of a handler, so the status code follows from what the handler
+/// did rather than from how it built its response.
+pub enum ApiResponse<T> {
+    /// `200 OK` with a JSON body
+    Ok(T),
+    /// `201 Created` with a `Location` header relative to the API prefix and,
+    /// when versioned, an `ETag`
+    Created {
+        path: String,
+        version: Option<i32>,
+        body: T,
+    },
+    /// `204 No Content`
+    NoContent,
+}
+
+impl<T> ApiResponse<T> {
+    pub fn created(path: String, body: T) -> Self {
+        Self::Created {
+            path,
+            version: None,
+            body,
+        }
+    }
+
+    pub fn created_versioned(path: String, version: i32, body: T) -> Self {
+        Self::Created {
+            path,
+            version: Some(version),
+            body,
+        }
+    }
+}
+
+impl<T: Serialize> Responder for ApiResponse<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse<Self::Body> {
+        match self {
+            Self::Ok(body) => HttpResponse::Ok().json(body),
+            Self::Created {
+                path,
+                version,
+                body,
+            } => {
+                let mut builder = HttpResponse::Created();
+                builder.insert_header((header::LOCATION, format!("{0}{1}", API_PREFIX, path)));
+                if let Some(version) = version {
+                    builder.insert_header(ETag(entity_tag(version)));
+                }
+                builder.json(body)
+            }
+            Self::NoContent => HttpResponse::NoContent().finish(),
+        }
+    }
+}
+
+/// Requires `If-Match` to name the stored version. The `version` in the body
+/// must be one that `If-Match` names, or any with `*`, so a stale update is
+/// always a `412` reporting both the attempted and the stored version.
+pub fn check_if_match(
+    req: &HttpRequest,
+    object: ObjectKind,
+    id: Uuid,
+    current_version: i32,
+    version: i32,
+) -> Result<(), TrackerError> {
+    if !req.headers().contains_key(header::IF_MATCH) {
+        return Err(TrackerError::PreconditionRequired(object));
+    }
+
+    let precondition_failed = || {
+        TrackerError::precondition_failed(
+            object,
+            [
+                FieldValue::new("id", id),
+                FieldValue::new("version", version),
+                FieldValue::new("current_version", current_version),
+            ],
+        )
+    };
+
+    match IfMatch::parse(req) {
+        Ok(IfMatch::Any) => {}
+        Ok(IfMatch::Items(tags)) => {
+            if !tags.iter().any(|tag| tag.strong_eq(&entity_tag(version))) {
+                return Err(TrackerError::invalid_field(
+                    FieldValue::new("version", version),
+                    AllowedValues::choice(
+                        tags.iter().filter_map(|tag| tag.tag().parse::<i32>().ok()),
+                    ),
+                ));
+            }
+        }
+        Err(_) => return Err(precondition_failed()),
+    }
+
+    if version == current_version {
+        Ok(())
+    } else {
+        Err(precondition_failed())
+    }
+}
+
+/// HTTP dates only have second precision, so the sub-second part is dropped
+/// to let an echoed `Last-Modified` compare equal in `If-Modified-Since`.
+pub fn last_modified(created_at: DateTime<Utc>, updated_at: Option<DateTime<Utc>>) -> HttpDate {
+    HttpDate::from(SystemTime::from(
+        updated_at.unwrap_or(created_at).trunc_subsecs(0),
+    ))
+}
+
+/// `If-Modified-Since` is only consulted when `If-None-Match` is absent.
+pub fn not_modified(
+    req: &HttpRequest,
+    version: i32,
+    last_modified: HttpDate,
+) -> Option<HttpResponse> {
+    let matches = if req.headers().contains_key(header::IF_NONE_MATCH) {
+        match IfNoneMatch::parse(req) {
+            Ok(IfNoneMatch::Any) => true,
+            Ok(IfNoneMatch::Items(tags)) => {
+                tags.iter().any(|tag| tag.weak_eq(&entity_tag(version)))
+            }
+            Err(_) => false,
+        }
+    } else {
+        IfModifiedSince::parse(req).is_ok_and(|IfModifiedSince(since)| last_modified <= since)
+    };
+
+    matches.then(|| {
+        HttpResponse::NotModified()
+            .insert_header(ETag(entity_tag(version)))
+            .insert_header(LastModified(last_modified))
+            .finish()
+    })
+}
+
+pub fn normalize_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub fn validate_name(field: &str, name: &str) -> Result<(), TrackerError> {
+    let normalized = normalize_name(name);
+    if normalized.is_empty() {
+        return Err(TrackerError::missing_field(
+            field,
+            AllowedValues::string_len_min(1),
+        ));
+    }
+
+    if normalized.chars().count() > MAX_NAME_LENGTH {
+        return Err(TrackerError::invalid_field(
+            FieldValue::new(field, name),
+            AllowedValues::string_len_between(1, MAX_NAME_LENGTH),
+        ));
+    }
+    Ok(())
+}
+
+/// Rejects search terms longer than `max_length` characters.
+pub fn validate_search_term(
+    field: &str,
+    term: &str,
+    max_length: usize,
+) -> Result<(), TrackerError> {
+    if term.chars().count() > max_length {
+        return Err(TrackerError::invalid_field(
+            FieldValue::new(field, term),
+            AllowedValues::string_len_max(max_length),
+        ));
+    }
+    Ok(())
+}
+
+/// Rejects NaN, infinities, zero and negative values for numeric inputs
+/// that only make sense as strictly positive quantities.
+pub fn validate_finite_positive(name: &str, value: f32) -> Result<(), TrackerError> {
+    if !value.is_finite() || value <= 0.0 {
+        return Err(TrackerError::invalid_field(
+            FieldValue::new(name, value),
+            AllowedValues::float_min(Bound::exclusive(0.0)),
+        ));
+    }
+    Ok(())
+}
+
+/// Rejects zero and values beyond what a SMALLINT column can hold for
+/// counts that are stored as `i16` but exposed as `u16`.
+pub fn validate_small_count(name: &str, value: u16) -> Result<(), TrackerError> {
+    if value == 0 || value > i16::MAX as u16 {
+        return Err(TrackerError::invalid_field(
+            FieldValue::new(name, value),
+            AllowedValues::integer_between(Bound::inclusive(1), Bound::inclusive(i16::MAX)),
+        ));
+    }
+    Ok(())
+}
+
+pub fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_invalid_field(result: Result<(), TrackerError>, field: &str) {
+        match result {
+            Err(TrackerError::InvalidFieldValue(value, _)) => assert_eq!(value.name, field),
+            other => panic!("expected an invalid `{field}`, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn search_term_at_max_length_is_accepted() {
+        let term = "a".repeat(MAX_NAME_SEARCH_LENGTH);
+        assert!(validate_search_term("name", &term, MAX_NAME_SEARCH_LENGTH).is_ok());
+    }
+
+    #[test]
+    fn oversized_search_term_is_rejected() {
+        let term = "a".repeat(MAX_NAME_SEARCH_LENGTH + 1);
+        assert_invalid_field(
+            validate_search_term("name", &term, MAX_NAME_SEARCH_LENGTH),
+            "name",
+        );
+    }
+
+    #[test]
+    fn search_term_length_counts_characters() {
+        let term = "é".repeat(MAX_NAME_SEARCH_LENGTH);
+        assert!(validate_search_term("name", &term, MAX_NAME_SEARCH_LENGTH).is_ok());
+    }
+
+    fn whole_number(value: serde_json::Value) -> Result<u32, TrackerError> {
+        serde_json::from_value::<WholeNumber<u32>>(value)
+            .expect("any JSON value should deserialize")
+            .check("mining_speed")
+    }
+
+    #[test]
+    fn whole_numbers_accept_integers_integral_floats_and_strings() {
+        assert_eq!(whole_number(serde_json::json!(2)).unwrap(), 2);
+        assert_eq!(whole_number(serde_json::json!(2.0)).unwrap(), 2);
+        assert_eq!(whole_number(serde_json::json!("2")).unwrap(), 2);
+    }
+
+    #[test]
+    fn fractional_whole_number_is_rejected() {
+        match whole_number(serde_json::json!(2.5)) {
+            Err(TrackerError::InvalidFieldValue(field, _)) => {
+                assert_eq!(field.name, "mining_speed")
+            }
+            other => panic!("expected an invalid mining_speed, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn negative_and_out_of_range_whole_numbers_are_rejected() {
+        assert!(whole_number(serde_json::json!(-2)).is_err());
+        assert!(whole_number(serde_json::json!(u64::from(u32::MAX) + 1)).is_err());
+        assert!(whole_number(serde_json::json!("2.5")).is_err());
+    }
+
+    #[test]
+    fn non_finite_and_non_positive_values_are_rejected() {
+        for value in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, 0.0, -0.0, -1.0] {
+            assert_invalid_field(validate_finite_positive("radius", value), "radius");
+        }
+    }
+
+    #[test]
+    fn positive_finite_values_are_accepted() {
+        for value in [f32::MIN_POSITIVE, 1.0, f32::MAX] {
+            assert!(validate_finite_positive("radius", value).is_ok(), "{value}");
+        }
+    }
+}